@@ -0,0 +1,122 @@
+use std::{collections::BTreeMap, fmt};
+
+use bollard::auth::DockerCredentials;
+
+use crate::image::ImageReference;
+
+/// Registry host, used for images without explicit registry.
+const DOCKER_HUB: &str = "docker.io";
+
+/// Credentials for a single image registry.
+#[derive(Clone, Default)]
+pub struct RegistryCredentials {
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub token: Option<String>,
+}
+
+/// Credentials are never printed, to avoid leaking them into logs.
+impl fmt::Debug for RegistryCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mask = |value: &Option<String>| value.as_ref().map(|_| "***");
+
+        f.debug_struct("RegistryCredentials")
+            .field("username", &self.username)
+            .field("password", &mask(&self.password))
+            .field("token", &mask(&self.token))
+            .finish()
+    }
+}
+
+/// Credentials of private registries, keyed by registry host.
+#[derive(Debug, Clone, Default)]
+pub struct Registries {
+    credentials: BTreeMap<String, RegistryCredentials>,
+}
+
+impl Registries {
+    pub fn new(credentials: BTreeMap<String, RegistryCredentials>) -> Self {
+        Self {
+            credentials: credentials
+                .into_iter()
+                .map(|(host, credentials)| (host.to_lowercase(), credentials))
+                .collect(),
+        }
+    }
+
+    /// Credentials to pull `image` with, or `None` for public images.
+    pub fn for_image(&self, image: &str) -> Option<DockerCredentials> {
+        let reference = image.parse::<ImageReference>().ok()?;
+        let host = reference.registry().unwrap_or(DOCKER_HUB);
+        let credentials = self.credentials.get(host)?;
+
+        Some(DockerCredentials {
+            username: credentials.username.clone(),
+            password: credentials.password.clone(),
+            registrytoken: credentials.token.clone(),
+            serveraddress: Some(host.to_owned()),
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bollard::{API_DEFAULT_VERSION, Docker};
+
+    use super::*;
+    use crate::{
+        docker::{RetryPolicy, pull_image},
+        testing::MockServer,
+    };
+
+    fn registries() -> Registries {
+        Registries::new(BTreeMap::from([(
+            "GHCR.io".to_owned(),
+            RegistryCredentials {
+                username: Some("ci".to_owned()),
+                password: Some("hunter2".to_owned()),
+                token: None,
+            },
+        )]))
+    }
+
+    #[test]
+    fn credentials_are_picked_by_registry_host() {
+        let credentials = registries().for_image("ghcr.io/org/private:1").unwrap();
+        assert_eq!(credentials.serveraddress.as_deref(), Some("ghcr.io"));
+        assert_eq!(credentials.username.as_deref(), Some("ci"));
+
+        assert!(registries().for_image("org/public:1").is_none());
+    }
+
+    #[test]
+    fn secrets_are_masked() {
+        let output = format!("{:?}", registries());
+        assert!(output.contains("***"));
+        assert!(!output.contains("hunter2"));
+    }
+
+    #[tokio::test]
+    async fn auth_header_is_sent_for_private_image_only() {
+        let server = MockServer::start(vec![(200, r#"{"status":"done"}"#)]).await;
+        let docker = Docker::connect_with_http(&server.url(""), 5, API_DEFAULT_VERSION).unwrap();
+        let retry = RetryPolicy::no_retries();
+
+        pull_image(
+            &docker,
+            retry,
+            "ghcr.io/org/private:1",
+            &registries(),
+            &|_| {},
+        )
+        .await
+        .unwrap();
+        pull_image(&docker, retry, "org/public:1", &registries(), &|_| {})
+            .await
+            .unwrap();
+
+        assert!(server.request(0).contains("x-registry-auth:"));
+        assert!(!server.request(1).contains("x-registry-auth:"));
+    }
+}
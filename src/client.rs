@@ -1,14 +1,184 @@
+use std::{borrow::Cow, net::IpAddr};
+
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
 use serde::{
     Deserialize, Serialize,
     de::{self, Visitor},
 };
-use strum::{Display, EnumString};
+use strum::{Display, EnumString, VariantNames};
+
+use crate::{
+    client::{ream::Ream, zeam::Zeam},
+    config::ChainTiming,
+};
 
 mod ream;
+mod zeam;
+
+/// Settings of a single node, which client needs to know about to run.
+#[derive(Debug, Clone)]
+pub struct NodeContext<'a> {
+    pub name: &'a str,
+
+    /// Data directory, as seen by the client. `None` leaves client default.
+    pub data_dir: Option<&'a str>,
+
+    /// Directory with validator keys of the node, as seen by the client.
+    pub keys_dir: Option<&'a str>,
+
+    /// Ports, client should listen on.
+    pub ports: NodePorts,
+
+    /// Addresses of other nodes, this node should connect to.
+    pub peers: &'a [String],
+
+    /// URL of a beacon node to checkpoint sync from, if any.
+    pub checkpoint_sync_url: Option<&'a str>,
+
+    /// Log verbosity, `None` leaves client default.
+    pub log_level: Option<LogLevel>,
+
+    /// File with JWT secret, as seen by the client.
+    pub jwt_path: Option<&'a str>,
+}
 
-pub trait Client {}
+/// Log verbosity of the client, translated by each client into its own flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString, VariantNames)]
+#[strum(serialize_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
 
-#[derive(Debug, Clone, Display, EnumString)]
+/// Node settings, which only some clients pass on, see
+/// [`Client::supported_settings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum NodeSetting {
+    LogLevel,
+    CheckpointSyncUrl,
+}
+
+/// Ports of a single node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodePorts {
+    pub p2p: u16,
+    pub rpc: u16,
+    pub metrics: u16,
+}
+
+/// Config file, rendered for a single node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigFile {
+    /// File name, under which config is written and mounted.
+    pub file_name: &'static str,
+    pub contents: String,
+}
+
+pub trait Client {
+    /// Command-line arguments, which configure slot and epoch timing.
+    ///
+    /// Clients, which read timing from the chain config, don't need any extra
+    /// flags, so this returns nothing by default.
+    fn timing_args(&self, _timing: &ChainTiming) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Flags, which are generated by the tool itself (ports, peers, data
+    /// directory, log level), and so must not be passed via `extra_args`.
+    fn managed_flags(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Ports, which client listens on, when not told otherwise.
+    ///
+    /// Containers always listen on these, and only host side of the port
+    /// mapping changes, so these are also used as base of host ports, when
+    /// config doesn't set them.
+    fn default_ports(&self) -> NodePorts {
+        NodePorts {
+            p2p: 9000,
+            rpc: 5052,
+            metrics: 8080,
+        }
+    }
+
+    /// Command-line arguments, which pass node settings (data directory,
+    /// ports, keys and peers) to the client. These are the
+    /// [`managed_flags`](Client::managed_flags).
+    ///
+    /// Clients without dedicated implementation don't know any flags, so
+    /// they run with `extra_args` only.
+    fn build_args(&self, _node: &NodeContext) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Optional settings of [`NodeContext`], which
+    /// [`build_args`](Client::build_args) or
+    /// [`config_file`](Client::config_file) pass to the client. Others are
+    /// dropped, which config check warns about.
+    fn supported_settings(&self) -> &'static [NodeSetting] {
+        &[]
+    }
+
+    /// Per-node config file, for clients which prefer it over command-line
+    /// flags.
+    ///
+    /// When this returns a file, it is written next to node keys, mounted
+    /// into the container, and client is invoked with `--config <path>`
+    /// instead of [`managed_flags`](Client::managed_flags). Clients configured
+    /// by flags return `None`, which is the default.
+    fn config_file(&self, _node: &NodeContext) -> Option<ConfigFile> {
+        None
+    }
+
+    /// Multiaddr of the client, listening for peers on `host` and `port`,
+    /// where `host` is either IP address or domain name.
+    ///
+    /// Lean clients use QUIC transport by default.
+    fn p2p_address(&self, host: &str, port: u16) -> String {
+        let protocol = match host.parse::<IpAddr>() {
+            Ok(IpAddr::V4(_)) => "ip4",
+            Ok(IpAddr::V6(_)) => "ip6",
+            Err(_) => "dns4",
+        };
+
+        format!("/{protocol}/{host}/udp/{port}/quic-v1")
+    }
+
+    /// Number of validators per node, used when config doesn't specify
+    /// `validator_count`.
+    fn default_validator_count(&self) -> u64 {
+        1
+    }
+
+    /// Path of the REST endpoint, which responds with 200 once node is healthy.
+    ///
+    /// Clients without such endpoint return `None`, in which case health is
+    /// derived from the container state.
+    fn health_path(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Path of the REST endpoint, reporting number of connected peers in
+    /// beacon API format (`{ "data": { "connected": "<count>" } }`).
+    fn peer_count_path(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Path of the Prometheus endpoint on metrics port.
+    ///
+    /// Clients, which don't expose metrics, return `None`, and are left out
+    /// of the scrape config.
+    fn metrics_path(&self) -> Option<&'static str> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Display, EnumString, VariantNames)]
 #[strum(serialize_all = "snake_case")]
 pub enum ClientKind {
     Ream,
@@ -20,6 +190,118 @@ pub enum ClientKind {
     Ethrex,
 }
 
+/// Client without any specific behaviour, for kinds which don't have
+/// dedicated implementation yet.
+struct Generic;
+
+impl Client for Generic {}
+
+impl ClientKind {
+    pub fn instantiate(&self) -> Box<dyn Client> {
+        match self {
+            Self::Ream => Box::new(Ream {}),
+            Self::Zeam => Box::new(Zeam {}),
+            Self::Qlean | Self::Lantern | Self::Lighthouse | Self::Grandine | Self::Ethrex => {
+                Box::new(Generic)
+            }
+        }
+    }
+
+    /// Image, used for nodes which don't specify one.
+    pub fn default_image(&self) -> &'static str {
+        match self {
+            Self::Ream => "ghcr.io/reamlabs/ream:latest",
+            Self::Zeam => "blockblaz/zeam:latest",
+            Self::Qlean => "qdrvm/qlean-mini:latest",
+            Self::Lantern => "piertwo/lantern:latest",
+            Self::Lighthouse => "hopinheimer/lighthouse:latest",
+            Self::Grandine => "sifrai/lean:latest",
+            Self::Ethrex => "ghcr.io/lambdaclass/ethrex:latest",
+        }
+    }
+
+    /// Note for unknown client `name`: the closest known identifier, if it
+    /// looks like a typo, or all of them otherwise.
+    pub fn suggest(name: &str) -> String {
+        // Allow roughly one typo per three characters, but at least two, so
+        // that swapped letters are still caught.
+        let threshold = (name.len() / 3).max(2);
+
+        let closest = Self::VARIANTS
+            .iter()
+            .map(|variant| (levenshtein(&name.to_lowercase(), variant), variant))
+            .min_by_key(|(distance, _)| *distance)
+            .filter(|(distance, _)| *distance <= threshold);
+
+        match closest {
+            Some((_, variant)) => format!("did you mean `{variant}`?"),
+            None => format!("valid clients are: {}", Self::VARIANTS.join(", ")),
+        }
+    }
+
+    /// Participant client type, as named in Kurtosis network params.
+    pub fn kurtosis_name(&self) -> &'static str {
+        match self {
+            Self::Ream => "ream",
+            Self::Zeam => "zeam",
+            Self::Qlean => "qlean",
+            Self::Lantern => "lantern",
+            Self::Lighthouse => "lighthouse",
+            Self::Grandine => "grandine",
+            Self::Ethrex => "ethrex",
+        }
+    }
+}
+
+/// Start of the error message for unrecognized client identifier.
+const UNKNOWN_CLIENT: &str = "unknown client";
+
+/// Note for toml parse error `message`, if it is about unknown client.
+///
+/// Parse errors carry only a message, so the client name is recovered from it.
+pub fn unknown_client_note(message: &str) -> Option<String> {
+    let name = message
+        .strip_prefix(UNKNOWN_CLIENT)?
+        .trim_start()
+        .strip_prefix('`')?
+        .strip_suffix('`')?;
+
+    Some(ClientKind::suggest(name))
+}
+
+/// Number of single character edits, needed to turn `a` into `b`.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+impl JsonSchema for ClientKind {
+    fn schema_name() -> Cow<'static, str> {
+        "ClientKind".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "Lean client identifier.",
+            "type": "string",
+            "enum": Self::VARIANTS,
+        })
+    }
+}
+
 impl Serialize for ClientKind {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -47,7 +329,8 @@ impl<'de> Deserialize<'de> for ClientKind {
             where
                 E: de::Error,
             {
-                v.parse().map_err(de::Error::custom)
+                v.parse()
+                    .map_err(|_| de::Error::custom(format!("{UNKNOWN_CLIENT} `{v}`")))
             }
 
             fn visit_string<E>(self, v: String) -> Result<Self::Value, E>
@@ -61,3 +344,54 @@ impl<'de> Deserialize<'de> for ClientKind {
         deserializer.deserialize_str(Vis)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::NetworkConfig;
+
+    /// Parse error of network with single node of `client`, together with
+    /// source it was parsed from.
+    fn parse_error(client: &str) -> (String, toml::de::Error) {
+        let source = format!("name = \"devnet\"\n\n[[node]]\nclient = \"{client}\"\n");
+        let error = toml::from_str::<NetworkConfig>(&source).unwrap_err();
+
+        (source, error)
+    }
+
+    #[test]
+    fn near_miss_suggests_closest_client() {
+        let (source, error) = parse_error("lighhouse");
+
+        assert_eq!(&source[error.span().unwrap()], "\"lighhouse\"");
+        assert_eq!(
+            unknown_client_note(error.message()).as_deref(),
+            Some("did you mean `lighthouse`?")
+        );
+    }
+
+    #[test]
+    fn unrelated_name_lists_all_clients() {
+        let (_, error) = parse_error("geth");
+
+        assert_eq!(
+            unknown_client_note(error.message()),
+            Some(format!(
+                "valid clients are: {}",
+                ClientKind::VARIANTS.join(", ")
+            ))
+        );
+    }
+
+    #[test]
+    fn every_client_has_default_image() {
+        for name in ClientKind::VARIANTS {
+            let kind = name.parse::<ClientKind>().unwrap();
+
+            assert!(
+                !kind.default_image().is_empty(),
+                "`{name}` has no default image"
+            );
+        }
+    }
+}
@@ -1,5 +1,246 @@
-use crate::client::Client;
+use serde::Serialize;
+
+use crate::{
+    client::{Client, ConfigFile, NodeContext, NodeSetting},
+    config::ChainTiming,
+};
 
 pub struct Ream {}
 
-impl Client for Ream {}
+/// Node settings in `ream.yaml`, keyed by the same names as the flags.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct ReamConfig<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data_dir: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keys_dir: Option<&'a str>,
+    socket_port: u16,
+    http_port: u16,
+    metrics_port: u16,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    bootnodes: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    log_level: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    checkpoint_sync_url: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    jwt_secret: Option<&'a str>,
+}
+
+impl Client for Ream {
+    fn timing_args(&self, timing: &ChainTiming) -> Vec<String> {
+        vec![
+            "--seconds-per-slot".to_owned(),
+            timing.seconds_per_slot.to_string(),
+            "--slots-per-epoch".to_owned(),
+            timing.slots_per_epoch.to_string(),
+        ]
+    }
+
+    fn supported_settings(&self) -> &'static [NodeSetting] {
+        &[NodeSetting::LogLevel, NodeSetting::CheckpointSyncUrl]
+    }
+
+    fn managed_flags(&self) -> &'static [&'static str] {
+        &[
+            "--data-dir",
+            "--keys-dir",
+            "--http-port",
+            "--socket-port",
+            "--metrics-port",
+            "--bootnodes",
+            "--checkpoint-sync-url",
+            "--log-level",
+            "--jwt-secret",
+            "--seconds-per-slot",
+            "--slots-per-epoch",
+        ]
+    }
+
+    fn build_args(&self, node: &NodeContext) -> Vec<String> {
+        let mut args = Vec::new();
+
+        if let Some(dir) = node.data_dir {
+            args.extend(["--data-dir".to_owned(), dir.to_owned()]);
+        }
+        if let Some(dir) = node.keys_dir {
+            args.extend(["--keys-dir".to_owned(), dir.to_owned()]);
+        }
+        args.extend([
+            "--socket-port".to_owned(),
+            node.ports.p2p.to_string(),
+            "--http-port".to_owned(),
+            node.ports.rpc.to_string(),
+            "--metrics-port".to_owned(),
+            node.ports.metrics.to_string(),
+        ]);
+        if !node.peers.is_empty() {
+            args.extend(["--bootnodes".to_owned(), node.peers.join(",")]);
+        }
+        if let Some(level) = node.log_level {
+            args.extend(["--log-level".to_owned(), level.to_string()]);
+        }
+        if let Some(url) = node.checkpoint_sync_url {
+            args.extend(["--checkpoint-sync-url".to_owned(), url.to_owned()]);
+        }
+        if let Some(path) = node.jwt_path {
+            args.extend(["--jwt-secret".to_owned(), path.to_owned()]);
+        }
+
+        args
+    }
+
+    fn config_file(&self, node: &NodeContext) -> Option<ConfigFile> {
+        let config = ReamConfig {
+            data_dir: node.data_dir,
+            keys_dir: node.keys_dir,
+            socket_port: node.ports.p2p,
+            http_port: node.ports.rpc,
+            metrics_port: node.ports.metrics,
+            bootnodes: node.peers,
+            log_level: node.log_level.map(|level| level.to_string()),
+            checkpoint_sync_url: node.checkpoint_sync_url,
+            jwt_secret: node.jwt_path,
+        };
+
+        Some(ConfigFile {
+            file_name: "ream.yaml",
+            contents: serde_yaml::to_string(&config).expect("ream config is always serializable"),
+        })
+    }
+
+    fn health_path(&self) -> Option<&'static str> {
+        Some("/eth/v1/node/health")
+    }
+
+    fn peer_count_path(&self) -> Option<&'static str> {
+        Some("/eth/v1/node/peer_count")
+    }
+
+    fn metrics_path(&self) -> Option<&'static str> {
+        Some("/metrics")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{LogLevel, NodePorts};
+
+    #[test]
+    fn timing_is_passed_as_flags() {
+        let timing = ChainTiming {
+            genesis_time: 0,
+            seconds_per_slot: 2,
+            slots_per_epoch: 8,
+        };
+
+        assert_eq!(
+            Ream {}.timing_args(&timing),
+            ["--seconds-per-slot", "2", "--slots-per-epoch", "8"]
+        );
+    }
+
+    #[test]
+    fn node_settings_are_mapped_to_flags() {
+        let peers = ["/dns4/devnet-ream_0/udp/9000/quic-v1".to_owned()];
+        let node = NodeContext {
+            name: "ream_1",
+            data_dir: Some("/data"),
+            keys_dir: Some("/liesas/keys"),
+            ports: NodePorts {
+                p2p: 9001,
+                rpc: 5053,
+                metrics: 8081,
+            },
+            peers: &peers,
+            checkpoint_sync_url: Some("http://checkpoint:5052"),
+            log_level: None,
+            jwt_path: Some("/liesas/keys/jwt.hex"),
+        };
+
+        assert_eq!(
+            Ream {}.build_args(&node),
+            [
+                "--data-dir",
+                "/data",
+                "--keys-dir",
+                "/liesas/keys",
+                "--socket-port",
+                "9001",
+                "--http-port",
+                "5053",
+                "--metrics-port",
+                "8081",
+                "--bootnodes",
+                "/dns4/devnet-ream_0/udp/9000/quic-v1",
+                "--checkpoint-sync-url",
+                "http://checkpoint:5052",
+                "--jwt-secret",
+                "/liesas/keys/jwt.hex",
+            ]
+        );
+    }
+
+    #[test]
+    fn config_file_has_ports_and_peers() {
+        let peers = [
+            "/dns4/devnet-ream_0/udp/9000/quic-v1".to_owned(),
+            "/dns4/devnet-ream_1/udp/9000/quic-v1".to_owned(),
+        ];
+        let node = NodeContext {
+            name: "ream_2",
+            data_dir: None,
+            keys_dir: Some("/liesas/keys"),
+            ports: NodePorts {
+                p2p: 9000,
+                rpc: 5052,
+                metrics: 8080,
+            },
+            peers: &peers,
+            checkpoint_sync_url: None,
+            log_level: None,
+            jwt_path: None,
+        };
+
+        let file = Ream {}.config_file(&node).unwrap();
+        let config: serde_yaml::Value = serde_yaml::from_str(&file.contents).unwrap();
+
+        assert_eq!(file.file_name, "ream.yaml");
+        assert_eq!(config["keys-dir"], "/liesas/keys");
+        assert_eq!(config["socket-port"], 9000);
+        assert_eq!(config["http-port"], 5052);
+        assert_eq!(config["metrics-port"], 8080);
+        assert_eq!(config["bootnodes"], serde_yaml::to_value(&peers).unwrap());
+        assert!(config.get("jwt-secret").is_none());
+    }
+
+    #[test]
+    fn log_level_is_mapped_to_flag() {
+        let node = NodeContext {
+            name: "ream",
+            data_dir: None,
+            keys_dir: None,
+            ports: NodePorts {
+                p2p: 9000,
+                rpc: 5052,
+                metrics: 8080,
+            },
+            peers: &[],
+            checkpoint_sync_url: None,
+            log_level: Some(LogLevel::Debug),
+            jwt_path: None,
+        };
+
+        let args = Ream {}.build_args(&node);
+        assert!(
+            args.windows(2).any(|pair| pair == ["--log-level", "debug"]),
+            "{args:?}"
+        );
+
+        let file = Ream {}.config_file(&node).unwrap();
+        let config: serde_yaml::Value = serde_yaml::from_str(&file.contents).unwrap();
+        assert_eq!(config["log-level"], "debug");
+    }
+}
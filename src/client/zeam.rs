@@ -0,0 +1,11 @@
+use crate::client::Client;
+
+pub struct Zeam {}
+
+impl Client for Zeam {
+    /// Zeam devnets run a few validators on each node, so that a single node
+    /// already exercises aggregation.
+    fn default_validator_count(&self) -> u64 {
+        4
+    }
+}
@@ -1,53 +1,278 @@
-use std::path::PathBuf;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
 
-use ariadne::{Label, ReportKind, Source};
-use color_eyre::owo_colors::OwoColorize;
-use sysexits::ExitCode;
-use toml::de;
+use ariadne::{Cache, Label, ReportKind, Source};
+use color_eyre::owo_colors::{OwoColorize, Stream};
 
-use crate::config::ConfigError;
+use crate::{
+    client::unknown_client_note,
+    config::{ConfigError, ConfigFormat, ConfigWarning, ParseError, Span},
+    exit::Failure,
+    output::{self, Diagnostic},
+};
 
-pub fn report_toml_error(prefix: String, file: PathBuf, source: String, error: de::Error) -> ! {
-    let Some(span) = error.span() else {
+/// Single file of the config.
+#[derive(Debug, Clone)]
+struct SourceFile {
+    path: PathBuf,
+    text: String,
+
+    /// Position of the file start in the shared span space.
+    offset: usize,
+}
+
+/// Files, which the config was read from: the config itself, and files
+/// included by it.
+///
+/// Every file gets its own range of offsets, so that spans from all of them
+/// can be told apart without tracking files separately, and [`locate`]
+/// translates span back to the file and position within it.
+///
+/// [`locate`]: Sources::locate
+#[derive(Debug, Clone)]
+pub struct Sources {
+    files: Vec<SourceFile>,
+}
+
+impl Sources {
+    pub fn new(path: PathBuf, text: String) -> Self {
+        Self {
+            files: vec![SourceFile {
+                path,
+                text,
+                offset: 0,
+            }],
+        }
+    }
+
+    /// Add file, returning offset, at which its spans start.
+    ///
+    /// Files are separated by a single unused offset, so that empty span at
+    /// the end of one file can't be mistaken for start of the next one.
+    pub fn add(&mut self, path: PathBuf, text: String) -> usize {
+        let last = self.files.last().expect("main config is always present");
+        let offset = last.offset + last.text.len() + 1;
+
+        self.files.push(SourceFile { path, text, offset });
+
+        offset
+    }
+
+    /// Path of the main config.
+    pub fn path(&self) -> &Path {
+        &self.files[0].path
+    }
+
+    fn file(&self, span: &Span) -> &SourceFile {
+        self.files
+            .iter()
+            .rev()
+            .find(|file| file.offset <= span.start)
+            .expect("main config starts at zero offset")
+    }
+
+    /// File name and span within that file, for ariadne labels.
+    pub fn locate(&self, span: Span) -> (String, Span) {
+        let file = self.file(&span);
+
+        (
+            file.path.display().to_string(),
+            span.start - file.offset..span.end - file.offset,
+        )
+    }
+
+    /// Attach location of `span` to `diagnostic`.
+    fn at(&self, diagnostic: Diagnostic, span: Span) -> Diagnostic {
+        let file = self.file(&span);
+
+        diagnostic.at(
+            &file.path,
+            &file.text,
+            Some(span.start - file.offset..span.end - file.offset),
+        )
+    }
+
+    fn cache(&self) -> impl Cache<String> {
+        ariadne::sources(
+            self.files
+                .iter()
+                .map(|file| (file.path.display().to_string(), file.text.clone())),
+        )
+    }
+}
+
+pub fn report_parse_error(
+    prefix: String,
+    format: ConfigFormat,
+    file: PathBuf,
+    source: String,
+    error: ParseError,
+) -> ! {
+    if output::is_json() {
+        output::failure(
+            Some(Failure::ConfigParse),
+            format!("{prefix} - failed to parse {format}"),
+            vec![Diagnostic::error(&error.message).at(&file, &source, error.span.clone())],
+        );
+    }
+
+    let Some(span) = error.span else {
         eprintln!(
             "{label} {prefix} - {message}",
-            label = "Error:".red(),
-            message = error.message()
+            label = "Error:".if_supports_color(Stream::Stderr, |text| text.red()),
+            message = error.message
         );
 
-        ExitCode::Config.exit();
+        Failure::ConfigParse.exit();
     };
 
     let file = file.display().to_string();
 
-    let report = ariadne::Report::build(ReportKind::Error, (&file, span.clone()))
-        .with_message(format!("{prefix} - failed to parse toml"))
-        .with_label(Label::new((&file, span)).with_message(error.message()))
-        .finish();
+    let mut report = ariadne::Report::build(ReportKind::Error, (&file, span.clone()))
+        .with_config(output::report_config())
+        .with_message(format!("{prefix} - failed to parse {format}"))
+        .with_label(Label::new((&file, span)).with_message(&error.message));
+
+    if let Some(note) = unknown_client_note(&error.message) {
+        report = report.with_note(note);
+    }
+
+    let report = report.finish();
 
     if report.eprint((&file, Source::from(source))).is_err() {
         eprintln!(
             "{label} {prefix} {message}",
-            label = "Error:".red(),
-            message = error.message()
+            label = "Error:".if_supports_color(Stream::Stderr, |text| text.red()),
+            message = error.message
         );
     }
 
-    ExitCode::Config.exit();
+    Failure::ConfigParse.exit();
+}
+
+pub fn report_config_error(sources: &Sources, error: ConfigError) -> ! {
+    report_config_errors(sources, vec![error]);
 }
 
-pub fn report_config_error(file: PathBuf, source: String, error: ConfigError) -> ! {
-    let report = error.report(file.clone());
+/// Report all errors, found while planning the network, and terminate.
+pub fn report_config_errors(sources: &Sources, mut errors: Vec<ConfigError>) -> ! {
+    // Values from `[defaults]` are shared by many nodes, so the same error
+    // may be found in each of them.
+    let mut seen = HashSet::new();
+    errors.retain(|error| seen.insert((error.to_string(), error.span())));
+
+    if output::is_json() {
+        output::failure(
+            Some(Failure::ConfigSemantic),
+            "invalid configuration".to_owned(),
+            errors
+                .iter()
+                .map(|error| sources.at(Diagnostic::error(error.to_string()), error.span()))
+                .collect(),
+        );
+    }
+
+    for error in &errors {
+        let report = error.report(sources);
+
+        if report.eprint(sources.cache()).is_err() {
+            eprintln!(
+                "{label} Invalid configuration - {error:?}",
+                label = "Error:".if_supports_color(Stream::Stderr, |text| text.red()),
+            );
+        }
+    }
+
+    Failure::ConfigSemantic.exit();
+}
+
+pub fn report_config_warnings(sources: &Sources, warnings: &[ConfigWarning]) {
+    for warning in warnings {
+        if output::is_json() {
+            output::warn(sources.at(Diagnostic::warning(warning.to_string()), warning.span()));
+            continue;
+        }
+
+        let report = warning.report(sources);
+
+        if report.eprint(sources.cache()).is_err() {
+            eprintln!(
+                "{label} {warning:?}",
+                label = "Warning:".if_supports_color(Stream::Stderr, |text| text.yellow())
+            );
+        }
+    }
+}
+
+/// Report image, set in config, which could be found neither locally nor in
+/// its registry, and terminate.
+pub fn report_missing_image(sources: &Sources, node: &str, image: &str, span: Span) -> ! {
+    let message = format!("image `{image}` of `{node}` not found");
+
+    if output::is_json() {
+        output::failure(
+            Some(Failure::Launch),
+            message.clone(),
+            vec![sources.at(Diagnostic::error(message), span)],
+        );
+    }
+
+    let report = ariadne::Report::build(ReportKind::Error, sources.locate(span.clone()))
+        .with_config(output::report_config())
+        .with_message(format!("Image of `{node}` not found"))
+        .with_label(Label::new(sources.locate(span)).with_message(format!(
+            "`{image}` is neither present locally, nor in its registry"
+        )))
+        .with_help(
+            "check image name and tag, private registries also need credentials in `[registry]`",
+        )
+        .finish();
+
+    if report.eprint(sources.cache()).is_err() {
+        eprintln!(
+            "{label} {message}",
+            label = "Error:".if_supports_color(Stream::Stderr, |text| text.red())
+        );
+    }
+
+    Failure::Launch.exit();
+}
+
+/// Report node name, given on the command line, which is not defined in the
+/// network, and terminate.
+pub fn report_unknown_node(node: &str, available: &[&str]) -> ! {
+    let message = format!("node `{node}` is not defined in the network");
+    let note = format!("available nodes: {}", available.join(", "));
+
+    if output::is_json() {
+        output::failure(
+            Some(Failure::Usage),
+            message.clone(),
+            vec![Diagnostic::error(format!("{message}; {note}"))],
+        );
+    }
+
+    let file = "<command line>".to_owned();
+    let source = std::env::args().skip(1).collect::<Vec<_>>().join(" ");
+    let span = source
+        .rfind(node)
+        .map_or(0..0, |start| start..start + node.len());
+
+    let report = ariadne::Report::build(ReportKind::Error, (&file, span.clone()))
+        .with_config(output::report_config())
+        .with_message("Unknown node")
+        .with_label(Label::new((&file, span)).with_message(&message))
+        .with_note(&note)
+        .finish();
 
-    if report
-        .eprint((file.display().to_string(), Source::from(source)))
-        .is_err()
-    {
+    if report.eprint((&file, Source::from(&source))).is_err() {
         eprintln!(
-            "{label} Invalid configuration - {error:?}",
-            label = "Error:".red(),
+            "{label} {message} ({note})",
+            label = "Error:".if_supports_color(Stream::Stderr, |text| text.red())
         );
     }
 
-    ExitCode::Config.exit();
+    Failure::Usage.exit();
 }
@@ -1,29 +1,221 @@
+mod clean;
+mod completions;
+mod compose;
+mod doctor;
+mod export;
+mod genesis;
+mod init;
+mod keys;
+mod logs;
+mod monitoring;
+mod ps;
+mod restart;
+mod scale;
+mod schema;
 mod start;
+mod status;
+mod stop;
+mod validate;
 
-use clap::{Parser, Subcommand};
+use std::{fs, io, path::Path};
 
-use crate::commands::start::StartCommand;
+use clap::{ArgAction, CommandFactory, Parser, Subcommand, error::ErrorKind};
+use color_eyre::{
+    Result,
+    eyre::{Context as _, bail},
+};
 
+use crate::{
+    commands::{
+        clean::CleanCommand, completions::CompletionsCommand, compose::ComposeCommand,
+        doctor::DoctorCommand, export::ExportCommand, genesis::GenesisCommand, init::InitCommand,
+        keys::KeysCommand, logs::LogsCommand, monitoring::MonitoringCommand, ps::PsCommand,
+        restart::RestartCommand, scale::ScaleCommand, schema::SchemaCommand, start::StartCommand,
+        status::StatusCommand, stop::StopCommand, validate::ValidateCommand,
+    },
+    docker::DockerArgs,
+    exit::Failure,
+    output::{self, ColorChoice, OutputMode},
+    validator::create_private_dir,
+};
+
+// Otherwise, docs of flattened arguments would be shown as description of
+// the tool.
 #[derive(Debug, Clone, Parser)]
+#[command(about = None, long_about = None)]
 pub struct Cli {
     #[command(subcommand)]
     command: Option<Command>,
 
     #[command(flatten)]
-    start: Option<StartCommand>,
+    start: StartCommand,
+
+    #[command(flatten)]
+    pub docker: DockerArgs,
+
+    /// Output format. With `json`, only a single JSON document, describing
+    /// the run or the error, is printed.
+    #[arg(long, global = true, value_enum, default_value = "human")]
+    pub output: OutputMode,
+
+    /// When to color diagnostics.
+    #[arg(long, global = true, value_enum, default_value = "auto")]
+    pub color: ColorChoice,
+
+    /// Log what liesas is doing to stderr. Repeat for more detail, e.g.
+    /// `-vv`. Overrides `RUST_LOG`.
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    pub verbose: u8,
 }
 
 #[derive(Debug, Clone, Subcommand)]
 #[command(args_conflicts_with_subcommands = true)]
 pub enum Command {
     Start(StartCommand),
+
+    /// Export network topology into format of other tools.
+    Export(ExportCommand),
+
+    /// Check, whether environment is ready to run networks.
+    Doctor(DoctorCommand),
+
+    /// Change number of running replicas of a node group.
+    Scale(ScaleCommand),
+
+    /// Show output of a node container.
+    Logs(LogsCommand),
+
+    /// Show state of every node of the network.
+    Status(StatusCommand),
+
+    /// List containers of all networks, started by liesas.
+    Ps(PsCommand),
+
+    /// Recreate nodes with current config, keeping their validator keys.
+    Restart(RestartCommand),
+
+    /// Stop and remove containers of the network.
+    Stop(StopCommand),
+
+    /// Remove stopped containers, docker networks and, optionally, validator
+    /// keys, left behind by previous runs.
+    Clean(CleanCommand),
+
+    /// Generate validator keys and genesis of the network, without launching
+    /// it.
+    Genesis(GenesisCommand),
+
+    /// List public keys of validators of the network.
+    Keys(KeysCommand),
+
+    /// Check config for errors, without generating keys or launching
+    /// anything.
+    Validate(ValidateCommand),
+
+    /// Print JSON schema of network config, for editor validation and
+    /// completion.
+    Schema(SchemaCommand),
+
+    /// Write starter network config.
+    Init(InitCommand),
+
+    /// Write `docker-compose.yml`, running the network, instead of launching
+    /// it directly.
+    Compose(ComposeCommand),
+
+    /// Write Prometheus scrape config, with metrics endpoints of all nodes.
+    Monitoring(MonitoringCommand),
+
+    /// Print shell completion script.
+    #[command(hide = true)]
+    Completions(CompletionsCommand),
 }
 
 impl Cli {
     pub fn command(&self) -> Command {
         self.command
             .clone()
-            .or(self.start.clone().map(Command::Start))
-            .expect("clap should automatically handle default command")
+            .unwrap_or_else(|| Command::Start(self.start.clone()))
+    }
+
+    /// Output mode, requested either globally, or by command-specific
+    /// shorthand flag.
+    pub fn output_mode(&self) -> OutputMode {
+        match self.command() {
+            Command::Validate(cmd) if cmd.json => OutputMode::Json,
+            _ => self.output,
+        }
+    }
+}
+
+/// Report invalid combination of flags, which clap can't detect by itself,
+/// e.g. because it depends on the config, and exit with usage error.
+pub fn usage_error(kind: ErrorKind, message: impl std::fmt::Display) -> ! {
+    if output::is_json() {
+        output::failure(Some(Failure::Usage), message.to_string(), Vec::new());
+    }
+
+    let _ = <Cli as CommandFactory>::command()
+        .error(kind, message)
+        .print();
+    Failure::Usage.exit();
+}
+
+/// Create key directory, refusing to mix new keys with existing files, unless
+/// `force` is set.
+pub fn prepare_out_dir(dir: &Path, force: bool) -> Result<()> {
+    match fs::read_dir(dir) {
+        Ok(mut entries) => {
+            if entries.next().is_some() && !force {
+                bail!(
+                    "output directory {dir:?} is not empty, pass `--force` to write keys into it anyway"
+                );
+            }
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => {
+            return Err(err).wrap_err_with(|| format!("failed to read output directory {dir:?}"));
+        }
+    }
+
+    create_private_dir(dir).wrap_err_with(|| format!("failed to create output directory {dir:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_kind(args: &[&str]) -> Option<ErrorKind> {
+        Cli::try_parse_from([&["liesas"], args].concat())
+            .err()
+            .map(|err| err.kind())
+    }
+
+    #[test]
+    fn conflicting_start_flags_are_rejected() {
+        for args in [
+            &["--dry-run", "--detach"][..],
+            &["--dry-run", "--print-command"],
+            &["--no-wait", "--verify-peers"],
+            &["--watch", "--detach"],
+            &["start", "--no-wait", "--timeout", "5m"],
+        ] {
+            assert_eq!(
+                error_kind(args),
+                Some(ErrorKind::ArgumentConflict),
+                "{args:?} should conflict"
+            );
+        }
+
+        assert_eq!(
+            error_kind(&["--force"]),
+            Some(ErrorKind::MissingRequiredArgument)
+        );
+    }
+
+    #[test]
+    fn compatible_start_flags_are_accepted() {
+        assert_eq!(error_kind(&["--dry-run", "--quiet"]), None);
+        assert_eq!(error_kind(&["start", "--out-dir", "keys", "--force"]), None);
     }
 }
@@ -0,0 +1,286 @@
+use std::{collections::HashMap, env, fs, io, path::PathBuf};
+
+use bollard::{Docker, query_parameters::ListContainersOptionsBuilder};
+use clap::Args;
+use color_eyre::{
+    Result,
+    eyre::Context as _,
+    owo_colors::{OwoColorize, Stream},
+};
+use serde_json::json;
+
+use crate::{
+    commands::stop::managed_networks,
+    config::{container_name, docker_network_name, find_config, load_network_config},
+    container::{MANAGED_LABEL, NETWORK_LABEL},
+    docker::{DockerArgs, DockerError, remove_container, remove_network_if_unused},
+    output, process,
+    prompt::ConfirmArgs,
+};
+
+/// Container or local process, left behind by liesas.
+#[derive(Debug, Clone)]
+struct Leftover {
+    name: String,
+    running: bool,
+
+    /// Network and node of local process, `None` for container.
+    process: Option<(String, String)>,
+}
+
+/// Containers of `network`, or of every network, if it is `None`.
+async fn leftover_containers(
+    docker: &Docker,
+    network: Option<&str>,
+) -> Result<Vec<Leftover>, DockerError> {
+    let mut labels = vec![format!("{MANAGED_LABEL}=true")];
+    if let Some(network) = network {
+        labels.push(format!("{NETWORK_LABEL}={network}"));
+    }
+
+    let filters = HashMap::from([("label", labels)]);
+    let containers = docker
+        .list_containers(Some(
+            ListContainersOptionsBuilder::default()
+                .all(true)
+                .filters(&filters)
+                .build(),
+        ))
+        .await
+        .map_err(|err| DockerError::request("listing containers", err))?;
+
+    Ok(containers
+        .iter()
+        .filter_map(|container| {
+            let name = container.names.as_ref()?.first()?;
+
+            Some(Leftover {
+                name: name.trim_start_matches('/').to_owned(),
+                running: container
+                    .state
+                    .is_some_and(|state| state.to_string() == "running"),
+                process: None,
+            })
+        })
+        .collect())
+}
+
+/// Recorded local processes of `network`, or of every network, if it is
+/// `None`.
+fn leftover_processes(network: Option<&str>) -> io::Result<Vec<Leftover>> {
+    process::tracked()?
+        .into_iter()
+        .filter(|(name, _)| network.is_none_or(|network| network == name))
+        .map(|(network, node)| {
+            Ok(Leftover {
+                name: container_name(&network, &node),
+                running: process::is_running(&network, &node)?,
+                process: Some((network, node)),
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct CleanCommand {
+    #[arg(long, conflicts_with = "all")]
+    config: Option<PathBuf>,
+
+    /// Network to clean, required when config defines multiple networks.
+    #[arg(long, conflicts_with = "all")]
+    network: Option<String>,
+
+    /// Clean up after every network, regardless of config.
+    #[arg(long)]
+    all: bool,
+
+    /// Remove validator keys too. With `--all`, keys of every network in the
+    /// default location are removed.
+    #[arg(long)]
+    keys: bool,
+
+    /// Directory with validator keys of the network. Defaults to the one,
+    /// used by `start`.
+    #[arg(
+        long,
+        visible_alias = "output-dir",
+        requires = "keys",
+        conflicts_with = "all"
+    )]
+    out_dir: Option<PathBuf>,
+
+    /// Remove running containers too, instead of skipping them.
+    #[arg(long)]
+    force: bool,
+
+    #[command(flatten)]
+    confirm: ConfirmArgs,
+}
+
+impl CleanCommand {
+    pub async fn run(&self, docker_args: &DockerArgs) -> Result<()> {
+        let network = if self.all {
+            None
+        } else {
+            let config_path = find_config(self.config.as_deref());
+            let (config, _) =
+                load_network_config(&config_path, None, self.network.as_deref()).await?;
+            Some(config.name().to_owned())
+        };
+
+        let key_dir = match (&self.out_dir, &network) {
+            _ if !self.keys => None,
+            (Some(dir), _) => Some(dir.clone()),
+            (None, Some(network)) => Some(env::temp_dir().join("liesas").join(network)),
+            (None, None) => Some(env::temp_dir().join("liesas")),
+        };
+
+        let docker = docker_args.connect().await?;
+        let retry = docker_args.retry_policy();
+
+        let mut leftovers = leftover_containers(&docker, network.as_deref()).await?;
+        leftovers.extend(
+            leftover_processes(network.as_deref()).wrap_err("failed to list local processes")?,
+        );
+        let (running, stopped): (Vec<_>, Vec<_>) = leftovers
+            .into_iter()
+            .partition(|container| container.running);
+
+        let (containers, skipped) = if self.force {
+            ([stopped, running].concat(), Vec::new())
+        } else {
+            (stopped, running)
+        };
+
+        if !output::is_json() {
+            for container in &skipped {
+                eprintln!(
+                    "{} {} is running, pass `--force` to remove it",
+                    "Warning:".if_supports_color(Stream::Stderr, |text| text.yellow()),
+                    container.name
+                );
+            }
+        }
+
+        // Running nodes still read their keys.
+        let key_dir = key_dir.filter(|dir| dir.exists());
+        let key_dir = match key_dir {
+            Some(dir) if !skipped.is_empty() => {
+                if !output::is_json() {
+                    eprintln!(
+                        "{} keys in {} are kept, as some nodes are still running",
+                        "Warning:".if_supports_color(Stream::Stderr, |text| text.yellow()),
+                        dir.display()
+                    );
+                }
+                None
+            }
+            key_dir => key_dir,
+        };
+
+        // Run directories hold logs and data of local processes, so they are
+        // kept while any process of the network still runs.
+        let run_networks = match &network {
+            Some(network) => vec![network.clone()],
+            None => process::run_networks().wrap_err("failed to list local processes")?,
+        };
+        let run_dirs = run_networks
+            .iter()
+            .filter(|name| {
+                !skipped.iter().any(
+                    |leftover| matches!(&leftover.process, Some((network, _)) if network == *name),
+                )
+            })
+            .map(|name| process::run_dir(name))
+            .filter(|dir| dir.exists())
+            .collect::<Vec<_>>();
+
+        // Networks are removed only once no container uses them, so they
+        // don't need confirmation.
+        let mut actions = Vec::new();
+        if !containers.is_empty() {
+            actions.push(format!("{} container(s)", containers.len()));
+        }
+        if !run_dirs.is_empty() {
+            actions.push("logs of local processes".to_owned());
+        }
+        if let Some(dir) = &key_dir {
+            actions.push(format!("keys in {}", dir.display()));
+        }
+
+        if !actions.is_empty()
+            && !self
+                .confirm
+                .confirm(&format!("remove {}", actions.join(" and ")))?
+        {
+            return Ok(());
+        }
+
+        let networks = match &network {
+            Some(network) => vec![docker_network_name(network)],
+            None => managed_networks(&docker).await?,
+        };
+
+        let mut removed = Vec::new();
+        for container in &containers {
+            let was_removed = match &container.process {
+                // Record is removed, even if process has already exited.
+                Some((network, node)) => {
+                    process::terminate(network, node).await?;
+                    true
+                }
+                None => remove_container(&docker, retry, &container.name).await?,
+            };
+
+            if was_removed {
+                if !output::is_json() {
+                    println!("removed {}", container.name);
+                }
+                removed.push(container.name.clone());
+            }
+        }
+
+        let mut removed_networks = Vec::new();
+        for network in networks {
+            if remove_network_if_unused(&docker, retry, &network).await? {
+                if !output::is_json() {
+                    println!("removed network {network}");
+                }
+                removed_networks.push(network);
+            }
+        }
+
+        for dir in &run_dirs {
+            fs::remove_dir_all(dir).wrap_err_with(|| format!("failed to remove {dir:?}"))?;
+        }
+
+        if let Some(dir) = &key_dir {
+            fs::remove_dir_all(dir)
+                .wrap_err_with(|| format!("failed to remove keys in {dir:?}"))?;
+            if !output::is_json() {
+                println!("removed keys in {}", dir.display());
+            }
+        }
+
+        if output::is_json() {
+            output::success(json!({
+                "network": network,
+                "removed": removed,
+                "skipped": skipped.iter().map(|container| &container.name).collect::<Vec<_>>(),
+                "removed_networks": removed_networks,
+                "removed_keys": key_dir,
+            }));
+        } else if removed.is_empty() && removed_networks.is_empty() && key_dir.is_none() {
+            println!("nothing to clean");
+        } else {
+            println!(
+                "removed {} container(s), {} network(s){}",
+                removed.len(),
+                removed_networks.len(),
+                if key_dir.is_some() { " and keys" } else { "" }
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,27 @@
+use std::io;
+
+use clap::{Args, CommandFactory};
+use clap_complete::Shell;
+use color_eyre::Result;
+
+use crate::Cli;
+
+#[derive(Debug, Clone, Args)]
+pub struct CompletionsCommand {
+    /// Shell to generate completions for.
+    #[arg(value_enum)]
+    shell: Shell,
+}
+
+impl CompletionsCommand {
+    /// Write completion script to stdout. It is generated from the CLI
+    /// definition, so it always covers every subcommand and flag.
+    pub fn run(&self) -> Result<()> {
+        let mut command = <Cli as CommandFactory>::command();
+        let name = command.get_name().to_owned();
+
+        clap_complete::generate(self.shell, &mut command, name, &mut io::stdout());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+use std::path::{self, PathBuf};
+
+use clap::Args;
+use color_eyre::{
+    Result,
+    eyre::Context as _,
+    owo_colors::{OwoColorize, Stream},
+};
+use serde_json::json;
+
+use crate::{
+    codespan::{report_config_errors, report_config_warnings},
+    commands::prepare_out_dir,
+    compose::ComposeFile,
+    config::{ResolveOptions, check, find_config, load_network_config, plan},
+    genesis::Genesis,
+    output::{self, Diagnostic},
+    validator::{KeyManifest, KeyStore},
+};
+
+#[derive(Debug, Clone, Args)]
+pub struct ComposeCommand {
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Network to write, required when config defines multiple networks.
+    #[arg(long)]
+    network: Option<String>,
+
+    /// File to write compose file to. Printed to stdout, if omitted.
+    #[arg(long)]
+    out: Option<PathBuf>,
+
+    /// Directory, where generated validator keys are written. Services mount
+    /// keys from here, so it must be kept along with the compose file.
+    #[arg(long, visible_alias = "output-dir", default_value = "keys")]
+    out_dir: PathBuf,
+
+    /// Write keys into `--out-dir`, even if it is not empty.
+    #[arg(long)]
+    force: bool,
+}
+
+impl ComposeCommand {
+    pub async fn run(&self) -> Result<()> {
+        let config_path = find_config(self.config.as_deref());
+        let (config, sources) =
+            load_network_config(&config_path, None, self.network.as_deref()).await?;
+
+        prepare_out_dir(&self.out_dir, self.force)?;
+        // Compose resolves relative bind mounts against directory of the
+        // compose file, which is not necessarily the current one.
+        let key_dir = path::absolute(&self.out_dir)
+            .wrap_err_with(|| format!("failed to resolve output directory {:?}", self.out_dir))?;
+
+        let options = ResolveOptions::new(&config_path, KeyStore::Disk(key_dir.clone()));
+        report_config_warnings(&sources, &check(&config));
+
+        let mut plan = match plan(config, options) {
+            Ok(value) => value,
+            Err(errors) => report_config_errors(&sources, errors),
+        };
+
+        KeyManifest::new(&plan.network)
+            .write(&key_dir)
+            .wrap_err_with(|| format!("failed to write key manifest to {key_dir:?}"))?;
+
+        let genesis = Genesis::new(&plan.network)
+            .and_then(|genesis| genesis.write(&key_dir))
+            .wrap_err_with(|| format!("failed to write genesis to {key_dir:?}"))?;
+        for spec in plan
+            .nodes
+            .iter_mut()
+            .filter_map(|node| node.container.as_mut())
+        {
+            spec.mount_genesis(&genesis);
+        }
+
+        let (compose, skipped) = ComposeFile::new(&plan);
+        for node in &skipped {
+            let message = format!("`{node}` runs local binary, and is left out of compose file");
+
+            if output::is_json() {
+                output::warn(Diagnostic::warning(message));
+            } else {
+                eprintln!(
+                    "{} {message}",
+                    "Warning:".if_supports_color(Stream::Stderr, |text| text.yellow())
+                );
+            }
+        }
+
+        let output = compose
+            .to_yaml()
+            .context("failed to serialize compose file")?;
+
+        match &self.out {
+            Some(path) => {
+                tokio::fs::write(path, output)
+                    .await
+                    .context(format!("failed to write compose file to {path:?}"))?;
+
+                if output::is_json() {
+                    output::success(json!({ "path": path, "keys": key_dir }));
+                }
+            }
+            None if output::is_json() => output::success(json!({ "compose": output })),
+            None => print!("{output}"),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::*;
+    use crate::container::GENESIS_PATH;
+
+    #[tokio::test]
+    async fn services_mount_written_genesis() {
+        let dir = env::temp_dir().join(format!("liesas-compose-genesis-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let config = dir.join("network.toml");
+        fs::write(
+            &config,
+            "name = \"devnet\"\n\n[[node]]\nclient = \"ream\"\n",
+        )
+        .unwrap();
+
+        let command = ComposeCommand {
+            config: Some(config),
+            network: None,
+            out: Some(dir.join("docker-compose.yml")),
+            out_dir: dir.join("keys"),
+            force: false,
+        };
+        command.run().await.unwrap();
+
+        let genesis = dir.join("keys").join(Genesis::FILE_NAME);
+        assert!(genesis.exists());
+        let compose = fs::read_to_string(dir.join("docker-compose.yml")).unwrap();
+        assert!(
+            compose.contains(&format!("{}:{GENESIS_PATH}:ro", genesis.display())),
+            "{compose}"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
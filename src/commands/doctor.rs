@@ -0,0 +1,220 @@
+use std::{fmt, path::PathBuf, thread};
+
+use bollard::Docker;
+use clap::Args;
+use color_eyre::{
+    Report, Result,
+    owo_colors::{OwoColorize, Stream},
+};
+
+use serde_json::json;
+
+use crate::{
+    docker::{DockerArgs, DockerError},
+    exit::Failure,
+    output::{self, Diagnostic},
+};
+
+/// Below this amount of free disk space, keys and node data cannot be written.
+const MIN_DISK_SPACE: u64 = 256 * 1024 * 1024;
+
+/// Below this amount of free disk space, larger networks may run out of space.
+const LOW_DISK_SPACE: u64 = 4 * 1024 * 1024 * 1024;
+
+/// Below this amount of cores, key generation is noticeably slow.
+const LOW_CPU_COUNT: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl Status {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Pass => "pass",
+            Self::Warn => "warn",
+            Self::Fail => "fail",
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pass => write!(
+                f,
+                "{}",
+                format!("[{}]", self.name()).if_supports_color(Stream::Stdout, |text| text.green())
+            ),
+            Self::Warn => write!(
+                f,
+                "{}",
+                format!("[{}]", self.name())
+                    .if_supports_color(Stream::Stdout, |text| text.yellow())
+            ),
+            Self::Fail => write!(
+                f,
+                "{}",
+                format!("[{}]", self.name()).if_supports_color(Stream::Stdout, |text| text.red())
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct DoctorCommand {
+    /// Directory, where keys and node data will be stored.
+    #[arg(long, default_value = ".")]
+    dir: PathBuf,
+}
+
+#[cfg(unix)]
+fn available_space(dir: &PathBuf) -> Option<u64> {
+    let stat = nix::sys::statvfs::statvfs(dir).ok()?;
+
+    #[allow(clippy::unnecessary_cast)]
+    Some(stat.blocks_available() as u64 * stat.fragment_size() as u64)
+}
+
+#[cfg(not(unix))]
+fn available_space(_dir: &PathBuf) -> Option<u64> {
+    None
+}
+
+/// Check that docker daemon, `docker` is connected to, responds.
+async fn check_docker(docker: Result<Docker, DockerError>) -> (Status, String, Failure) {
+    match docker {
+        Ok(client) => match client.version().await {
+            Ok(version) => (
+                Status::Pass,
+                format!(
+                    "docker is reachable (version {}, api {})",
+                    version.version.as_deref().unwrap_or("unknown"),
+                    version.api_version.as_deref().unwrap_or("unknown"),
+                ),
+                Failure::DockerUnavailable,
+            ),
+            Err(err) => (
+                Status::Fail,
+                format!("docker is not reachable: {err}"),
+                Failure::DockerUnavailable,
+            ),
+        },
+        Err(err) => {
+            let class = err.failure();
+            (Status::Fail, format!("{:#}", Report::new(err)), class)
+        }
+    }
+}
+
+impl DoctorCommand {
+    pub async fn run(&self, docker: &DockerArgs) -> Result<()> {
+        let mut failure = None;
+        let mut checks = Vec::new();
+        let mut report = |status: Status, message: String, class: Failure| {
+            if output::is_json() {
+                checks.push(json!({ "status": status.name(), "message": message }));
+            } else {
+                println!("{status} {message}");
+            }
+
+            if status == Status::Fail {
+                failure.get_or_insert(class);
+            }
+        };
+
+        let (status, message, class) = check_docker(docker.connect().await).await;
+        report(status, message, class);
+
+        match available_space(&self.dir) {
+            Some(space) => {
+                let status = match space {
+                    space if space < MIN_DISK_SPACE => Status::Fail,
+                    space if space < LOW_DISK_SPACE => Status::Warn,
+                    _ => Status::Pass,
+                };
+
+                report(
+                    status,
+                    format!(
+                        "{:.1} GiB of disk space available in {:?}",
+                        space as f64 / (1024.0 * 1024.0 * 1024.0),
+                        self.dir
+                    ),
+                    Failure::Launch,
+                );
+            }
+            None => report(
+                Status::Warn,
+                format!("cannot determine available disk space in {:?}", self.dir),
+                Failure::Launch,
+            ),
+        }
+
+        let cpus = thread::available_parallelism().map_or(1, |v| v.get());
+        let status = if cpus < LOW_CPU_COUNT {
+            Status::Warn
+        } else {
+            Status::Pass
+        };
+        report(
+            status,
+            format!("{cpus} cpu cores available for key generation"),
+            Failure::Launch,
+        );
+
+        if output::is_json() {
+            match failure {
+                Some(failure) => output::failure(
+                    Some(failure),
+                    "environment is not ready".to_owned(),
+                    checks
+                        .iter()
+                        .filter(|check| check["status"] == Status::Fail.name())
+                        .map(|check| {
+                            Diagnostic::error(check["message"].as_str().unwrap_or_default())
+                        })
+                        .collect(),
+                ),
+                None => output::success(json!({ "checks": checks })),
+            }
+
+            return Ok(());
+        }
+
+        if let Some(failure) = failure {
+            failure.exit();
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bollard::API_DEFAULT_VERSION;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn unreachable_docker_fails() {
+        let docker = Docker::connect_with_http("http://127.0.0.1:1", 1, API_DEFAULT_VERSION);
+        let (status, message, class) = check_docker(Ok(docker.unwrap())).await;
+
+        assert_eq!(status, Status::Fail);
+        assert!(message.starts_with("docker is not reachable"), "{message}");
+        assert_eq!(class, Failure::DockerUnavailable);
+    }
+
+    #[tokio::test]
+    async fn unsupported_host_fails() {
+        let err = DockerError::UnsupportedHost("ftp://docker".to_owned());
+        let (status, _, class) = check_docker(Err(err)).await;
+
+        assert_eq!(status, Status::Fail);
+        assert_eq!(class, Failure::DockerUnavailable);
+    }
+}
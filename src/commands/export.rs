@@ -0,0 +1,73 @@
+use std::path::PathBuf;
+
+use clap::{Args, ValueEnum};
+use color_eyre::{Result, eyre::Context as _};
+use serde_json::json;
+
+use crate::{
+    codespan::{report_config_errors, report_config_warnings},
+    config::{ResolveOptions, check, find_config, load_network_config, plan},
+    kurtosis::NetworkParams,
+    output,
+    validator::KeyStore,
+};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    /// Kurtosis ethereum-package `network_params.yaml`.
+    Kurtosis,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ExportCommand {
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Network to export, required when config defines multiple networks.
+    #[arg(long)]
+    network: Option<String>,
+
+    #[arg(long, value_enum)]
+    format: ExportFormat,
+
+    /// File to write the export to. Printed to stdout, if omitted.
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
+
+impl ExportCommand {
+    pub async fn run(&self) -> Result<()> {
+        let config_path = find_config(self.config.as_deref());
+        let (config, sources) =
+            load_network_config(&config_path, None, self.network.as_deref()).await?;
+
+        report_config_warnings(&sources, &check(&config));
+
+        let plan = match plan(config, ResolveOptions::new(&config_path, KeyStore::Skip)) {
+            Ok(value) => value,
+            Err(errors) => report_config_errors(&sources, errors),
+        };
+
+        let output = match self.format {
+            ExportFormat::Kurtosis => NetworkParams::new(&plan.network)
+                .to_yaml()
+                .context("failed to serialize kurtosis network params")?,
+        };
+
+        match &self.out {
+            Some(path) => {
+                tokio::fs::write(path, output)
+                    .await
+                    .context(format!("failed to write export to {path:?}"))?;
+
+                if output::is_json() {
+                    output::success(json!({ "path": path }));
+                }
+            }
+            None if output::is_json() => output::success(json!({ "export": output })),
+            None => print!("{output}"),
+        }
+
+        Ok(())
+    }
+}
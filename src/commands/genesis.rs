@@ -0,0 +1,93 @@
+use std::{
+    env,
+    path::{self, PathBuf},
+};
+
+use clap::Args;
+use color_eyre::{Result, eyre::Context as _};
+use serde_json::json;
+
+use crate::{
+    codespan::{report_config_errors, report_config_warnings},
+    commands::prepare_out_dir,
+    config::{ResolveOptions, check, find_config, load_network_config},
+    genesis::Genesis,
+    output,
+    validator::{KeyManifest, KeyStore},
+};
+
+#[derive(Debug, Clone, Args)]
+pub struct GenesisCommand {
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Network to generate genesis of, required when config defines multiple
+    /// networks.
+    #[arg(long)]
+    network: Option<String>,
+
+    /// Directory, where `genesis.json` is written, along with validator keys
+    /// it refers to.
+    #[arg(long, visible_alias = "output-dir")]
+    out_dir: Option<PathBuf>,
+
+    /// Write into `--out-dir`, even if it is not empty.
+    #[arg(long, requires = "out_dir")]
+    force: bool,
+
+    /// Seed of validator key generation, overriding `seed` of the config.
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+impl GenesisCommand {
+    pub async fn run(&self) -> Result<()> {
+        let config_path = find_config(self.config.as_deref());
+        let (config, sources) =
+            load_network_config(&config_path, None, self.network.as_deref()).await?;
+
+        let out_dir = match &self.out_dir {
+            Some(dir) => dir.clone(),
+            None => env::temp_dir().join("liesas").join(config.name()),
+        };
+        let out_dir = path::absolute(&out_dir)
+            .wrap_err_with(|| format!("failed to resolve output directory {out_dir:?}"))?;
+
+        // Genesis is useless without keys it lists, so they are kept too.
+        prepare_out_dir(&out_dir, self.force || self.out_dir.is_none())?;
+
+        report_config_warnings(&sources, &check(&config));
+
+        let options =
+            ResolveOptions::new(&config_path, KeyStore::Disk(out_dir.clone())).with_seed(self.seed);
+        let network = match config.resolve(options) {
+            Ok(value) => value,
+            Err(errors) => report_config_errors(&sources, errors),
+        };
+
+        KeyManifest::new(&network)
+            .write(&out_dir)
+            .wrap_err_with(|| format!("failed to write key manifest to {out_dir:?}"))?;
+
+        let genesis = Genesis::new(&network).wrap_err("failed to read validator public keys")?;
+        let path = genesis
+            .write(&out_dir)
+            .wrap_err_with(|| format!("failed to write genesis to {out_dir:?}"))?;
+
+        if output::is_json() {
+            output::success(json!({
+                "network": network.name(),
+                "path": path,
+                "validators": genesis.validator_count(),
+            }));
+        } else {
+            println!(
+                "wrote genesis of {} validator(s) to {}",
+                genesis.validator_count(),
+                path.display()
+            );
+        }
+
+        Ok(())
+    }
+}
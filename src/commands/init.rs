@@ -0,0 +1,58 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use color_eyre::{
+    Result,
+    eyre::Context as _,
+    owo_colors::{OwoColorize, Stream},
+};
+use serde_json::json;
+
+use crate::{exit::Failure, output};
+
+/// Starter config, with one node per common client.
+const TEMPLATE: &str = include_str!("init.toml");
+
+#[derive(Debug, Clone, Args)]
+pub struct InitCommand {
+    /// Where to write the config.
+    #[arg(long, default_value = "network.toml")]
+    path: PathBuf,
+
+    /// Overwrite config, if it already exists.
+    #[arg(long)]
+    force: bool,
+}
+
+impl InitCommand {
+    pub async fn run(&self) -> Result<()> {
+        if !self.force && tokio::fs::try_exists(&self.path).await.unwrap_or(false) {
+            let message = format!(
+                "{:?} already exists, pass `--force` to overwrite it",
+                self.path
+            );
+
+            if output::is_json() {
+                output::failure(Some(Failure::ConfigSemantic), message, Vec::new());
+            }
+
+            eprintln!(
+                "{} {message}",
+                "Error:".if_supports_color(Stream::Stderr, |text| text.red())
+            );
+            Failure::ConfigSemantic.exit();
+        }
+
+        tokio::fs::write(&self.path, TEMPLATE)
+            .await
+            .wrap_err_with(|| format!("failed to write config to {:?}", self.path))?;
+
+        if output::is_json() {
+            output::success(json!({ "path": self.path }));
+        } else {
+            println!("wrote {}", self.path.display());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,225 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use color_eyre::{
+    Result,
+    eyre::{Context as _, bail},
+};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    codespan::{report_config_errors, report_unknown_node},
+    config::{ResolveOptions, ResolvedNetworkConfig, find_config, load_network_config},
+    output,
+    table::{Cell, Table, stdout_supports_color},
+    validator::{KeyFile, KeyStore, key_path},
+};
+
+/// Public key of a single validator. Private key is deliberately left out.
+#[derive(Debug, Clone, Serialize)]
+struct ValidatorKey {
+    index: usize,
+    node: String,
+
+    /// Hex-encoded public key, with `0x` prefix.
+    public_key: String,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct KeysCommand {
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Network to list keys of, required when config defines multiple
+    /// networks.
+    #[arg(long)]
+    network: Option<String>,
+
+    /// Read keys, previously written by `start` or `genesis` into this
+    /// directory, instead of generating new ones.
+    #[arg(long, visible_alias = "output-dir")]
+    out_dir: Option<PathBuf>,
+
+    /// Seed of validator key generation, overriding `seed` of the config.
+    #[arg(long, conflicts_with = "out_dir")]
+    seed: Option<u64>,
+
+    /// List only validators of this node.
+    #[arg(long)]
+    node: Option<String>,
+
+    /// Print keys as JSON.
+    #[arg(long)]
+    json: bool,
+}
+
+impl KeysCommand {
+    pub async fn run(&self) -> Result<()> {
+        let config_path = find_config(self.config.as_deref());
+        let (config, sources) =
+            load_network_config(&config_path, None, self.network.as_deref()).await?;
+
+        // Existing keys only need node layout, without generation.
+        let key_store = match &self.out_dir {
+            Some(dir) => KeyStore::Planned(dir.clone()),
+            None => KeyStore::Memory,
+        };
+        let options = ResolveOptions::new(&config_path, key_store).with_seed(self.seed);
+        let network = match config.resolve(options) {
+            Ok(value) => value,
+            Err(errors) => report_config_errors(&sources, errors),
+        };
+
+        if let Some(node) = &self.node
+            && !network.nodes().contains_key(node)
+        {
+            let available = network
+                .nodes()
+                .keys()
+                .map(String::as_str)
+                .collect::<Vec<_>>();
+            report_unknown_node(node, &available);
+        }
+
+        let keys = self.keys(&network)?;
+
+        if output::is_json() {
+            output::success(json!({ "network": network.name(), "keys": keys }));
+            return Ok(());
+        }
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&keys)?);
+            return Ok(());
+        }
+
+        let mut table = Table::new(vec!["index", "node", "public key"]);
+        for key in &keys {
+            table.push(vec![
+                Cell::new(key.index),
+                Cell::new(&key.node),
+                Cell::new(&key.public_key),
+            ]);
+        }
+        print!("{}", table.render(stdout_supports_color()));
+
+        Ok(())
+    }
+
+    /// Public keys of selected validators, ordered by global index.
+    fn keys(&self, network: &ResolvedNetworkConfig) -> Result<Vec<ValidatorKey>> {
+        let offset = network.validator_index_offset();
+        let mut keys = Vec::new();
+
+        for (name, node) in network.nodes() {
+            if self.node.as_ref().is_some_and(|node| node != name) {
+                continue;
+            }
+
+            for &index in &node.validators {
+                let public_key = match &self.out_dir {
+                    Some(dir) => {
+                        let path = dir.join(key_path(name, index));
+                        let file = KeyFile::read(&path).wrap_err_with(|| {
+                            format!("failed to read key of validator {index} from {path:?}")
+                        })?;
+                        if file.index != index {
+                            bail!(
+                                "key file {path:?} belongs to validator {}, not {index}, keys in {dir:?} were generated for different config",
+                                file.index
+                            );
+                        }
+
+                        file.public_key
+                    }
+                    None => network.validators()[index - offset]
+                        .keys
+                        .public_key()
+                        .wrap_err_with(|| format!("failed to read key of validator {index}"))?,
+                };
+
+                keys.push(ValidatorKey {
+                    index,
+                    node: name.clone(),
+                    public_key: format!("0x{}", hex::encode(public_key)),
+                });
+            }
+        }
+
+        keys.sort_by_key(|key| key.index);
+        Ok(keys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::config::NetworkConfig;
+
+    fn command(node: Option<&str>) -> KeysCommand {
+        KeysCommand {
+            config: None,
+            network: None,
+            out_dir: None,
+            seed: None,
+            node: node.map(str::to_owned),
+            json: false,
+        }
+    }
+
+    #[test]
+    fn listed_indices_match_node_validators() {
+        let config: NetworkConfig = toml::from_str(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            name = "alpha"
+            client = "ream"
+            validator_count = 2
+            num_active_epochs = 4
+
+            [[node]]
+            name = "beta"
+            client = "zeam"
+            validator_count = 1
+            num_active_epochs = 4
+            "#,
+        )
+        .unwrap();
+        let network = config
+            .resolve(ResolveOptions::new(
+                Path::new("network.toml"),
+                KeyStore::Memory,
+            ))
+            .unwrap();
+
+        let keys = command(None).keys(&network).unwrap();
+        let listed = keys
+            .iter()
+            .map(|key| (key.index, key.node.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(listed, [(0, "alpha"), (1, "alpha"), (2, "beta")]);
+
+        for (name, node) in network.nodes() {
+            let indices = keys
+                .iter()
+                .filter(|key| key.node == *name)
+                .map(|key| key.index)
+                .collect::<Vec<_>>();
+            assert_eq!(indices, node.validators);
+        }
+
+        let beta = command(Some("beta")).keys(&network).unwrap();
+        assert_eq!(beta.len(), 1);
+        assert_eq!(beta[0].index, 2);
+
+        let listed = serde_json::to_value(&beta[0]).unwrap();
+        let mut fields = listed.as_object().unwrap().keys().collect::<Vec<_>>();
+        fields.sort();
+        assert_eq!(fields, ["index", "node", "public_key"]);
+    }
+}
@@ -0,0 +1,284 @@
+use std::{
+    io::Write as _,
+    path::PathBuf,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bollard::{
+    container::LogOutput,
+    query_parameters::{LogsOptions, LogsOptionsBuilder},
+};
+use clap::{Args, error::ErrorKind};
+use color_eyre::{Result, eyre::bail};
+use futures_util::{StreamExt, stream::select_all};
+use serde_json::json;
+
+use crate::{
+    codespan::{report_config_errors, report_unknown_node},
+    commands::usage_error,
+    config::{ResolveOptions, find_config, load_network_config},
+    docker::{DockerArgs, DockerError},
+    output,
+    validator::KeyStore,
+};
+
+/// Point in time, either absolute RFC 3339 timestamp, or duration before now,
+/// e.g. `10m`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogTime {
+    Absolute(SystemTime),
+    Relative(Duration),
+}
+
+impl FromStr for LogTime {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(time) = humantime::parse_rfc3339_weak(s) {
+            return Ok(Self::Absolute(time));
+        }
+
+        humantime::parse_duration(s)
+            .map(Self::Relative)
+            .map_err(|_| format!("expected RFC 3339 timestamp or duration like `10m`, got `{s}`"))
+    }
+}
+
+impl LogTime {
+    /// Unix timestamp in seconds, as expected by docker logs API.
+    fn timestamp(self, now: SystemTime) -> i32 {
+        let time = match self {
+            Self::Absolute(time) => time,
+            Self::Relative(duration) => now.checked_sub(duration).unwrap_or(UNIX_EPOCH),
+        };
+
+        let seconds = time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        i32::try_from(seconds).unwrap_or(i32::MAX)
+    }
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct LogsCommand {
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Network of the node, required when config defines multiple networks.
+    #[arg(long)]
+    network: Option<String>,
+
+    /// Node to show logs of.
+    #[arg(required_unless_present = "group")]
+    node: Option<String>,
+
+    /// Show logs of all nodes of this group, each line prefixed by name of
+    /// the node.
+    #[arg(long, conflicts_with = "node")]
+    group: Option<String>,
+
+    /// Keep streaming new output.
+    #[arg(short, long)]
+    follow: bool,
+
+    /// Number of lines to show from the end of the logs.
+    #[arg(long)]
+    tail: Option<u64>,
+
+    /// Show logs since timestamp (e.g. `2026-01-02T15:04:05Z`) or relative
+    /// duration (e.g. `10m`).
+    #[arg(long)]
+    since: Option<LogTime>,
+
+    /// Show logs before timestamp or relative duration. Ignored with
+    /// `--follow`.
+    #[arg(long)]
+    until: Option<LogTime>,
+}
+
+impl LogsCommand {
+    fn options(&self, now: SystemTime) -> LogsOptions {
+        let mut options = LogsOptionsBuilder::default()
+            .stdout(true)
+            .stderr(true)
+            .follow(self.follow);
+
+        if let Some(tail) = self.tail {
+            options = options.tail(&tail.to_string());
+        }
+
+        if let Some(since) = self.since {
+            options = options.since(since.timestamp(now));
+        }
+
+        // Following logs never ends, so upper bound makes no sense.
+        if let Some(until) = self.until
+            && !self.follow
+        {
+            options = options.until(until.timestamp(now));
+        }
+
+        options.build()
+    }
+
+    pub async fn run(&self, docker_args: &DockerArgs) -> Result<()> {
+        if self.follow && output::is_json() {
+            usage_error(
+                ErrorKind::ArgumentConflict,
+                "`--follow` cannot be used with json output",
+            );
+        }
+
+        let config_path = find_config(self.config.as_deref());
+        let (config, sources) =
+            load_network_config(&config_path, None, self.network.as_deref()).await?;
+
+        let resolved = match config.resolve(ResolveOptions::new(&config_path, KeyStore::Skip)) {
+            Ok(value) => value,
+            Err(errors) => report_config_errors(&sources, errors),
+        };
+
+        let names = match (&self.node, &self.group) {
+            (Some(node), _) => {
+                if !resolved.nodes().contains_key(node) {
+                    let available = resolved
+                        .nodes()
+                        .keys()
+                        .map(String::as_str)
+                        .collect::<Vec<_>>();
+                    report_unknown_node(node, &available);
+                }
+
+                vec![node.clone()]
+            }
+            (None, Some(group)) => {
+                let names = resolved.node_names(Some(group));
+                if names.is_empty() {
+                    bail!("network has no nodes in group `{group}`");
+                }
+
+                names
+            }
+            (None, None) => unreachable!("clap requires either node or group"),
+        };
+
+        let docker = docker_args.connect().await?;
+        let options = self.options(SystemTime::now());
+        let mut stream = select_all(names.iter().map(|name| {
+            docker
+                .logs(&resolved.container_name(name), Some(options.clone()))
+                .map(move |chunk| (name, chunk))
+                .boxed()
+        }));
+
+        let mut lines = Vec::new();
+        while let Some((name, chunk)) = stream.next().await {
+            let chunk = chunk.map_err(|err| DockerError::request("reading logs", err))?;
+
+            if output::is_json() {
+                let stream = match &chunk {
+                    LogOutput::StdErr { .. } => "stderr",
+                    _ => "stdout",
+                };
+                lines.push(json!({
+                    "node": name,
+                    "stream": stream,
+                    "message": chunk.to_string(),
+                }));
+                continue;
+            }
+
+            // Lines of different nodes are interleaved, so they are told apart
+            // by prefix.
+            let prefix = match &self.group {
+                Some(_) => format!("{name} | "),
+                None => String::new(),
+            };
+
+            match chunk {
+                LogOutput::StdErr { message } => {
+                    let mut stderr = std::io::stderr().lock();
+                    stderr.write_all(prefix.as_bytes())?;
+                    stderr.write_all(&message)?;
+                }
+                other => {
+                    let mut stdout = std::io::stdout().lock();
+                    stdout.write_all(prefix.as_bytes())?;
+                    stdout.write_all(&other.into_bytes())?;
+                }
+            }
+        }
+
+        if output::is_json() {
+            output::success(json!({
+                "node": self.node,
+                "group": self.group,
+                "logs": lines,
+            }));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bollard::{API_DEFAULT_VERSION, Docker};
+    use clap::Parser;
+
+    use super::*;
+    use crate::testing::MockServer;
+
+    #[derive(Debug, Parser)]
+    struct Cli {
+        #[command(flatten)]
+        logs: LogsCommand,
+    }
+
+    /// Head of the logs request, made with `args`, 1000 seconds after epoch.
+    async fn logs_request(args: &[&str]) -> String {
+        let command = Cli::try_parse_from([&["logs", "alpha"], args].concat())
+            .unwrap()
+            .logs;
+        let options = command.options(UNIX_EPOCH + Duration::from_secs(1000));
+
+        let server = MockServer::start(vec![(200, "")]).await;
+        let docker = Docker::connect_with_http(&server.url(""), 5, API_DEFAULT_VERSION).unwrap();
+        docker
+            .logs("devnet-alpha", Some(options))
+            .collect::<Vec<_>>()
+            .await;
+
+        server.request(0)
+    }
+
+    #[tokio::test]
+    async fn time_window_is_forwarded() {
+        let request = logs_request(&[
+            "--since",
+            "10m",
+            "--until",
+            "1970-01-01T00:16:00Z",
+            "--tail",
+            "5",
+        ])
+        .await;
+
+        assert!(request.contains("/containers/devnet-alpha/logs?"));
+        assert!(request.contains("since=400"));
+        assert!(request.contains("until=960"));
+        assert!(request.contains("tail=5"));
+    }
+
+    #[tokio::test]
+    async fn follow_ignores_until() {
+        let request = logs_request(&["--since", "1m", "--until", "30s", "--follow"]).await;
+
+        assert!(request.contains("follow=true"));
+        assert!(request.contains("since=940"));
+        // Zero is the default of docker, meaning no upper bound.
+        assert!(request.contains("until=0&"));
+    }
+}
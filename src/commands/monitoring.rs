@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use color_eyre::{Result, eyre::Context as _};
+use serde_json::json;
+
+use crate::{
+    codespan::report_config_errors,
+    config::{ResolveOptions, find_config, load_network_config},
+    output,
+    prometheus::ScrapeConfig,
+    validator::KeyStore,
+};
+
+#[derive(Debug, Clone, Args)]
+pub struct MonitoringCommand {
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Network to monitor, required when config defines multiple networks.
+    #[arg(long)]
+    network: Option<String>,
+
+    /// File to write the scrape config to. Printed to stdout, if omitted.
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
+
+impl MonitoringCommand {
+    pub async fn run(&self) -> Result<()> {
+        let config_path = find_config(self.config.as_deref());
+        let (config, sources) =
+            load_network_config(&config_path, None, self.network.as_deref()).await?;
+
+        // Ports are allocated same way as by `start`, so targets match
+        // running network, as long as config didn't change since.
+        let network = match config.resolve(ResolveOptions::new(&config_path, KeyStore::Skip)) {
+            Ok(value) => value,
+            Err(errors) => report_config_errors(&sources, errors),
+        };
+
+        let scrape_config = ScrapeConfig::new(&network);
+        let contents = scrape_config
+            .to_yaml()
+            .context("failed to serialize scrape config")?;
+
+        match &self.out {
+            Some(path) => {
+                tokio::fs::write(path, contents)
+                    .await
+                    .context(format!("failed to write scrape config to {path:?}"))?;
+
+                if output::is_json() {
+                    output::success(json!({
+                        "path": path,
+                        "targets": scrape_config.target_count(),
+                    }));
+                }
+            }
+            None if output::is_json() => output::success(json!({ "scrape_config": contents })),
+            None => print!("{contents}"),
+        }
+
+        Ok(())
+    }
+}
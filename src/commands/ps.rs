@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use bollard::query_parameters::ListContainersOptionsBuilder;
+use clap::Args;
+use color_eyre::Result;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    commands::status::{published_ports, state_color},
+    container::{CLIENT_LABEL, MANAGED_LABEL, NETWORK_LABEL, NODE_LABEL, VALIDATORS_LABEL},
+    docker::{DockerArgs, DockerError},
+    output,
+    table::{Cell, Table, stdout_supports_color},
+};
+
+/// Container, created by liesas for any network.
+#[derive(Debug, Clone, Serialize)]
+struct ManagedContainer {
+    name: String,
+    network: Option<String>,
+    node: Option<String>,
+    client: Option<String>,
+
+    /// Number of validators, handled by the node.
+    validators: Option<usize>,
+
+    /// Container state, e.g. `running` or `exited`.
+    state: String,
+
+    /// Published ports, as `host_ip:host_port->container_port`.
+    ports: Vec<String>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct PsCommand {
+    /// Print containers as JSON.
+    #[arg(long)]
+    json: bool,
+}
+
+impl PsCommand {
+    pub async fn run(&self, docker_args: &DockerArgs) -> Result<()> {
+        let docker = docker_args.connect().await?;
+
+        let filters = HashMap::from([("label", vec![format!("{MANAGED_LABEL}=true")])]);
+        let summaries = docker
+            .list_containers(Some(
+                ListContainersOptionsBuilder::default()
+                    .all(true)
+                    .filters(&filters)
+                    .build(),
+            ))
+            .await
+            .map_err(|err| DockerError::request("listing containers", err))?;
+
+        let mut containers = summaries
+            .iter()
+            .map(|container| {
+                let label = |key: &str| container.labels.as_ref()?.get(key).cloned();
+
+                ManagedContainer {
+                    name: container
+                        .names
+                        .iter()
+                        .flatten()
+                        .next()
+                        .map(|name| name.trim_start_matches('/').to_owned())
+                        .unwrap_or_default(),
+                    network: label(NETWORK_LABEL),
+                    node: label(NODE_LABEL),
+                    client: label(CLIENT_LABEL),
+                    validators: label(VALIDATORS_LABEL).and_then(|count| count.parse().ok()),
+                    state: container
+                        .state
+                        .map_or_else(|| "unknown".to_owned(), |state| state.to_string()),
+                    ports: published_ports(container),
+                }
+            })
+            .collect::<Vec<_>>();
+        containers.sort_by(|a, b| (&a.network, &a.name).cmp(&(&b.network, &b.name)));
+
+        if output::is_json() {
+            output::success(json!({ "containers": containers }));
+            return Ok(());
+        }
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&containers)?);
+            return Ok(());
+        }
+
+        let mut table = Table::new(vec![
+            "name",
+            "network",
+            "client",
+            "validators",
+            "state",
+            "ports",
+        ]);
+        for container in &containers {
+            let ports = if container.ports.is_empty() {
+                "-".to_owned()
+            } else {
+                container.ports.join(", ")
+            };
+
+            table.push(vec![
+                Cell::new(&container.name),
+                Cell::new(container.network.as_deref().unwrap_or("-")),
+                Cell::new(container.client.as_deref().unwrap_or("-")),
+                Cell::new(
+                    container
+                        .validators
+                        .map_or_else(|| "-".to_owned(), |count| count.to_string()),
+                ),
+                Cell::colored(&container.state, state_color(&container.state)),
+                Cell::new(ports),
+            ]);
+        }
+        print!("{}", table.render(stdout_supports_color()));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    env,
+    path::{self, PathBuf},
+};
+
+use clap::Args;
+use color_eyre::{
+    Result,
+    eyre::{Context as _, bail},
+};
+use serde_json::json;
+
+use crate::{
+    codespan::{report_config_errors, report_unknown_node},
+    config::{NodeNameDefinition, ResolveOptions, find_config, load_network_config, plan},
+    docker::{DockerArgs, DockerError, remove_container},
+    genesis::Genesis,
+    output, process,
+    validator::{KeyManifest, KeyStore},
+};
+
+#[derive(Debug, Clone, Args)]
+pub struct RestartCommand {
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Network to restart, required when config defines multiple networks.
+    #[arg(long)]
+    network: Option<String>,
+
+    /// Node to restart, or node group, i.e. name of the node with `count` in
+    /// config, to restart all of its replicas.
+    #[arg(required_unless_present = "all", conflicts_with = "all")]
+    node: Option<String>,
+
+    /// Restart every node of the network.
+    #[arg(long)]
+    all: bool,
+
+    /// Directory with validator keys of the network. Defaults to the one,
+    /// used by `start`.
+    #[arg(long, visible_alias = "output-dir")]
+    out_dir: Option<PathBuf>,
+}
+
+impl RestartCommand {
+    pub async fn run(&self, docker_args: &DockerArgs) -> Result<()> {
+        let config_path = find_config(self.config.as_deref());
+        let (config, sources) =
+            load_network_config(&config_path, None, self.network.as_deref()).await?;
+
+        let key_dir = match &self.out_dir {
+            Some(dir) => dir.clone(),
+            None => env::temp_dir().join("liesas").join(config.name()),
+        };
+        let key_dir = path::absolute(&key_dir)
+            .wrap_err_with(|| format!("failed to resolve output directory {key_dir:?}"))?;
+
+        if !key_dir.join(KeyManifest::FILE_NAME).exists() {
+            bail!(
+                "no validator keys found in {key_dir:?}, start the network first, or pass `--out-dir` it was started with"
+            );
+        }
+
+        // Nodes keep keys, they were started with, so config changes apply
+        // to everything, except validators.
+        let options = ResolveOptions::new(&config_path, KeyStore::Planned(key_dir.clone()));
+        let planned =
+            plan(config, options).and_then(|plan| plan.network.check_binaries().map(|()| plan));
+        let mut plan = match planned {
+            Ok(value) => value,
+            Err(errors) => report_config_errors(&sources, errors),
+        };
+
+        if let Some(node) = &self.node
+            && !plan.network.nodes().contains_key(node)
+            && !plan.network.is_group(node)
+        {
+            let available = plan
+                .network
+                .nodes()
+                .keys()
+                .map(String::as_str)
+                .collect::<Vec<_>>();
+            report_unknown_node(node, &available);
+        }
+
+        let genesis = key_dir.join(Genesis::FILE_NAME);
+        if genesis.exists() {
+            for spec in plan
+                .nodes
+                .iter_mut()
+                .filter_map(|node| node.container.as_mut())
+            {
+                spec.mount_genesis(&genesis);
+            }
+        }
+
+        let docker = docker_args.connect().await?;
+        let retry = docker_args.retry_policy();
+
+        let mut restarted = Vec::new();
+        let mut absent = Vec::new();
+
+        for node in &plan.nodes {
+            let selected = match &self.node {
+                Some(filter) => {
+                    node.name == *filter
+                        || matches!(
+                            &plan.network.nodes()[&node.name].def,
+                            NodeNameDefinition::Prefix { prefix, .. } if prefix == filter
+                        )
+                }
+                None => true,
+            };
+            if !selected {
+                continue;
+            }
+
+            let name = &node.name;
+            let network = plan.network.name();
+
+            if let Some(spec) = &node.process {
+                if !process::terminate(network, name).await? {
+                    if !output::is_json() {
+                        println!("{name} is not running");
+                    }
+                    absent.push(name.clone());
+                    continue;
+                }
+
+                spec.spawn(&process::run_dir(network))
+                    .wrap_err_with(|| format!("failed to spawn {:?}", spec.bin))?;
+            } else if let Some(spec) = &node.container {
+                if !remove_container(&docker, retry, &spec.name).await? {
+                    if !output::is_json() {
+                        println!("{name} is not running");
+                    }
+                    absent.push(name.clone());
+                    continue;
+                }
+
+                let (options, body) = spec.create_request();
+                retry
+                    .run(&format!("creating {name}"), || {
+                        docker.create_container(Some(options.clone()), body.clone())
+                    })
+                    .await
+                    .map_err(|err| DockerError::create(&spec.name, &spec.image, err))?;
+                retry
+                    .run(&format!("starting {name}"), || {
+                        docker.start_container(&spec.name, None)
+                    })
+                    .await
+                    .map_err(|err| DockerError::start(&spec.name, err))?;
+            } else {
+                continue;
+            }
+
+            if !output::is_json() {
+                println!("restarted {name}");
+            }
+            restarted.push(name.clone());
+        }
+
+        if output::is_json() {
+            output::success(json!({
+                "network": plan.network.name(),
+                "restarted": restarted,
+                "absent": absent,
+            }));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,371 @@
+use std::{
+    collections::HashMap,
+    env,
+    path::{self, PathBuf},
+};
+
+use bollard::{Docker, query_parameters::ListContainersOptionsBuilder};
+use clap::Args;
+use color_eyre::{
+    Result,
+    eyre::{Context as _, bail},
+};
+use serde_json::json;
+
+use crate::{
+    codespan::{Sources, report_config_error, report_config_errors, report_unknown_node},
+    config::{
+        ConfigError, NodeNameDefinition, ResolveOptions, ResolvedNetworkConfig, find_config,
+        load_network_config,
+    },
+    container::{ContainerSpec, NETWORK_LABEL, NODE_LABEL},
+    docker::{DockerArgs, DockerError, RetryPolicy, remove_container},
+    output,
+    validator::{KeyManifest, KeyStore},
+};
+
+#[derive(Debug, Clone, Args)]
+pub struct ScaleCommand {
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Network to scale, required when config defines multiple networks.
+    #[arg(long)]
+    network: Option<String>,
+
+    /// Node group to scale, i.e. name of the node with `count` in config.
+    node: String,
+
+    /// Number of replicas, which should be running.
+    #[arg(long)]
+    count: u64,
+
+    /// Directory with validator keys of the network, where keys of new
+    /// replicas are written. Defaults to the one, used by `start`.
+    #[arg(long, visible_alias = "output-dir")]
+    out_dir: Option<PathBuf>,
+}
+
+impl ScaleCommand {
+    pub async fn run(&self, docker_args: &DockerArgs) -> Result<()> {
+        let config_path = find_config(self.config.as_deref());
+        let (config, sources) =
+            load_network_config(&config_path, None, self.network.as_deref()).await?;
+
+        // Containers mount keys, so they always go to disk, by default to the
+        // same directory as `start` uses.
+        let key_dir = match &self.out_dir {
+            Some(dir) => dir.clone(),
+            None => env::temp_dir().join("liesas").join(config.name()),
+        };
+        let key_dir = path::absolute(&key_dir)
+            .wrap_err_with(|| format!("failed to resolve output directory {key_dir:?}"))?;
+
+        // Existing replicas already have keys, so network is resolved without
+        // generating them, and keys are generated only for new replicas.
+        let options = ResolveOptions::new(&config_path, KeyStore::Planned(key_dir.clone()));
+        let mut resolved = match config.resolve(options) {
+            Ok(value) => value,
+            Err(errors) => report_config_errors(&sources, errors),
+        };
+
+        if !resolved.is_group(&self.node) {
+            if resolved.nodes().contains_key(&self.node) {
+                bail!(
+                    "`{}` is a single node without replicas, raise its `count` in the config \
+                     to 2 or more to scale it",
+                    self.node
+                );
+            }
+
+            let mut available = resolved
+                .nodes()
+                .values()
+                .filter_map(|node| match &node.def {
+                    NodeNameDefinition::Prefix { prefix, .. } => Some(prefix.as_str()),
+                    NodeNameDefinition::Singular(_) => None,
+                })
+                .collect::<Vec<_>>();
+            available.dedup();
+            report_unknown_node(&self.node, &available);
+        }
+        resolved.set_key_store(KeyStore::Disk(key_dir.clone()));
+
+        // Replicas of earlier runs are not in the config, but their keys are
+        // still in the manifest.
+        let previous = KeyManifest::read(&key_dir)
+            .wrap_err_with(|| format!("failed to read key manifest from {key_dir:?}"))?;
+        if let Some(previous) = &previous {
+            resolved.skip_validators_below(previous.next_validator_index());
+        }
+
+        let docker = docker_args.connect().await?;
+        let (started, removed) = scale(
+            &docker,
+            docker_args.retry_policy(),
+            &mut resolved,
+            &sources,
+            &self.node,
+            self.count,
+            previous.as_ref(),
+        )
+        .await?;
+
+        if !started.is_empty() {
+            let manifest = KeyManifest::new(&resolved);
+            let manifest = match previous {
+                Some(previous) => manifest.merge(previous),
+                None => manifest,
+            };
+            manifest
+                .write(&key_dir)
+                .wrap_err_with(|| format!("failed to write key manifest to {key_dir:?}"))?;
+        }
+
+        if output::is_json() {
+            output::success(json!({ "started": started, "removed": removed }));
+        }
+
+        Ok(())
+    }
+}
+
+/// Start or remove replicas of group `node`, until exactly `count` of them
+/// exist. Returns names of started and removed replicas.
+///
+/// `previous` is the key manifest of earlier runs, which lists replicas,
+/// that were removed since.
+async fn scale(
+    docker: &Docker,
+    retry: RetryPolicy,
+    resolved: &mut ResolvedNetworkConfig,
+    sources: &Sources,
+    node: &str,
+    count: u64,
+    previous: Option<&KeyManifest>,
+) -> Result<(Vec<String>, Vec<String>)> {
+    let filters = HashMap::from([(
+        "label",
+        vec![format!("{NETWORK_LABEL}={}", resolved.name())],
+    )]);
+    let containers = docker
+        .list_containers(Some(
+            ListContainersOptionsBuilder::default()
+                .all(true)
+                .filters(&filters)
+                .build(),
+        ))
+        .await
+        .map_err(|err| DockerError::request("listing containers", err))?;
+
+    // Replicas of the group, which currently exist, ordered by index.
+    let group_prefix = format!("{node}_");
+    let replica_index = |name: &str| name.strip_prefix(&group_prefix)?.parse::<u64>().ok();
+    let mut running = containers
+        .iter()
+        .filter_map(|container| {
+            let name = container.labels.as_ref()?.get(NODE_LABEL)?;
+            Some((replica_index(name)?, name.clone()))
+        })
+        .collect::<Vec<_>>();
+    running.sort();
+
+    let current = running.len() as u64;
+    let mut started = Vec::new();
+    let mut removed = Vec::new();
+
+    if count > current {
+        // Continue after the highest index, which has ever been used, so that
+        // names of removed replicas, and key files in their directories, are
+        // never reused.
+        let next = running
+            .iter()
+            .map(|(index, _)| *index)
+            .chain(
+                previous
+                    .iter()
+                    .flat_map(|manifest| &manifest.nodes)
+                    .filter_map(|node| replica_index(&node.name)),
+            )
+            .max()
+            .map_or(0, |index| index + 1);
+
+        for index in next..next + (count - current) {
+            let name = match resolved.add_replica(node, index) {
+                Ok(Some(name)) => name,
+                Ok(None) => unreachable!("node group is checked above"),
+                Err(err) => report_config_error(sources, err),
+            };
+
+            let Some(spec) = ContainerSpec::new(resolved, &name, &resolved.nodes()[&name]) else {
+                bail!("`{node}` runs without container and cannot be scaled");
+            };
+            spec.write_config_file(&name, resolved.key_store())
+                .map_err(|(path, error)| ConfigError::WriteConfigFile {
+                    path,
+                    error,
+                    span: resolved.nodes()[&name].client_span.clone(),
+                })
+                .unwrap_or_else(|err| report_config_error(sources, err));
+
+            let (options, body) = spec.create_request();
+            retry
+                .run(&format!("creating {name}"), || {
+                    docker.create_container(Some(options.clone()), body.clone())
+                })
+                .await
+                .map_err(|err| DockerError::create(&spec.name, &spec.image, err))?;
+            retry
+                .run(&format!("starting {name}"), || {
+                    docker.start_container(&spec.name, None)
+                })
+                .await
+                .map_err(|err| DockerError::start(&spec.name, err))?;
+
+            if !output::is_json() {
+                println!("started {name}");
+            }
+            started.push(name);
+        }
+    }
+
+    if count < current {
+        for (_, name) in running.iter().rev().take((current - count) as usize) {
+            remove_container(docker, retry, &resolved.container_name(name)).await?;
+
+            if !output::is_json() {
+                println!("removed {name}");
+            }
+            removed.push(name.clone());
+        }
+    }
+
+    Ok((started, removed))
+}
+
+#[cfg(test)]
+mod tests {
+    use bollard::API_DEFAULT_VERSION;
+
+    use super::*;
+    use crate::{
+        testing::{MockServer, resolve},
+        validator::{ManifestNode, key_path},
+    };
+
+    /// Manifest of earlier runs, with one validator per replica of `ream`.
+    fn manifest(replicas: std::ops::Range<usize>) -> KeyManifest {
+        KeyManifest {
+            network: "devnet".to_owned(),
+            nodes: replicas
+                .map(|index| {
+                    let name = format!("ream_{index}");
+                    ManifestNode {
+                        validators: vec![key_path(&name, index)],
+                        name,
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn group_is_scaled_up_from_highest_index() {
+        let source = r#"
+            name = "devnet"
+
+            [[node]]
+            name = "ream"
+            client = "ream"
+            count = 2
+            "#;
+        let mut resolved = resolve(source).unwrap();
+        let sources = Sources::new(PathBuf::from("network.toml"), source.to_owned());
+
+        let server = MockServer::start(vec![
+            (
+                200,
+                r#"[{"Labels":{"liesas.node":"ream_0"}},{"Labels":{"liesas.node":"ream_1"}}]"#,
+            ),
+            (201, r#"{"Id":"ream_2","Warnings":[]}"#),
+            (204, ""),
+            (201, r#"{"Id":"ream_3","Warnings":[]}"#),
+            (204, ""),
+        ])
+        .await;
+        let docker = Docker::connect_with_http(&server.url(""), 5, API_DEFAULT_VERSION).unwrap();
+
+        let (started, removed) = scale(
+            &docker,
+            RetryPolicy::no_retries(),
+            &mut resolved,
+            &sources,
+            "ream",
+            4,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(started, ["ream_2", "ream_3"]);
+        assert!(removed.is_empty());
+
+        let created = (0..server.requests())
+            .filter(|&index| server.request(index).contains("/containers/create"))
+            .count();
+        assert_eq!(created, 2);
+    }
+
+    #[tokio::test]
+    async fn replicas_of_earlier_runs_are_not_reused() {
+        let source = r#"
+            name = "devnet"
+
+            [[node]]
+            name = "ream"
+            client = "ream"
+            count = 2
+            "#;
+        let mut resolved = resolve(source).unwrap();
+        let sources = Sources::new(PathBuf::from("network.toml"), source.to_owned());
+
+        // Scaled to 5 earlier, and then back to 4, so `ream_4` is gone, but
+        // its validator is still taken.
+        let previous = manifest(0..5);
+        resolved.skip_validators_below(previous.next_validator_index());
+
+        let server = MockServer::start(vec![
+            (
+                200,
+                r#"[
+                    {"Labels":{"liesas.node":"ream_0"}},
+                    {"Labels":{"liesas.node":"ream_1"}},
+                    {"Labels":{"liesas.node":"ream_2"}},
+                    {"Labels":{"liesas.node":"ream_3"}}
+                ]"#,
+            ),
+            (201, r#"{"Id":"ream_5","Warnings":[]}"#),
+            (204, ""),
+            (201, r#"{"Id":"ream_6","Warnings":[]}"#),
+            (204, ""),
+        ])
+        .await;
+        let docker = Docker::connect_with_http(&server.url(""), 5, API_DEFAULT_VERSION).unwrap();
+
+        let (started, _) = scale(
+            &docker,
+            RetryPolicy::no_retries(),
+            &mut resolved,
+            &sources,
+            "ream",
+            6,
+            Some(&previous),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(started, ["ream_5", "ream_6"]);
+        assert_eq!(resolved.nodes()["ream_5"].validators, [5]);
+        assert_eq!(resolved.nodes()["ream_6"].validators, [6]);
+    }
+}
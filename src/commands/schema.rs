@@ -0,0 +1,65 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use color_eyre::{Result, eyre::Context as _};
+use serde_json::json;
+
+use crate::{config::NetworkConfig, output};
+
+#[derive(Debug, Clone, Args)]
+pub struct SchemaCommand {
+    /// File to write the schema to. Printed to stdout, if omitted.
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
+
+impl SchemaCommand {
+    pub async fn run(&self) -> Result<()> {
+        let schema = schemars::schema_for!(NetworkConfig);
+
+        match &self.out {
+            Some(path) => {
+                let contents =
+                    serde_json::to_string_pretty(&schema).context("failed to serialize schema")?;
+                tokio::fs::write(path, contents)
+                    .await
+                    .context(format!("failed to write schema to {path:?}"))?;
+
+                if output::is_json() {
+                    output::success(json!({ "path": path }));
+                }
+            }
+            None if output::is_json() => output::success(json!({ "schema": schema })),
+            None => println!("{}", serde_json::to_string_pretty(&schema)?),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+    use strum::VariantNames;
+
+    use super::*;
+    use crate::client::ClientKind;
+
+    #[test]
+    fn schema_lists_all_client_kinds() {
+        let schema = serde_json::to_string_pretty(&schemars::schema_for!(NetworkConfig)).unwrap();
+        let schema: Value = serde_json::from_str(&schema).unwrap();
+
+        assert_eq!(
+            schema["$defs"]["ClientKind"]["enum"],
+            json!(ClientKind::VARIANTS)
+        );
+        assert_eq!(
+            schema["$defs"]["ClientSource"]["oneOf"]
+                .as_array()
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+}
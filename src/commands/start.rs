@@ -1,46 +1,696 @@
-use std::path::PathBuf;
+use std::{
+    collections::HashSet,
+    env,
+    io::{self, IsTerminal},
+    path::{self, PathBuf},
+    time::Duration,
+};
 
-use clap::Args;
-use color_eyre::{Result, eyre::Context as _};
-use tokio::{fs::File, io::AsyncReadExt};
+use bollard::Docker;
+use clap::{Args, error::ErrorKind};
+use color_eyre::{
+    Result,
+    eyre::{Context as _, bail},
+    owo_colors::{AnsiColors, OwoColorize, Stream},
+};
+use indicatif::{ProgressBar, ProgressStyle};
+use serde_json::json;
+use tokio::time::sleep;
 
 use crate::{
-    codespan::{report_config_error, report_toml_error},
-    config::NetworkConfig,
+    codespan::{report_config_errors, report_config_warnings, report_missing_image},
+    commands::{prepare_out_dir, usage_error},
+    config::{
+        ClientSource, ConfigFormat, NetworkPlan, ResolveOptions, ResolvedNetworkConfig, check,
+        find_config, load_network_config, plan,
+    },
+    docker::{DockerArgs, DockerError, PullPolicy, RetryPolicy},
+    events::{Event, EventSink, EventTarget},
+    exit::Failure,
+    genesis::Genesis,
+    hook::{NodeEndpoint, run_post_start},
+    launch::{LaunchError, LaunchedNode, Launcher},
+    output::{self, Diagnostic},
+    prometheus::ScrapeConfig,
+    table::{Cell, Table, stdout_supports_color},
+    validator::{KeyManifest, KeyStore},
+    watch::{ImageWatcher, POLL_INTERVAL, affected_nodes},
 };
 
+/// Summary of nodes, one row per node, with containers from `launched`.
+fn summary(plan: &NetworkPlan, launched: &[LaunchedNode]) -> Table {
+    let mut table = Table::new(vec!["name", "client", "status", "container", "ports"]);
+    for node in &plan.nodes {
+        let ports = node
+            .container
+            .iter()
+            .flat_map(|spec| &spec.ports)
+            .map(|port| {
+                format!(
+                    "{}:{}->{}",
+                    port.host_ip, port.host_port, port.container_port
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let launched = launched.iter().find(|launched| launched.name == node.name);
+        let (status, instance) = match launched {
+            Some(launched) => (
+                Cell::colored("running", AnsiColors::Green),
+                launched.instance.to_string(),
+            ),
+            None => (Cell::colored("skipped", AnsiColors::Yellow), "-".to_owned()),
+        };
+
+        table.push(vec![
+            Cell::new(&node.name),
+            Cell::new(plan.network.nodes()[&node.name].client.kind()),
+            status,
+            Cell::new(instance),
+            Cell::new(if ports.is_empty() {
+                "-".to_owned()
+            } else {
+                ports.join(", ")
+            }),
+        ]);
+    }
+
+    table
+}
+
+/// Print planned nodes, with the commands that would run them.
+fn print_plan(plan: &NetworkPlan) {
+    let nodes = plan.nodes.iter().map(|node| {
+        let resolved = &plan.network.nodes()[&node.name];
+        let source = match &resolved.client {
+            ClientSource::Binary { bin, .. } => bin.display().to_string(),
+            client => client.image().unwrap_or_default().to_owned(),
+        };
+        let command = match (&node.container, &node.process) {
+            (Some(spec), _) => Some(spec.to_docker_run()),
+            (None, Some(spec)) => Some(spec.to_command_line()),
+            (None, None) => None,
+        };
+
+        (node, resolved, source, command)
+    });
+
+    if output::is_json() {
+        let nodes = nodes
+            .map(|(node, resolved, source, command)| {
+                json!({
+                    "name": node.name,
+                    "client": resolved.client.kind(),
+                    "source": source,
+                    "validators": node.validators.len(),
+                    "ports": {
+                        "p2p": resolved.ports.p2p,
+                        "rpc": resolved.ports.rpc,
+                        "metrics": resolved.ports.metrics,
+                    },
+                    "command": command,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        output::success(json!({
+            "network": plan.network.name(),
+            "dry_run": true,
+            "nodes": nodes,
+        }));
+        return;
+    }
+
+    println!("network `{}` would start:", plan.network.name());
+    for (node, resolved, source, command) in nodes {
+        println!();
+        println!("{} ({}, {source})", node.name, resolved.client.kind());
+        println!("  validators: {}", node.validators.len());
+        println!(
+            "  ports: p2p {}, rpc {}, metrics {}",
+            resolved.ports.p2p, resolved.ports.rpc, resolved.ports.metrics
+        );
+        if let Some(command) = command {
+            println!("  {command}");
+        }
+    }
+}
+
 #[derive(Debug, Clone, Args)]
 pub struct StartCommand {
+    /// Config file, or `-` to read it from stdin.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Format of the config, detected by extension of `--config`, if
+    /// omitted. Config from stdin is TOML by default.
+    #[arg(long, value_enum)]
+    config_format: Option<ConfigFormat>,
+
+    /// Network to start, required when config defines multiple networks.
+    #[arg(long)]
+    network: Option<String>,
+
+    /// Launch only nodes of this group. Nodes, they depend on, must already
+    /// be running.
+    #[arg(long)]
+    group: Option<String>,
+
+    /// Directory, where generated validator keys are written, as
+    /// `<node>/validator_<index>.json`, along with `manifest.json` listing
+    /// them, and `genesis.json` of the network.
+    ///
+    /// Keys are streamed to disk as they are generated, instead of being kept
+    /// in memory.
+    #[arg(long, visible_alias = "output-dir")]
+    out_dir: Option<PathBuf>,
+
+    /// Write keys into `--out-dir`, even if it is not empty.
+    #[arg(long, requires = "out_dir")]
+    force: bool,
+
+    /// Print equivalent `docker run` command for each node.
     #[arg(long)]
-    config: PathBuf,
+    print_command: bool,
+
+    /// Don't fail, when `post_start` hook exits with non-zero status.
+    #[arg(long)]
+    ignore_hook_failure: bool,
+
+    /// Treat config warnings as errors.
+    #[arg(long)]
+    strict: bool,
+
+    /// Refuse to start network with more validators in total.
+    #[arg(long)]
+    max_validators: Option<u64>,
+
+    /// Seed of validator key generation, overriding `seed` of the config.
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// When to pull images of nodes before launching them.
+    #[arg(long, value_enum, default_value = "missing")]
+    pull: PullPolicy,
+
+    /// Driver of the docker network, nodes are attached to. Existing network
+    /// is reused as is.
+    #[arg(long, default_value = "bridge")]
+    network_driver: String,
+
+    /// Return right after launch, without waiting for nodes to become
+    /// healthy.
+    #[arg(long)]
+    no_wait: bool,
+
+    /// How long to wait for nodes to become healthy, e.g. `90s` or `5m`.
+    #[arg(long, default_value = "2m", value_parser = humantime::parse_duration, conflicts_with = "no_wait")]
+    timeout: Duration,
+
+    /// Once nodes are healthy, wait until each of them connects to its peers,
+    /// failing if some didn't within `--timeout`.
+    #[arg(long, conflicts_with = "no_wait")]
+    verify_peers: bool,
+
+    /// Keep checking for updated images of nodes, and restart nodes, whose
+    /// image changed, until Ctrl-C.
+    #[arg(long, conflicts_with = "detach")]
+    watch: bool,
+
+    /// Don't show progress of key generation.
+    #[arg(long, short)]
+    quiet: bool,
+
+    /// Leave network running in the background, instead of stopping it on
+    /// Ctrl-C.
+    #[arg(long, short)]
+    detach: bool,
+
+    /// Resolve the network and print what would be launched, without
+    /// generating keys, or touching docker or the disk.
+    #[arg(long, conflicts_with_all = ["force", "print_command", "detach", "no_wait", "verify_peers", "watch"])]
+    dry_run: bool,
+
+    /// Write newline-delimited JSON lifecycle events to file, or to already
+    /// open file descriptor, given as `fd:<n>`.
+    #[arg(long, value_name = "PATH_OR_FD")]
+    events: Option<EventTarget>,
 }
 
 impl StartCommand {
-    pub async fn run(&self) -> Result<()> {
-        let mut file = File::open(&self.config)
-            .await
-            .context(format!("failed to read config at {:?}", self.config))?;
+    pub async fn run(&self, docker_args: &DockerArgs) -> Result<()> {
+        let mut events = match &self.events {
+            Some(target) => EventSink::open(target)
+                .wrap_err_with(|| format!("failed to open event stream {target:?}"))?,
+            None => EventSink::disabled(),
+        };
 
-        let mut buffer = String::new();
-        file.read_to_string(&mut buffer)
-            .await
-            .context("invalid network config")?;
+        let result = self.start(docker_args, &mut events).await;
+        if let Err(err) = &result {
+            events.emit(Event::Error {
+                class: Failure::of(err),
+                message: format!("{err:#}"),
+            });
+        }
 
-        let config: NetworkConfig = match toml::de::from_str(&buffer) {
-            Ok(value) => value,
-            Err(err) => report_toml_error(
-                "Invalid network configuration".to_owned(),
-                self.config.clone(),
-                buffer,
-                err,
-            ),
+        result
+    }
+
+    async fn start(&self, docker_args: &DockerArgs, events: &mut EventSink) -> Result<()> {
+        let config_path = find_config(self.config.as_deref());
+        let (config, sources) =
+            load_network_config(&config_path, self.config_format, self.network.as_deref()).await?;
+
+        events.emit(Event::ResolveStarted {
+            network: config.name().to_owned(),
+        });
+
+        // Containers mount keys, so they always go to disk.
+        let key_dir = match &self.out_dir {
+            Some(dir) => dir.clone(),
+            None => env::temp_dir().join("liesas").join(config.name()),
+        };
+        let key_dir = path::absolute(&key_dir)
+            .wrap_err_with(|| format!("failed to resolve output directory {key_dir:?}"))?;
+
+        let key_store = if self.dry_run {
+            KeyStore::Planned(key_dir.clone())
+        } else {
+            // Default directory is private to this tool, so it is reused.
+            prepare_out_dir(&key_dir, self.force || self.out_dir.is_none())?;
+            KeyStore::Disk(key_dir.clone())
+        };
+
+        // Docker is checked before planning too, so that key generation isn't
+        // wasted when daemon is not running.
+        let docker = if self.dry_run {
+            None
+        } else {
+            Some(docker_args.connect().await?)
         };
 
-        let resolved = match config.resolve() {
+        // Warnings are checked before planning, so that `--strict` doesn't
+        // wait for key generation.
+        let warnings = check(&config);
+        report_config_warnings(&sources, &warnings);
+
+        if self.strict && !warnings.is_empty() {
+            let message = format!("{} config warning(s), denied by `--strict`", warnings.len());
+            events.emit(Event::Error {
+                class: Some(Failure::ConfigSemantic),
+                message: message.clone(),
+            });
+
+            if output::is_json() {
+                output::failure(Some(Failure::ConfigSemantic), message, Vec::new());
+            }
+
+            eprintln!(
+                "{} {message}",
+                "Error:".if_supports_color(Stream::Stderr, |text| text.red())
+            );
+            Failure::ConfigSemantic.exit();
+        }
+
+        let options = ResolveOptions::new(&config_path, key_store)
+            .with_progress(self.progress())
+            .with_max_validators(self.max_validators)
+            .with_seed(self.seed);
+        let planned =
+            plan(config, options).and_then(|plan| plan.network.check_binaries().map(|()| plan));
+        let mut plan = match planned {
             Ok(value) => value,
-            Err(err) => report_config_error(self.config.clone(), buffer, err),
+            Err(errors) => {
+                events.emit(Event::Error {
+                    class: Some(Failure::ConfigSemantic),
+                    message: errors
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join("; "),
+                });
+                report_config_errors(&sources, errors)
+            }
+        };
+
+        if let Some(group) = &self.group
+            && !plan.retain_group(group)
+        {
+            bail!("network has no nodes in group `{group}`");
+        }
+
+        if self.dry_run {
+            print_plan(&plan);
+            return Ok(());
+        }
+
+        events.emit(Event::KeysGenerated {
+            count: plan.nodes.iter().map(|node| node.validators.len()).sum(),
+        });
+
+        KeyManifest::new(&plan.network)
+            .write(&key_dir)
+            .wrap_err_with(|| format!("failed to write key manifest to {key_dir:?}"))?;
+
+        let genesis = Genesis::new(&plan.network)
+            .and_then(|genesis| genesis.write(&key_dir))
+            .wrap_err_with(|| format!("failed to write genesis to {key_dir:?}"))?;
+        for spec in plan
+            .nodes
+            .iter_mut()
+            .filter_map(|node| node.container.as_mut())
+        {
+            spec.mount_genesis(&genesis);
+        }
+
+        if self.ignore_hook_failure && plan.network.post_start().is_none() {
+            usage_error(
+                ErrorKind::ArgumentConflict,
+                "`--ignore-hook-failure` requires network to define `post_start` hook",
+            );
+        }
+
+        if self.print_command && !output::is_json() {
+            for node in &plan.nodes {
+                if let Some(spec) = &node.container {
+                    println!("{}", spec.to_docker_run());
+                } else if let Some(spec) = &node.process {
+                    println!("{}", spec.to_command_line());
+                }
+            }
+        }
+
+        let docker = docker.expect("docker is connected, unless it is dry run");
+        let mut launcher = Launcher::new(&docker, docker_args.retry_policy(), plan.network.name());
+
+        match launcher.pull_images(&plan, self.pull).await {
+            Err(LaunchError::Pull {
+                node,
+                source: DockerError::ImageNotFound { image },
+            }) if matches!(
+                plan.network.nodes()[&node].client,
+                ClientSource::Image { .. }
+            ) =>
+            {
+                events.emit(Event::Error {
+                    class: Some(Failure::Launch),
+                    message: format!("image `{image}` of `{node}` not found"),
+                });
+                report_missing_image(
+                    &sources,
+                    &node,
+                    &image,
+                    plan.network.nodes()[&node].client_span.clone(),
+                );
+            }
+            result => result?,
+        }
+
+        // Ctrl-C midway through launch is not a failure, but nodes launched
+        // so far are removed, same as when network is stopped with Ctrl-C.
+        let launch = tokio::select! {
+            result = self.launch(&mut launcher, &plan, events) => Some(result),
+            result = tokio::signal::ctrl_c() => {
+                result.context("failed to wait for Ctrl-C")?;
+                None
+            }
+        };
+
+        match launch {
+            Some(Ok(())) => {}
+            Some(Err(err)) => {
+                launcher.rollback().await;
+                return Err(err);
+            }
+            None => {
+                if output::is_json() {
+                    output::success(json!({
+                        "network": plan.network.name(),
+                        "interrupted": true,
+                        "launched": launcher
+                            .nodes()
+                            .iter()
+                            .map(|node| &node.name)
+                            .collect::<Vec<_>>(),
+                    }));
+                } else {
+                    eprintln!("interrupted, {} node(s) launched", launcher.nodes().len());
+                }
+                if !self.detach {
+                    launcher.rollback_interrupted(&plan).await;
+                }
+                return Ok(());
+            }
+        }
+
+        // Scrape config is regenerated on each start, as ports may change.
+        let scrape_config = ScrapeConfig::new(&plan.network);
+        let scrape_config = if scrape_config.target_count() > 0 {
+            Some(
+                scrape_config
+                    .write(&key_dir)
+                    .wrap_err_with(|| format!("failed to write scrape config to {key_dir:?}"))?,
+            )
+        } else {
+            None
+        };
+
+        if output::is_json() {
+            let mut result = json!({
+                "network": plan.network.name(),
+                "nodes": summary(&plan, launcher.nodes()).to_json(),
+                "scrape_config": scrape_config,
+            });
+
+            if self.print_command {
+                result["commands"] = plan
+                    .nodes
+                    .iter()
+                    .filter_map(|node| match (&node.container, &node.process) {
+                        (Some(spec), _) => Some(spec.to_docker_run()),
+                        (None, Some(spec)) => Some(spec.to_command_line()),
+                        (None, None) => None,
+                    })
+                    .collect();
+            }
+
+            output::success(result);
+        } else {
+            print!(
+                "{}",
+                summary(&plan, launcher.nodes()).render(stdout_supports_color())
+            );
+            if let Some(path) = &scrape_config {
+                println!("prometheus scrape config: {}", path.display());
+            }
+        }
+        events.emit(Event::StartComplete);
+
+        if self.detach {
+            return Ok(());
+        }
+
+        if !output::is_json() {
+            eprintln!("network is running, press Ctrl-C to stop it");
+        }
+
+        let result = if self.watch {
+            self.watch(
+                &docker,
+                docker_args.retry_policy(),
+                &mut launcher,
+                &plan,
+                events,
+            )
+            .await
+        } else {
+            tokio::signal::ctrl_c()
+                .await
+                .context("failed to wait for Ctrl-C")
         };
+        launcher.rollback().await;
+
+        result
+    }
+
+    /// Restart nodes of `plan`, whose image was updated, until Ctrl-C.
+    async fn watch(
+        &self,
+        docker: &Docker,
+        retry: RetryPolicy,
+        launcher: &mut Launcher<'_>,
+        plan: &NetworkPlan,
+        events: &mut EventSink,
+    ) -> Result<()> {
+        let images = plan
+            .nodes
+            .iter()
+            .filter_map(|node| Some(node.container.as_ref()?.image.clone()))
+            .collect::<HashSet<_>>();
+        let mut watcher = ImageWatcher::new(docker, images).await;
+
+        // Created once, so that Ctrl-C, pressed while images are checked, is
+        // not missed.
+        let ctrl_c = tokio::signal::ctrl_c();
+        tokio::pin!(ctrl_c);
+
+        loop {
+            tokio::select! {
+                result = &mut ctrl_c => return result.context("failed to wait for Ctrl-C"),
+                () = sleep(POLL_INTERVAL) => {}
+            }
+
+            // Registry may be temporarily unreachable, which is not a reason
+            // to stop the network.
+            let changed = match watcher
+                .poll(docker, retry, self.pull, plan.network.registries())
+                .await
+            {
+                Ok(changed) => changed,
+                Err(err) => {
+                    let message = format!("failed to check for updated images: {err}");
+                    if output::is_json() {
+                        output::warn(Diagnostic::warning(message));
+                    } else {
+                        eprintln!(
+                            "{} {message}",
+                            "Warning:".if_supports_color(Stream::Stderr, |text| text.yellow())
+                        );
+                    }
+                    continue;
+                }
+            };
+
+            for node in affected_nodes(plan, &changed) {
+                launcher.relaunch(node, events).await?;
+                if !output::is_json() {
+                    println!("restarted {} with updated image", node.name);
+                }
+            }
+        }
+    }
+
+    /// Launch nodes of `plan` in docker network, wait for them to become
+    /// healthy, unless `--no-wait` is passed, and run `post_start` hook.
+    ///
+    /// On failure, launched nodes are left for caller to roll back.
+    async fn launch(
+        &self,
+        launcher: &mut Launcher<'_>,
+        plan: &NetworkPlan,
+        events: &mut EventSink,
+    ) -> Result<()> {
+        launcher.create_network(plan, &self.network_driver).await?;
+        launcher.launch(plan, events).await?;
+
+        if !self.no_wait {
+            launcher.wait_healthy(plan, self.timeout, events).await?;
+        }
+
+        if self.verify_peers {
+            launcher.verify_peers(plan, self.timeout).await?;
+        }
+
+        let endpoints = launcher
+            .nodes()
+            .iter()
+            .map(|node| NodeEndpoint {
+                name: node.name.clone(),
+                ip: plan.network.host_address(&node.name),
+                http_port: plan.network.nodes()[&node.name].ports.rpc,
+            })
+            .collect::<Vec<_>>();
+        self.post_start(&plan.network, &endpoints).await
+    }
+
+    /// Progress bar of key generation, hidden unless stdout is a terminal.
+    fn progress(&self) -> ProgressBar {
+        if self.quiet || self.dry_run || output::is_json() || !io::stdout().is_terminal() {
+            return ProgressBar::hidden();
+        }
+
+        ProgressBar::new(0).with_style(
+            ProgressStyle::with_template("generating keys {bar:30} {pos}/{len} [{elapsed}]")
+                .expect("progress template is valid"),
+        )
+    }
+
+    /// Run `post_start` hook of the network, if any, once nodes at `endpoints`
+    /// are healthy.
+    async fn post_start(
+        &self,
+        network: &ResolvedNetworkConfig,
+        endpoints: &[NodeEndpoint],
+    ) -> Result<()> {
+        let Some(command) = network.post_start() else {
+            return Ok(());
+        };
+
+        match run_post_start(command, network.base_dir(), network.name(), endpoints).await {
+            Err(err) if self.ignore_hook_failure => {
+                if output::is_json() {
+                    output::warn(Diagnostic::warning(format!("{err:#}")));
+                } else {
+                    eprintln!(
+                        "{} {err:#}",
+                        "Warning:".if_supports_color(Stream::Stderr, |text| text.yellow())
+                    );
+                }
+
+                Ok(())
+            }
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::{config::NetworkConfig, launch::Instance};
+
+    #[test]
+    fn summary_has_row_per_node() {
+        let config: NetworkConfig = toml::from_str(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            name = "alpha"
+            client = "ream"
+            rpc_port = 5052
+
+            [[node]]
+            name = "beta"
+            client = "ream"
+            "#,
+        )
+        .unwrap();
+        let plan = plan(
+            config,
+            ResolveOptions::new(Path::new("network.toml"), KeyStore::Skip),
+        )
+        .unwrap();
+        let launched = [LaunchedNode {
+            name: "alpha".to_owned(),
+            instance: Instance::Container("0123456789abcdef0123".to_owned()),
+        }];
+
+        let table = summary(&plan, &launched).render(false);
+        let lines = table.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0].split_whitespace().collect::<Vec<_>>(),
+            ["NAME", "CLIENT", "STATUS", "CONTAINER", "PORTS"]
+        );
+
+        let alpha = lines[1].split_whitespace().collect::<Vec<_>>();
+        assert_eq!(alpha[..4], ["alpha", "ream", "running", "0123456789ab"]);
+        assert!(lines[1].contains(":5052->"));
 
-        Ok(())
+        let beta = lines[2].split_whitespace().collect::<Vec<_>>();
+        assert_eq!(beta[..4], ["beta", "ream", "skipped", "-"]);
     }
 }
@@ -0,0 +1,327 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use bollard::{Docker, models::ContainerSummary, query_parameters::ListContainersOptionsBuilder};
+use clap::{Args, ValueEnum};
+use color_eyre::{Result, owo_colors::AnsiColors};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    codespan::report_config_errors,
+    config::{
+        ResolveOptions, ResolvedNetworkConfig, ResolvedNodeConfig, find_config, load_network_config,
+    },
+    container::{NETWORK_LABEL, NODE_LABEL},
+    docker::{DockerArgs, DockerError},
+    output,
+    table::{Cell, Table, stdout_supports_color},
+    validator::KeyStore,
+};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum StatusFormat {
+    /// Aligned table, colored when printed to terminal.
+    Table,
+    Json,
+    Yaml,
+}
+
+/// State of a single node of the network.
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeStatus {
+    pub name: String,
+    pub client: String,
+
+    /// Id of node container, `None` if there is no container.
+    pub container_id: Option<String>,
+
+    /// Container state, e.g. `running`, or `missing` if there is no
+    /// container.
+    pub state: String,
+
+    /// Result of container health check, if client defines one.
+    pub health: Option<String>,
+
+    /// Published ports, as `host_ip:host_port->container_port`.
+    pub ports: Vec<String>,
+
+    pub ip: Option<String>,
+    pub group: Option<String>,
+
+    /// Number of validators, handled by the node.
+    pub validators: usize,
+}
+
+impl NodeStatus {
+    fn new(name: &str, node: &ResolvedNodeConfig, container: Option<&ContainerSummary>) -> Self {
+        let client = node.client.kind().to_string();
+        let group = node.group.clone();
+        let validators = node.validators.len();
+
+        let Some(container) = container else {
+            return Self {
+                name: name.to_owned(),
+                client,
+                container_id: None,
+                state: "missing".to_owned(),
+                health: None,
+                ports: Vec::new(),
+                ip: None,
+                group,
+                validators,
+            };
+        };
+
+        let ports = published_ports(container);
+
+        let ip = container
+            .network_settings
+            .as_ref()
+            .and_then(|settings| settings.networks.as_ref())
+            .and_then(|networks| {
+                networks
+                    .values()
+                    .filter_map(|network| network.ip_address.clone())
+                    .find(|ip| !ip.is_empty())
+            });
+
+        Self {
+            name: name.to_owned(),
+            client,
+            container_id: container.id.clone(),
+            state: container
+                .state
+                .map_or_else(|| "unknown".to_owned(), |state| state.to_string()),
+            health: container
+                .health
+                .as_ref()
+                .and_then(|health| health.status)
+                .map(|status| status.to_string()),
+            ports,
+            ip,
+            group,
+            validators,
+        }
+    }
+}
+
+/// Color of container `state` in tables.
+pub fn state_color(state: &str) -> AnsiColors {
+    match state {
+        "running" => AnsiColors::Green,
+        "created" | "restarting" | "paused" => AnsiColors::Yellow,
+        _ => AnsiColors::Red,
+    }
+}
+
+/// Published ports of `container`, as `host_ip:host_port->container_port`.
+pub fn published_ports(container: &ContainerSummary) -> Vec<String> {
+    container
+        .ports
+        .iter()
+        .flatten()
+        .filter_map(|port| {
+            Some(format!(
+                "{}:{}->{}",
+                port.ip.as_deref().unwrap_or("0.0.0.0"),
+                port.public_port?,
+                port.private_port
+            ))
+        })
+        .collect()
+}
+
+/// Status of every node of `network`, in declaration order.
+pub async fn gather(
+    docker: &Docker,
+    network: &ResolvedNetworkConfig,
+) -> Result<Vec<NodeStatus>, DockerError> {
+    let filters = HashMap::from([("label", vec![format!("{NETWORK_LABEL}={}", network.name())])]);
+    let containers = docker
+        .list_containers(Some(
+            ListContainersOptionsBuilder::default()
+                .all(true)
+                .filters(&filters)
+                .build(),
+        ))
+        .await
+        .map_err(|err| DockerError::request("listing containers", err))?;
+
+    let by_node = containers
+        .iter()
+        .filter_map(|container| {
+            let node = container.labels.as_ref()?.get(NODE_LABEL)?;
+            Some((node.as_str(), container))
+        })
+        .collect::<HashMap<_, _>>();
+
+    Ok(network
+        .nodes()
+        .iter()
+        .map(|(name, node)| NodeStatus::new(name, node, by_node.get(name.as_str()).copied()))
+        .collect())
+}
+
+fn render_table(statuses: &[NodeStatus]) -> Table {
+    let mut table = Table::new(vec![
+        "name",
+        "client",
+        "state",
+        "health",
+        "validators",
+        "ports",
+        "ip",
+    ]);
+    for status in statuses {
+        let ports = if status.ports.is_empty() {
+            "-".to_owned()
+        } else {
+            status.ports.join(", ")
+        };
+
+        table.push(vec![
+            Cell::new(&status.name),
+            Cell::new(&status.client),
+            Cell::colored(&status.state, state_color(&status.state)),
+            Cell::new(status.health.as_deref().unwrap_or("-")),
+            Cell::new(status.validators),
+            Cell::new(ports),
+            Cell::new(status.ip.as_deref().unwrap_or("-")),
+        ]);
+    }
+
+    table
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct StatusCommand {
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Network to show, required when config defines multiple networks.
+    #[arg(long)]
+    network: Option<String>,
+
+    /// Show only nodes of this group.
+    #[arg(long)]
+    group: Option<String>,
+
+    #[arg(long, value_enum, default_value = "table")]
+    format: StatusFormat,
+
+    /// Shorthand for `--format json`.
+    #[arg(long, conflicts_with = "format")]
+    json: bool,
+}
+
+impl StatusCommand {
+    pub async fn run(&self, docker_args: &DockerArgs) -> Result<()> {
+        let config_path = find_config(self.config.as_deref());
+        let (config, sources) =
+            load_network_config(&config_path, None, self.network.as_deref()).await?;
+
+        let resolved = match config.resolve(ResolveOptions::new(&config_path, KeyStore::Skip)) {
+            Ok(value) => value,
+            Err(errors) => report_config_errors(&sources, errors),
+        };
+
+        let docker = docker_args.connect().await?;
+        let mut statuses = gather(&docker, &resolved).await?;
+        if self.group.is_some() {
+            statuses.retain(|status| status.group == self.group);
+        }
+
+        if output::is_json() {
+            output::success(json!({ "network": resolved.name(), "nodes": statuses }));
+            return Ok(());
+        }
+
+        let format = if self.json {
+            StatusFormat::Json
+        } else {
+            self.format
+        };
+
+        match format {
+            StatusFormat::Table => print!(
+                "{}",
+                render_table(&statuses).render(stdout_supports_color())
+            ),
+            StatusFormat::Json => println!("{}", serde_json::to_string_pretty(&statuses)?),
+            StatusFormat::Yaml => print!("{}", serde_yaml::to_string(&statuses)?),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bollard::API_DEFAULT_VERSION;
+
+    use super::*;
+    use crate::testing::{MockServer, resolve};
+
+    #[tokio::test]
+    async fn json_has_object_per_node() {
+        let network = resolve(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            name = "alpha"
+            client = "ream"
+            group = "honest"
+
+            [[node]]
+            name = "beta"
+            client = "ream"
+            "#,
+        )
+        .unwrap();
+
+        let server = MockServer::start(vec![(
+            200,
+            r#"[{
+                "Id": "0123456789ab",
+                "Labels": {"liesas.network": "devnet", "liesas.node": "alpha"},
+                "State": "running",
+                "Ports": [{"IP": "127.0.0.1", "PrivatePort": 5052, "PublicPort": 5052, "Type": "tcp"}],
+                "NetworkSettings": {"Networks": {"devnet": {"IPAddress": "172.18.0.2"}}}
+            }]"#,
+        )])
+        .await;
+        let docker = Docker::connect_with_http(&server.url(""), 5, API_DEFAULT_VERSION).unwrap();
+
+        let statuses = gather(&docker, &network).await.unwrap();
+        let json = serde_json::to_value(&statuses).unwrap();
+
+        assert_eq!(
+            json,
+            json!([
+                {
+                    "name": "alpha",
+                    "client": "ream",
+                    "container_id": "0123456789ab",
+                    "state": "running",
+                    "health": null,
+                    "ports": ["127.0.0.1:5052->5052"],
+                    "ip": "172.18.0.2",
+                    "group": "honest",
+                    "validators": 1,
+                },
+                {
+                    "name": "beta",
+                    "client": "ream",
+                    "container_id": null,
+                    "state": "missing",
+                    "health": null,
+                    "ports": [],
+                    "ip": null,
+                    "group": null,
+                    "validators": 1,
+                },
+            ])
+        );
+    }
+}
@@ -0,0 +1,276 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use bollard::{
+    Docker,
+    query_parameters::{ListContainersOptionsBuilder, ListNetworksOptionsBuilder},
+};
+use clap::Args;
+use color_eyre::{
+    Result,
+    eyre::{Context as _, bail},
+};
+use serde_json::json;
+
+use crate::{
+    codespan::report_config_errors,
+    config::{
+        ResolveOptions, container_name, docker_network_name, find_config, load_network_config,
+    },
+    container::{GROUP_LABEL, MANAGED_LABEL, NETWORK_LABEL, NODE_LABEL},
+    docker::{DockerArgs, DockerError, remove_container, remove_network_if_unused},
+    output, process,
+    prompt::ConfirmArgs,
+    validator::KeyStore,
+};
+
+/// Names of all containers, started by liesas, in any network.
+async fn managed_containers(docker: &Docker) -> Result<Vec<String>, DockerError> {
+    let filters = HashMap::from([("label", vec![format!("{MANAGED_LABEL}=true")])]);
+    let containers = docker
+        .list_containers(Some(
+            ListContainersOptionsBuilder::default()
+                .all(true)
+                .filters(&filters)
+                .build(),
+        ))
+        .await
+        .map_err(|err| DockerError::request("listing containers", err))?;
+
+    Ok(containers
+        .iter()
+        .filter_map(|container| container.names.as_ref()?.first())
+        .map(|name| name.trim_start_matches('/').to_owned())
+        .collect())
+}
+
+/// Nodes of `network`, as labelled on their containers, optionally only of
+/// `group`. Unlike config, labels also cover replicas added by `scale`.
+async fn network_containers(
+    docker: &Docker,
+    network: &str,
+    group: Option<&str>,
+) -> Result<Vec<String>, DockerError> {
+    let mut labels = vec![format!("{NETWORK_LABEL}={network}")];
+    if let Some(group) = group {
+        labels.push(format!("{GROUP_LABEL}={group}"));
+    }
+    let filters = HashMap::from([("label", labels)]);
+    let containers = docker
+        .list_containers(Some(
+            ListContainersOptionsBuilder::default()
+                .all(true)
+                .filters(&filters)
+                .build(),
+        ))
+        .await
+        .map_err(|err| DockerError::request("listing containers", err))?;
+
+    Ok(containers
+        .iter()
+        .filter_map(|container| container.labels.as_ref()?.get(NODE_LABEL).cloned())
+        .collect())
+}
+
+/// Names of all docker networks, created by liesas.
+pub async fn managed_networks(docker: &Docker) -> Result<Vec<String>, DockerError> {
+    let filters = HashMap::from([("label", vec![format!("{MANAGED_LABEL}=true")])]);
+    let networks = docker
+        .list_networks(Some(
+            ListNetworksOptionsBuilder::default()
+                .filters(&filters)
+                .build(),
+        ))
+        .await
+        .map_err(|err| DockerError::request("listing networks", err))?;
+
+    Ok(networks
+        .into_iter()
+        .filter_map(|network| network.name)
+        .collect())
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct StopCommand {
+    #[arg(long, conflicts_with = "all")]
+    config: Option<PathBuf>,
+
+    /// Network to stop, required when config defines multiple networks.
+    #[arg(long, conflicts_with = "all")]
+    network: Option<String>,
+
+    /// Stop only nodes of this group.
+    #[arg(long, conflicts_with = "all")]
+    group: Option<String>,
+
+    /// Stop every container and local process, started by liesas, regardless
+    /// of config.
+    #[arg(long)]
+    all: bool,
+
+    #[command(flatten)]
+    confirm: ConfirmArgs,
+}
+
+impl StopCommand {
+    /// Name of the network, and names of its nodes, dependents before their
+    /// dependencies.
+    async fn network_nodes(&self, config_path: &Path) -> Result<(String, Vec<String>)> {
+        let (config, sources) =
+            load_network_config(config_path, None, self.network.as_deref()).await?;
+
+        let resolved = match config.resolve(ResolveOptions::new(config_path, KeyStore::Skip)) {
+            Ok(value) => value,
+            Err(errors) => report_config_errors(&sources, errors),
+        };
+
+        let names = resolved
+            .launch_order()
+            .iter()
+            .rev()
+            .filter(|name| self.group.is_none() || resolved.nodes()[*name].group == self.group)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if let Some(group) = &self.group
+            && names.is_empty()
+        {
+            bail!("network has no nodes in group `{group}`");
+        }
+
+        Ok((resolved.name().to_owned(), names))
+    }
+
+    pub async fn run(&self, docker_args: &DockerArgs) -> Result<()> {
+        // Resolve config before connecting, so that config errors are
+        // reported even without docker.
+        let nodes = if self.all {
+            None
+        } else {
+            let config_path = find_config(self.config.as_deref());
+            Some(self.network_nodes(&config_path).await?)
+        };
+
+        let docker = docker_args.connect().await?;
+        let retry = docker_args.retry_policy();
+
+        // Docker networks are removed once their last container is.
+        let networks = match &nodes {
+            Some((network, _)) => vec![docker_network_name(network)],
+            None => managed_networks(&docker).await?,
+        };
+
+        // Local processes are recorded per network, while containers are
+        // found by their name.
+        let targets = match nodes {
+            Some((network, nodes)) => {
+                // Containers, which config doesn't know about, are replicas,
+                // so they are stopped before nodes they depend on.
+                let mut targets = network_containers(&docker, &network, self.group.as_deref())
+                    .await?
+                    .into_iter()
+                    .filter(|node| !nodes.contains(node))
+                    .collect::<Vec<_>>();
+                targets.sort();
+                targets.extend(nodes);
+                targets
+                    .into_iter()
+                    .map(|node| (Some(network.clone()), node))
+                    .collect::<Vec<_>>()
+            }
+            None => {
+                let mut targets = managed_containers(&docker)
+                    .await?
+                    .into_iter()
+                    .map(|node| (None, node))
+                    .collect::<Vec<_>>();
+                targets.extend(
+                    process::tracked()
+                        .wrap_err("failed to list local processes")?
+                        .into_iter()
+                        .map(|(network, node)| (Some(network), node)),
+                );
+                targets
+            }
+        };
+
+        if self.all
+            && !targets.is_empty()
+            && !self
+                .confirm
+                .confirm(&format!("stop and remove {} container(s)", targets.len()))?
+        {
+            return Ok(());
+        }
+
+        let mut stopped = Vec::new();
+        let mut absent = Vec::new();
+
+        for (network, name) in targets {
+            let removed = match &network {
+                Some(network) if self.all => process::terminate(network, &name).await?,
+                Some(network) => {
+                    remove_container(&docker, retry, &container_name(network, &name)).await?
+                        || process::terminate(network, &name).await?
+                }
+                None => remove_container(&docker, retry, &name).await?,
+            };
+
+            if removed {
+                if !output::is_json() {
+                    println!("stopped {name}");
+                }
+                stopped.push(name);
+            } else {
+                if !output::is_json() {
+                    println!("{name} is not running");
+                }
+                absent.push(name);
+            }
+        }
+
+        for network in &networks {
+            if remove_network_if_unused(&docker, retry, network).await? && !output::is_json() {
+                println!("removed network {network}");
+            }
+        }
+
+        if output::is_json() {
+            output::success(json!({ "stopped": stopped, "absent": absent }));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bollard::API_DEFAULT_VERSION;
+
+    use super::*;
+    use crate::testing::MockServer;
+
+    #[tokio::test]
+    async fn replicas_are_found_by_network_label() {
+        let server = MockServer::start(vec![(
+            200,
+            r#"[
+                {"Id": "1", "Labels": {"liesas.network": "devnet", "liesas.node": "ream"}},
+                {"Id": "2", "Labels": {"liesas.network": "devnet", "liesas.node": "ream_1"}}
+            ]"#,
+        )])
+        .await;
+        let docker = Docker::connect_with_http(&server.url(""), 5, API_DEFAULT_VERSION).unwrap();
+
+        let nodes = network_containers(&docker, "devnet", Some("honest"))
+            .await
+            .unwrap();
+
+        assert_eq!(nodes, ["ream", "ream_1"]);
+        let request = server.request(0);
+        assert!(request.contains("liesas.network%3ddevnet"), "{request}");
+        assert!(request.contains("liesas.group%3dhonest"), "{request}");
+    }
+}
@@ -0,0 +1,65 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use color_eyre::Result;
+use serde_json::json;
+
+use crate::{
+    codespan::{report_config_errors, report_config_warnings},
+    config::{ResolveOptions, check, find_config, load_network_config, plan},
+    output,
+    validator::KeyStore,
+};
+
+#[derive(Debug, Clone, Args)]
+pub struct ValidateCommand {
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Network to validate, required when config defines multiple networks.
+    #[arg(long)]
+    network: Option<String>,
+
+    /// Shorthand for `--output json`.
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl ValidateCommand {
+    pub async fn run(&self) -> Result<()> {
+        let config_path = find_config(self.config.as_deref());
+        let (config, sources) =
+            load_network_config(&config_path, None, self.network.as_deref()).await?;
+
+        report_config_warnings(&sources, &check(&config));
+
+        // Keys are not needed to check the config, and generating them is
+        // slow.
+        let plan = match plan(config, ResolveOptions::new(&config_path, KeyStore::Skip)) {
+            Ok(value) => value,
+            Err(errors) => report_config_errors(&sources, errors),
+        };
+
+        let nodes = plan.nodes.len();
+        let validators = plan
+            .nodes
+            .iter()
+            .map(|node| node.validators.len())
+            .sum::<usize>();
+
+        if output::is_json() {
+            output::success(json!({
+                "network": plan.network.name(),
+                "nodes": nodes,
+                "validators": validators,
+            }));
+        } else {
+            println!(
+                "network `{}` is valid: {nodes} node(s), {validators} validator(s)",
+                plan.network.name()
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+
+use indexmap::IndexMap;
+use serde::Serialize;
+
+use crate::{
+    config::NetworkPlan,
+    container::{ContainerSpec, PublishedPort, RestartPolicy, Volume},
+};
+
+#[derive(Debug, Clone, Serialize)]
+struct ComposeUlimit {
+    soft: i64,
+    hard: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Service {
+    image: String,
+
+    container_name: String,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    command: Vec<String>,
+
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    labels: BTreeMap<String, String>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    ports: Vec<String>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    volumes: Vec<String>,
+
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    environment: BTreeMap<String, String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    working_dir: Option<String>,
+
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    ulimits: BTreeMap<String, ComposeUlimit>,
+
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    sysctls: BTreeMap<String, String>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    extra_hosts: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cpus: Option<f64>,
+
+    /// Memory limit, in bytes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mem_limit: Option<u64>,
+
+    stop_grace_period: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    restart: Option<String>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    depends_on: Vec<String>,
+}
+
+impl Service {
+    fn new(spec: &ContainerSpec, depends_on: Vec<String>) -> Self {
+        Self {
+            image: spec.image.clone(),
+            container_name: spec.name.clone(),
+            command: spec.args.clone(),
+            labels: spec.labels.clone(),
+            ports: spec.ports.iter().map(PublishedPort::to_publish).collect(),
+            volumes: spec.volumes.iter().map(Volume::to_bind).collect(),
+            environment: spec.env.clone(),
+            working_dir: spec
+                .working_dir
+                .as_ref()
+                .map(|dir| dir.display().to_string()),
+            ulimits: spec
+                .ulimits
+                .iter()
+                .map(|(name, limit)| {
+                    (
+                        name.clone(),
+                        ComposeUlimit {
+                            soft: limit.soft(),
+                            hard: limit.hard(),
+                        },
+                    )
+                })
+                .collect(),
+            sysctls: spec.sysctls.clone(),
+            extra_hosts: spec.extra_hosts.clone(),
+            cpus: spec.cpus,
+            mem_limit: spec.memory,
+            stop_grace_period: humantime::format_duration(spec.stop_timeout).to_string(),
+            restart: (spec.restart_policy != RestartPolicy::No)
+                .then(|| spec.restart_policy.to_string()),
+            depends_on,
+        }
+    }
+}
+
+/// `docker-compose.yml`, with one service per containerized node, named
+/// after the node.
+#[derive(Debug, Clone, Serialize)]
+pub struct ComposeFile {
+    name: String,
+    services: IndexMap<String, Service>,
+}
+
+impl ComposeFile {
+    /// Services are listed in launch order. Nodes running local binary can't
+    /// be expressed in compose, so they are left out, and returned
+    /// separately.
+    pub fn new(plan: &NetworkPlan) -> (Self, Vec<String>) {
+        let mut services = IndexMap::new();
+        let mut skipped = Vec::new();
+
+        for node in &plan.nodes {
+            let Some(spec) = &node.container else {
+                skipped.push(node.name.clone());
+                continue;
+            };
+
+            let depends_on = plan.network.nodes()[&node.name]
+                .depends_on
+                .iter()
+                .filter(|dependency| services.contains_key(*dependency))
+                .cloned()
+                .collect();
+
+            services.insert(node.name.clone(), Service::new(spec, depends_on));
+        }
+
+        let file = Self {
+            name: plan.network.name().to_owned(),
+            services,
+        };
+
+        (file, skipped)
+    }
+
+    pub fn to_yaml(&self) -> Result<String, serde_yaml::Error> {
+        serde_yaml::to_string(self)
+    }
+}
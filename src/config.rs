@@ -1,14 +1,48 @@
+mod builder;
+mod document;
+mod interpolate;
+mod load;
+mod plan;
+
 use ariadne::{Label, ReportKind};
+use color_eyre::owo_colors::{OwoColorize, Stream};
+use indexmap::{IndexMap, IndexSet};
+use indicatif::ProgressBar;
 use leansig::serialization::Serializable;
-use serde::{Deserialize, Serialize};
+use rayon::prelude::*;
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+use semver::Version;
+use serde::{
+    Deserialize, Serialize,
+    de::{self, IgnoredAny, Visitor},
+};
+use std::borrow::Cow;
 use std::fmt::Debug;
+use std::net::{IpAddr, Ipv4Addr};
 use std::ops::Range;
-use std::{collections::HashMap, path::PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
 use thiserror::Error;
 use toml::Spanned;
+use tracing::{debug, info, trace};
+
+use crate::auth::{Registries, RegistryCredentials};
+use crate::client::{ClientKind, LogLevel, NodePorts};
+use crate::codespan::Sources;
+use crate::container::{CONFIG_DIR, HOST_GATEWAY, RestartPolicy, Volume};
+use crate::docker::REQUEST_TIMEOUT;
+use crate::exit::Failure;
+use crate::image::{ImageReference, ImageReferenceError};
+use crate::output;
+use crate::validator::{KeyStore, StoredKeypair, generate_random_keypair, generate_seeded_keypair};
+use strum::VariantNames as _;
 
-use crate::client::ClientKind;
-use crate::validator::generate_keypair;
+pub use builder::{NetworkConfigBuilder, NodeConfigBuilder};
+pub use load::{ConfigFormat, ParseError, STDIN_CONFIG, load_network_config};
+pub use plan::{ConfigWarning, NetworkPlan, NodePlan, check, plan};
 
 pub type Span = Range<usize>;
 
@@ -27,6 +61,51 @@ impl NodeNameSource {
     }
 }
 
+/// Whether `name` can be used as docker container name.
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars.next().is_some_and(|c| c.is_ascii_alphanumeric())
+        && !chars.as_str().is_empty()
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+/// Longest container name, which is still a valid hostname, so that nodes
+/// can reach each other by name.
+const MAX_CONTAINER_NAME_LEN: usize = 63;
+
+/// Name of the container, running `node` of `network`.
+///
+/// Network name is included, so that nodes of different networks with the
+/// same name don't clash.
+pub fn container_name(network: &str, node: &str) -> String {
+    format!("{network}-{node}")
+}
+
+/// Name of the docker network, containers of `network` are attached to.
+///
+/// Prefixed, so that it doesn't clash with predefined networks, like `host`.
+pub fn docker_network_name(network: &str) -> String {
+    format!("liesas-{network}")
+}
+
+/// Closest valid container name to `name`, if there is one.
+fn sanitize_name(name: &str) -> Option<String> {
+    let sanitized = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    let sanitized = sanitized.trim_start_matches(|c: char| !c.is_ascii_alphanumeric());
+
+    is_valid_name(sanitized).then(|| sanitized.to_owned())
+}
+
 #[derive(Debug, Clone)]
 pub enum NodeNameDefinition {
     Singular(NodeNameSource),
@@ -37,160 +116,1058 @@ pub enum NodeNameDefinition {
     },
 }
 
+impl NodeNameDefinition {
+    /// Index of the replica, which got `name` generated from this prefix.
+    fn replica_index<'a>(&self, name: &'a str) -> Option<&'a str> {
+        match self {
+            Self::Singular(_) => None,
+            Self::Prefix { prefix, .. } => name.strip_prefix(prefix)?.strip_prefix('_'),
+        }
+    }
+
+    /// Prefix, which generated the name, when the other definition is a
+    /// singular one, i.e. when explicit name collides with a generated one.
+    fn collides_with_prefix<'a>(&'a self, other: &'a Self) -> Option<&'a str> {
+        match (self, other) {
+            (Self::Prefix { prefix, .. }, Self::Singular(_))
+            | (Self::Singular(_), Self::Prefix { prefix, .. }) => Some(prefix),
+            _ => None,
+        }
+    }
+
+    /// Labels, pointing to where `name` comes from, for duplicate name
+    /// reports.
+    fn labels(&self, name: &str, previous: bool) -> Vec<(Span, String)> {
+        let what = if previous {
+            "first defined"
+        } else {
+            "defined again"
+        };
+
+        match self {
+            Self::Singular(NodeNameSource::Name(span)) => {
+                vec![(span.clone(), format!("`{name}` {what} here"))]
+            }
+            Self::Singular(NodeNameSource::Kind(span)) => vec![(
+                span.clone(),
+                format!("`{name}` {what} here, derived from client kind"),
+            )],
+            Self::Prefix {
+                prefix,
+                prefix_span,
+                count_span,
+            } => {
+                let origin = match prefix_span {
+                    NodeNameSource::Name(_) => "",
+                    NodeNameSource::Kind(_) => " (derived from client kind)",
+                };
+
+                vec![
+                    (
+                        prefix_span.span(),
+                        format!("`{name}` {what} here, generated from prefix `{prefix}`{origin}"),
+                    ),
+                    (
+                        count_span.clone(),
+                        format!("`count` makes `{prefix}` a prefix"),
+                    ),
+                ]
+            }
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum ConfigError {
     #[error("count cannot equal to zero")]
     InvalidCount(Span),
 
-    #[error("the name `{name}` is defined multiple times")]
+    #[error("validator count cannot equal to zero")]
+    InvalidValidatorCount(Span),
+
+    #[error(
+        "the name `{name}` is defined multiple times{}",
+        match curr_def.collides_with_prefix(prev_def) {
+            Some(prefix) => format!(", once explicitly and once generated from prefix `{prefix}`"),
+            None => String::new(),
+        }
+    )]
     DuplicateName {
         name: String,
         curr_def: NodeNameDefinition,
         prev_def: NodeNameDefinition,
     },
+
+    #[error("config requires liesas >= {required}")]
+    UnsupportedVersion { required: Version, span: Span },
+
+    #[error("`{field}` of {value} exceeds limit of {limit}")]
+    LimitExceeded {
+        field: &'static str,
+        value: u64,
+        limit: u64,
+        span: Span,
+    },
+
+    #[error("network has {total} validators, exceeding `--max-validators` of {limit}")]
+    TooManyValidators {
+        total: u64,
+        limit: u64,
+        shares: Vec<ValidatorShare>,
+    },
+
+    #[error("`{field}` cannot equal to zero")]
+    InvalidTiming { field: &'static str, span: Span },
+
+    #[error("invalid duration `{value}`: {error}")]
+    InvalidDuration {
+        value: String,
+        error: humantime::DurationError,
+        span: Span,
+    },
+
+    #[error("`{field}` of {value} is out of range")]
+    DurationOutOfRange {
+        field: &'static str,
+        value: String,
+        min: Duration,
+        max: Duration,
+        span: Span,
+    },
+
+    #[error("invalid key lifetime: {reason}")]
+    InvalidKeyLifetime { reason: String, span: Span },
+
+    #[error("`genesis_time` and `genesis_delay` cannot be set at the same time")]
+    ConflictingGenesis { time_span: Span, delay_span: Span },
+
+    #[error("cannot run {path:?}: {reason}")]
+    InvalidBinary {
+        path: PathBuf,
+        reason: String,
+        span: Span,
+    },
+
+    #[error("working directory of container must be an absolute path")]
+    RelativeWorkingDir(Span),
+
+    #[error("node has neither container image nor binary to run")]
+    NothingToRun(Span),
+
+    #[error("invalid volume `{value}`: {reason}")]
+    InvalidVolume {
+        value: String,
+        reason: &'static str,
+        span: Span,
+    },
+
+    #[error("volume target `{target}` is mounted more than once")]
+    VolumeConflict {
+        target: String,
+        span: Span,
+        /// Span of the other volume, `None` if target is reserved by liesas.
+        prev_span: Option<Span>,
+    },
+
+    #[error("invalid peer `{value}`: {reason}")]
+    InvalidPeer {
+        value: String,
+        reason: &'static str,
+        span: Span,
+    },
+
+    #[error("health path `{value}` must start with `/`")]
+    InvalidHealthPath { value: String, span: Span },
+
+    #[error("invalid checkpoint sync url `{value}`: {reason}")]
+    InvalidCheckpointSyncUrl {
+        value: String,
+        reason: String,
+        span: Span,
+    },
+
+    #[error("invalid environment variable name `{name}`")]
+    InvalidEnvName { name: String, span: Span },
+
+    #[error("invalid memory limit `{value}`")]
+    InvalidMemory { value: String, span: Span },
+
+    #[error("`cpus` must be positive, got {value}")]
+    InvalidCpus { value: f64, span: Span },
+
+    #[error("`image_tag` only applies to default image")]
+    MisplacedImageTag(Span),
+
+    #[error("invalid image reference: {error}")]
+    InvalidImage {
+        error: ImageReferenceError,
+        span: Span,
+    },
+
+    #[error("failed to read secret from {path:?}: {error}")]
+    SecretFile {
+        path: PathBuf,
+        error: std::io::Error,
+        span: Span,
+    },
+
+    #[error("environment variable `{name}` with secret is not set")]
+    SecretEnv { name: String, span: Span },
+
+    #[error("failed to write secret to {path:?}: {error}")]
+    WriteSecret {
+        path: PathBuf,
+        error: std::io::Error,
+        span: Span,
+    },
+
+    #[error("failed to write config file to {path:?}: {error}")]
+    WriteConfigFile {
+        path: PathBuf,
+        error: std::io::Error,
+        span: Span,
+    },
+
+    #[error("`{address}` is not a valid IP address")]
+    InvalidBindAddress { address: String, span: Span },
+
+    #[error("unknown restart policy `{value}`")]
+    UnknownRestartPolicy { value: String, span: Span },
+
+    #[error("unknown log level `{value}`")]
+    UnknownLogLevel { value: String, span: Span },
+
+    #[error("unknown ulimit `{name}`")]
+    UnknownUlimit { name: String, span: Span },
+
+    #[error("failed to write validator keys to {path:?}: {error}")]
+    KeyStore {
+        path: PathBuf,
+        error: std::io::Error,
+        span: Span,
+    },
+
+    #[error("`{field}` of {base} + {offset} is not a valid port")]
+    PortOutOfRange {
+        field: &'static str,
+        base: u16,
+        offset: u64,
+        span: Span,
+    },
+
+    #[error("port {port} is used by both `{node}` and `{prev_node}`")]
+    PortConflict {
+        port: u16,
+        node: String,
+        field: &'static str,
+        span: Span,
+        prev_node: String,
+        prev_field: &'static str,
+        prev_span: Span,
+    },
+
+    #[error("`{name}` is not a valid container name")]
+    InvalidName { name: String, span: Span },
+
+    #[error("container name `{name}` is longer than {limit} characters")]
+    NameTooLong {
+        name: String,
+        limit: usize,
+        span: Span,
+    },
+
+    #[error("variable `{name}`, referenced in config, is not set")]
+    UndefinedVariable { name: String, span: Span },
+
+    #[error("invalid variable reference")]
+    InvalidInterpolation(Span),
+
+    #[error("failed to read included config {path:?}: {error}")]
+    IncludeFile {
+        path: PathBuf,
+        error: std::io::Error,
+        span: Span,
+    },
+
+    #[error("config includes itself: {}", cycle.iter().map(|path| format!("{path:?}")).collect::<Vec<_>>().join(" -> "))]
+    IncludeCycle { cycle: Vec<PathBuf>, span: Span },
+
+    #[error("unknown dependency `{name}`")]
+    UnknownDependency { name: String, span: Span },
+
+    #[error("dependency cycle: {}", cycle.join(" -> "))]
+    DependencyCycle { cycle: Vec<String>, span: Span },
 }
 
 impl ConfigError {
     pub fn span(&self) -> Span {
         match self {
             Self::InvalidCount(span) => span.clone(),
+            Self::InvalidValidatorCount(span) => span.clone(),
             Self::DuplicateName { curr_def, .. } => match curr_def {
                 NodeNameDefinition::Singular(source) => source.span(),
                 NodeNameDefinition::Prefix { prefix_span, .. } => prefix_span.span(),
             },
+            Self::UnsupportedVersion { span, .. } => span.clone(),
+            Self::LimitExceeded { span, .. } => span.clone(),
+            Self::TooManyValidators { shares, .. } => shares
+                .first()
+                .map_or(0..0, |share| share.validator_count.span()),
+            Self::InvalidTiming { span, .. } => span.clone(),
+            Self::InvalidDuration { span, .. } => span.clone(),
+            Self::DurationOutOfRange { span, .. } => span.clone(),
+            Self::InvalidKeyLifetime { span, .. } => span.clone(),
+            Self::ConflictingGenesis { delay_span, .. } => delay_span.clone(),
+            Self::InvalidBinary { span, .. } => span.clone(),
+            Self::RelativeWorkingDir(span) => span.clone(),
+            Self::NothingToRun(span) => span.clone(),
+            Self::InvalidVolume { span, .. } => span.clone(),
+            Self::VolumeConflict { span, .. } => span.clone(),
+            Self::InvalidPeer { span, .. } => span.clone(),
+            Self::InvalidHealthPath { span, .. } => span.clone(),
+            Self::InvalidCheckpointSyncUrl { span, .. } => span.clone(),
+            Self::InvalidEnvName { span, .. } => span.clone(),
+            Self::InvalidMemory { span, .. } => span.clone(),
+            Self::InvalidCpus { span, .. } => span.clone(),
+            Self::MisplacedImageTag(span) => span.clone(),
+            Self::InvalidImage { span, .. } => span.clone(),
+            Self::SecretFile { span, .. } => span.clone(),
+            Self::SecretEnv { span, .. } => span.clone(),
+            Self::WriteSecret { span, .. } => span.clone(),
+            Self::WriteConfigFile { span, .. } => span.clone(),
+            Self::InvalidBindAddress { span, .. } => span.clone(),
+            Self::UnknownUlimit { span, .. } => span.clone(),
+            Self::UnknownRestartPolicy { span, .. } => span.clone(),
+            Self::UnknownLogLevel { span, .. } => span.clone(),
+            Self::KeyStore { span, .. } => span.clone(),
+            Self::PortOutOfRange { span, .. } => span.clone(),
+            Self::PortConflict { span, .. } => span.clone(),
+            Self::InvalidName { span, .. } => span.clone(),
+            Self::NameTooLong { span, .. } => span.clone(),
+            Self::UndefinedVariable { span, .. } => span.clone(),
+            Self::InvalidInterpolation(span) => span.clone(),
+            Self::IncludeFile { span, .. } => span.clone(),
+            Self::IncludeCycle { span, .. } => span.clone(),
+            Self::UnknownDependency { span, .. } => span.clone(),
+            Self::DependencyCycle { span, .. } => span.clone(),
         }
     }
 
-    pub fn report(&self, file: PathBuf) -> ariadne::Report<'_, (String, Span)> {
-        let file = file.display().to_string();
-        let mut builder = ariadne::Report::build(ReportKind::Error, (file.clone(), self.span()));
+    pub fn report(&self, sources: &Sources) -> ariadne::Report<'_, (String, Span)> {
+        let mut builder = ariadne::Report::build(ReportKind::Error, sources.locate(self.span()))
+            .with_config(output::report_config());
 
         match self {
             Self::InvalidCount(span) => {
                 builder = builder
                     .with_message("Invalid node configuration")
                     .with_label(
-                        Label::new((file.clone(), span.clone()))
+                        Label::new(sources.locate(span.clone()))
                             .with_message("cannot equal to zero"),
+                    )
+                    .with_help(
+                        "to keep node in config, but not run it, set `allow_disabled = true` on the network",
                     );
             }
+            Self::InvalidValidatorCount(span) => {
+                builder = builder
+                    .with_message("Invalid node configuration")
+                    .with_label(
+                        Label::new(sources.locate(span.clone()))
+                            .with_message("cannot equal to zero"),
+                    )
+                    .with_note("node must host at least one validator");
+            }
             Self::DuplicateName {
                 name,
                 curr_def,
                 prev_def,
             } => {
-                builder =
-                    builder.with_message(format!("the name `{name}` is defined multiple times"));
-
-                match prev_def {
-                    NodeNameDefinition::Singular(kind) => {
-                        let (span, message) = match kind {
-                            NodeNameSource::Name(span) => {
-                                (span.clone(), format!("previous definition here"))
-                            }
-                            NodeNameSource::Kind(span) => (
-                                span.clone(),
-                                format!("previous definition derived from client kind here"),
-                            ),
-                        };
+                builder = builder.with_message(self.to_string());
 
+                for (def, previous) in [(prev_def, true), (curr_def, false)] {
+                    for (span, message) in def.labels(name, previous) {
                         builder = builder
-                            .with_label(Label::new((file.clone(), span)).with_message(message));
+                            .with_label(Label::new(sources.locate(span)).with_message(message));
                     }
-                    NodeNameDefinition::Prefix {
-                        prefix,
-                        prefix_span,
-                        count_span,
-                    } => {
-                        let (prefix_span, message) = match prefix_span {
-                            NodeNameSource::Name(span) => {
-                                (span.clone(), format!("prefix `{prefix}` defined here"))
-                            }
-                            NodeNameSource::Kind(span) => (
-                                span.clone(),
-                                format!("prefix `{prefix}` derived from client kind here"),
-                            ),
+
+                    if let Some(index) = def.replica_index(name) {
+                        let NodeNameDefinition::Prefix { prefix, .. } = def else {
+                            unreachable!("only prefixes generate names");
                         };
 
-                        builder = builder
-                            .with_label(
-                                Label::new((file.clone(), prefix_span)).with_message(message),
-                            )
-                            .with_label(
-                                Label::new((file.clone(), count_span.clone()))
-                                    .with_message("count defined here"),
-                            )
-                            .with_note(format!("the first generated name `{name}` comes from prefix `{prefix}_` and index {}", name.strip_prefix(&format!("{prefix}_")).unwrap_or("<unknown>")));
+                        builder = builder.with_note(format!(
+                            "nodes with `count` are named `<prefix>_<index>`, so `{name}` is replica {index} of prefix `{prefix}`"
+                        ));
                     }
                 }
 
-                match curr_def {
-                    NodeNameDefinition::Singular(kind) => {
-                        let (span, message) = match kind {
-                            NodeNameSource::Name(span) => {
-                                (span.clone(), format!("second definition appears here"))
-                            }
-                            NodeNameSource::Kind(span) => (
-                                span.clone(),
-                                format!("second definition appears here, derived from client"),
-                            ),
-                        };
+                if let Some(prefix) = curr_def.collides_with_prefix(prev_def) {
+                    builder = builder.with_help(format!(
+                        "rename node `{name}`, or use other prefix than `{prefix}`"
+                    ));
+                }
+            }
+            Self::UnsupportedVersion { required, span } => {
+                builder = builder
+                    .with_message(format!("config requires liesas >= {required}"))
+                    .with_label(
+                        Label::new(sources.locate(span.clone()))
+                            .with_message("version required here"),
+                    )
+                    .with_note(format!(
+                        "this binary is liesas {}, upgrade it to use this config",
+                        supported_version()
+                    ));
+            }
+            Self::LimitExceeded {
+                field,
+                value,
+                limit,
+                span,
+            } => {
+                builder = builder
+                    .with_message("Invalid node configuration")
+                    .with_label(
+                        Label::new(sources.locate(span.clone()))
+                            .with_message(format!("`{field}` of {value} exceeds limit of {limit}")),
+                    )
+                    .with_help(format!(
+                        "if this is intended, raise `max_{field}` on the network"
+                    ));
+            }
+            Self::TooManyValidators {
+                total,
+                limit,
+                shares,
+            } => {
+                builder = builder
+                    .with_message(format!(
+                        "Network has {total} validators, more than allowed {limit}"
+                    ))
+                    .with_labels(shares.iter().flat_map(|share| share.labels(sources)))
+                    .with_help("reduce `count` or `validator_count`, or raise `--max-validators`");
+            }
+            Self::InvalidTiming { field, span } => {
+                builder = builder
+                    .with_message("Invalid network configuration")
+                    .with_label(
+                        Label::new(sources.locate(span.clone()))
+                            .with_message(format!("`{field}` cannot equal to zero")),
+                    );
+            }
+            Self::InvalidDuration { value, error, span } => {
+                builder = builder
+                    .with_message(format!("Invalid duration `{value}`"))
+                    .with_label(
+                        Label::new(sources.locate(span.clone())).with_message(error.to_string()),
+                    )
+                    .with_note("durations are written like `30s`, `2m` or `1h 30m`");
+            }
+            Self::DurationOutOfRange {
+                field,
+                value,
+                min,
+                max,
+                span,
+            } => {
+                builder = builder
+                    .with_message(format!("Invalid `{field}`"))
+                    .with_label(
+                        Label::new(sources.locate(span.clone()))
+                            .with_message(format!("`{value}` is out of range")),
+                    )
+                    .with_note(format!(
+                        "must be between {} and {}",
+                        humantime::format_duration(*min),
+                        humantime::format_duration(*max)
+                    ));
+            }
+            Self::InvalidKeyLifetime { reason, span } => {
+                builder = builder
+                    .with_message("Invalid node configuration")
+                    .with_label(Label::new(sources.locate(span.clone())).with_message(reason))
+                    .with_note(format!(
+                        "keys are valid for epochs [activation_epoch, activation_epoch + num_active_epochs), which must fit into key lifetime of {KEY_LIFETIME} epochs"
+                    ));
+            }
+            Self::ConflictingGenesis {
+                time_span,
+                delay_span,
+            } => {
+                builder = builder
+                    .with_message("Genesis time is specified twice")
+                    .with_label(
+                        Label::new(sources.locate(time_span.clone()))
+                            .with_message("genesis is pinned here"),
+                    )
+                    .with_label(
+                        Label::new(sources.locate(delay_span.clone()))
+                            .with_message("but delay is also set here"),
+                    )
+                    .with_help("remove either `genesis_time` or `genesis_delay`");
+            }
+            Self::NothingToRun(span) => {
+                builder = builder
+                    .with_message("Invalid node configuration")
+                    .with_label(
+                        Label::new(sources.locate(span.clone()))
+                            .with_message("client has nothing to run"),
+                    )
+                    .with_help("set either `image` or `bin` of the client");
+            }
+            Self::RelativeWorkingDir(span) => {
+                builder = builder
+                    .with_message("Invalid node configuration")
+                    .with_label(
+                        Label::new(sources.locate(span.clone()))
+                            .with_message("must be an absolute path"),
+                    )
+                    .with_note("working directory of container is resolved inside it, so it cannot be relative to the config");
+            }
+            Self::InvalidVolume {
+                value,
+                reason,
+                span,
+            } => {
+                builder = builder
+                    .with_message(format!("Invalid volume `{value}`"))
+                    .with_label(Label::new(sources.locate(span.clone())).with_message(*reason))
+                    .with_note("volumes are written as `<host path>:<container path>`, optionally followed by `:ro` or `:rw`");
+            }
+            Self::VolumeConflict {
+                target,
+                span,
+                prev_span,
+            } => {
+                builder = builder
+                    .with_message(format!("`{target}` is mounted more than once"))
+                    .with_label(
+                        Label::new(sources.locate(span.clone()))
+                            .with_message("volume is mounted here"),
+                    );
 
-                        builder = builder
-                            .with_label(Label::new((file.clone(), span)).with_message(message));
-                    }
-                    NodeNameDefinition::Prefix {
-                        prefix,
-                        prefix_span,
-                        count_span,
-                    } => {
-                        let (prefix_span, message) = match prefix_span {
-                            NodeNameSource::Name(span) => {
-                                (span.clone(), format!("prefix `{prefix}` defined here"))
-                            }
-                            NodeNameSource::Kind(span) => (
-                                span.clone(),
-                                format!("prefix `{prefix}` derived from client kind here"),
-                            ),
-                        };
+                builder = match prev_span {
+                    Some(prev_span) => builder.with_label(
+                        Label::new(sources.locate(prev_span.clone()))
+                            .with_message("but this volume is already mounted there"),
+                    ),
+                    None => builder.with_note(format!(
+                        "`{CONFIG_DIR}` is reserved for keys, genesis and config, which liesas mounts itself"
+                    )),
+                };
+            }
+            Self::InvalidPeer {
+                value,
+                reason,
+                span,
+            } => {
+                builder = builder
+                    .with_message(format!("Invalid peer `{value}`"))
+                    .with_label(Label::new(sources.locate(span.clone())).with_message(*reason))
+                    .with_note("peers are multiaddrs, like `/ip4/10.0.0.1/udp/9000/quic-v1`, or ENRs, starting with `enr:`");
+            }
+            Self::InvalidHealthPath { value, span } => {
+                builder = builder
+                    .with_message(format!("Invalid health path `{value}`"))
+                    .with_label(
+                        Label::new(sources.locate(span.clone()))
+                            .with_message("must start with `/`"),
+                    )
+                    .with_note("health path is polled on rpc port of the node, e.g. `/health`");
+            }
+            Self::InvalidCheckpointSyncUrl {
+                value,
+                reason,
+                span,
+            } => {
+                builder = builder
+                    .with_message(format!("Invalid checkpoint sync url `{value}`"))
+                    .with_label(Label::new(sources.locate(span.clone())).with_message(reason))
+                    .with_note("checkpoint sync url is an http(s) url of a beacon node, e.g. `http://localhost:5052`");
+            }
+            Self::InvalidEnvName { name, span } => {
+                builder = builder
+                    .with_message(format!("Invalid environment variable name `{name}`"))
+                    .with_label(
+                        Label::new(sources.locate(span.clone()))
+                            .with_message("must be non-empty, without `=` and whitespace"),
+                    );
+            }
+            Self::InvalidMemory { value, span } => {
+                builder = builder
+                    .with_message(format!("Invalid memory limit `{value}`"))
+                    .with_label(
+                        Label::new(sources.locate(span.clone()))
+                            .with_message("expected number of bytes, with optional unit"),
+                    )
+                    .with_note(
+                        "memory is written like `512m` or `2g`, with `b`, `k`, `m` or `g` unit",
+                    );
+            }
+            Self::InvalidCpus { span, .. } => {
+                builder = builder
+                    .with_message("Invalid node configuration")
+                    .with_label(
+                        Label::new(sources.locate(span.clone())).with_message("must be positive"),
+                    )
+                    .with_note("`cpus` is number of cpu cores, node may use, e.g. `1.5`");
+            }
+            Self::MisplacedImageTag(span) => {
+                builder = builder
+                    .with_message("Invalid node configuration")
+                    .with_label(
+                        Label::new(sources.locate(span.clone()))
+                            .with_message("`image_tag` only applies to default image"),
+                    )
+                    .with_help("set the tag in `image` instead, or remove `image_tag`");
+            }
+            Self::InvalidImage { error, span } => {
+                builder = builder
+                    .with_message("Invalid image reference")
+                    .with_label(Label::new(sources.locate(span.clone())).with_message(error))
+                    .with_note("expected `[registry/]name[:tag][@digest]`");
+            }
+            Self::InvalidBinary { path, reason, span } => {
+                builder = builder
+                    .with_message(format!("cannot run {path:?}"))
+                    .with_label(Label::new(sources.locate(span.clone())).with_message(reason))
+                    .with_note(
+                        "relative paths are resolved against directory of the config, \
+                         and bare names are looked up in `PATH`",
+                    );
+            }
+            Self::SecretFile { path, error, span } => {
+                builder = builder
+                    .with_message(format!("failed to read secret from {path:?}"))
+                    .with_label(Label::new(sources.locate(span.clone())).with_message(error))
+                    .with_note("relative paths are resolved against directory of the config");
+            }
+            Self::SecretEnv { name, span } => {
+                builder = builder
+                    .with_message(format!(
+                        "environment variable `{name}` with secret is not set"
+                    ))
+                    .with_label(
+                        Label::new(sources.locate(span.clone()))
+                            .with_message("secret is read from here"),
+                    );
+            }
+            Self::WriteSecret { path, error, span } => {
+                builder = builder
+                    .with_message(format!("failed to write secret to {path:?}"))
+                    .with_label(
+                        Label::new(sources.locate(span.clone()))
+                            .with_message("secret of this node"),
+                    )
+                    .with_note(error);
+            }
+            Self::WriteConfigFile { path, error, span } => {
+                builder = builder
+                    .with_message(format!("failed to write config file to {path:?}"))
+                    .with_label(
+                        Label::new(sources.locate(span.clone()))
+                            .with_message("client of this node is configured by file"),
+                    )
+                    .with_note(error);
+            }
+            Self::InvalidBindAddress { address, span } => {
+                builder = builder
+                    .with_message(format!("`{address}` is not a valid IP address"))
+                    .with_label(
+                        Label::new(sources.locate(span.clone()))
+                            .with_message("expected IPv4 or IPv6 address"),
+                    );
+            }
+            Self::UnknownRestartPolicy { value, span } => {
+                builder = builder
+                    .with_message(format!("unknown restart policy `{value}`"))
+                    .with_label(
+                        Label::new(sources.locate(span.clone()))
+                            .with_message("unknown restart policy"),
+                    )
+                    .with_note(format!(
+                        "supported restart policies: {}",
+                        RestartPolicy::VARIANTS.join(", ")
+                    ));
+            }
+            Self::UnknownLogLevel { value, span } => {
+                builder = builder
+                    .with_message(format!("unknown log level `{value}`"))
+                    .with_label(
+                        Label::new(sources.locate(span.clone())).with_message("unknown log level"),
+                    )
+                    .with_note(format!(
+                        "supported log levels: {}",
+                        LogLevel::VARIANTS.join(", ")
+                    ));
+            }
+            Self::UnknownUlimit { name, span } => {
+                builder = builder
+                    .with_message(format!("unknown ulimit `{name}`"))
+                    .with_label(
+                        Label::new(sources.locate(span.clone())).with_message("unknown ulimit"),
+                    )
+                    .with_note(format!("supported ulimits: {}", ULIMITS.join(", ")));
+            }
+            Self::KeyStore { path, error, span } => {
+                builder = builder
+                    .with_message(format!("failed to write validator keys to {path:?}"))
+                    .with_label(
+                        Label::new(sources.locate(span.clone()))
+                            .with_message("while generating keys for this node"),
+                    )
+                    .with_note(error);
+            }
+            Self::UnknownDependency { name, span } => {
+                builder = builder
+                    .with_message(format!("Unknown dependency `{name}`"))
+                    .with_label(
+                        Label::new(sources.locate(span.clone()))
+                            .with_message("no node or node group with this name"),
+                    );
+            }
+            Self::PortOutOfRange {
+                field,
+                base,
+                offset,
+                span,
+            } => {
+                builder = builder
+                    .with_message("Invalid node configuration")
+                    .with_label(
+                        Label::new(sources.locate(span.clone())).with_message(format!(
+                            "replica {offset} would get `{field}` of {}, which exceeds 65535",
+                            *base as u64 + offset
+                        )),
+                    )
+                    .with_note("each replica gets next port after the configured one");
+            }
+            Self::PortConflict {
+                port,
+                node,
+                field,
+                span,
+                prev_node,
+                prev_field,
+                prev_span,
+            } => {
+                builder =
+                    builder
+                        .with_message(format!("Port {port} is used by multiple nodes"))
+                        .with_label(
+                            Label::new(sources.locate(span.clone()))
+                                .with_message(format!("`{field}` of `{node}`")),
+                        )
+                        .with_label(Label::new(sources.locate(prev_span.clone())).with_message(
+                            format!("already used as `{prev_field}` of `{prev_node}`"),
+                        ))
+                        .with_help("set distinct ports, or leave them unset to allocate free ones");
+            }
+            Self::InvalidName { name, span } => {
+                builder = builder
+                    .with_message(format!("`{name}` is not a valid container name"))
+                    .with_label(
+                        Label::new(sources.locate(span.clone()))
+                            .with_message("node names become docker container names"),
+                    )
+                    .with_note(
+                        "names must be at least two characters long, start with a letter or digit, and contain only letters, digits, `_`, `.` or `-`",
+                    );
 
-                        builder = builder
-                            .with_label(
-                                Label::new((file.clone(), prefix_span)).with_message(message),
-                            )
-                            .with_label(
-                                Label::new((file.clone(), count_span.clone()))
-                                    .with_message("count defined here"),
-                            )
-                            .with_note(format!("the redefinition name `{name}` comes from prefix `{prefix}_` and index {}", name.strip_prefix(&format!("{prefix}_")).unwrap_or("<unknown>")));
-                        }
+                if let Some(suggestion) = sanitize_name(name) {
+                    builder = builder.with_help(format!("try `{suggestion}` instead"));
                 }
             }
+            Self::NameTooLong { name, limit, span } => {
+                builder = builder
+                    .with_message(format!("Container name `{name}` is too long"))
+                    .with_label(
+                        Label::new(sources.locate(span.clone())).with_message(format!(
+                            "container name is `<network>-<node>`, and must fit into {limit} characters"
+                        )),
+                    )
+                    .with_help("use shorter node or network name");
+            }
+            Self::UndefinedVariable { name, span } => {
+                builder = builder
+                    .with_message(format!(
+                        "variable `{name}`, referenced in config, is not set"
+                    ))
+                    .with_label(
+                        Label::new(sources.locate(span.clone())).with_message("referenced here"),
+                    )
+                    .with_help(format!(
+                        "set it, or provide default value with `${{{name}:-<default>}}`"
+                    ));
+            }
+            Self::InvalidInterpolation(span) => {
+                builder = builder
+                    .with_message("Invalid variable reference")
+                    .with_label(
+                        Label::new(sources.locate(span.clone()))
+                            .with_message("expected `${NAME}` or `${NAME:-<default>}`"),
+                    )
+                    .with_help("to write literal `$`, use `$$`");
+            }
+            Self::IncludeFile { path, error, span } => {
+                builder = builder
+                    .with_message(format!("failed to read included config {path:?}"))
+                    .with_label(Label::new(sources.locate(span.clone())).with_message(error))
+                    .with_note(
+                        "relative paths are resolved against directory of the including file",
+                    );
+            }
+            Self::IncludeCycle { cycle, span } => {
+                builder = builder
+                    .with_message("Config includes itself")
+                    .with_label(
+                        Label::new(sources.locate(span.clone()))
+                            .with_message("this include closes the cycle"),
+                    )
+                    .with_note(format!(
+                        "cycle: {}",
+                        cycle
+                            .iter()
+                            .map(|path| path.display().to_string())
+                            .collect::<Vec<_>>()
+                            .join(" -> ")
+                    ));
+            }
+            Self::DependencyCycle { cycle, span } => {
+                builder = builder
+                    .with_message("Nodes depend on each other")
+                    .with_label(
+                        Label::new(sources.locate(span.clone()))
+                            .with_message("this dependency closes the cycle"),
+                    )
+                    .with_note(format!("cycle: {}", cycle.join(" -> ")));
+            }
         }
 
         builder.finish()
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize)]
 #[serde(untagged)]
-enum ClientSource {
+pub enum ClientSource {
     Default(ClientKind),
-    Binary { kind: ClientKind, bin: PathBuf },
-    Image { kind: ClientKind, image: String },
+    Binary {
+        kind: ClientKind,
+        bin: PathBuf,
+        #[serde(skip)]
+        bin_span: Span,
+    },
+    Image {
+        kind: ClientKind,
+        image: String,
+    },
 }
 
-impl ClientSource {
-    fn kind(&self) -> ClientKind {
-        match self {
-            Self::Default(kind) => kind.clone(),
-            Self::Binary { kind, .. } => kind.clone(),
+/// Table form of [`ClientSource`].
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ClientTable {
+    kind: ClientKind,
+    bin: Option<Spanned<PathBuf>>,
+    image: Option<String>,
+}
+
+// Written by hand instead of `#[serde(untagged)]`, which replaces errors of
+// the variants with generic one, hiding unknown client kind.
+impl<'de> Deserialize<'de> for ClientSource {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct Vis;
+
+        impl<'de> Visitor<'de> for Vis {
+            type Value = ClientSource;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("client identifier, or table with `kind` and `bin` or `image`")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                ClientKind::deserialize(de::value::StrDeserializer::new(v))
+                    .map(ClientSource::Default)
+            }
+
+            fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+            where
+                A: de::MapAccess<'de>,
+            {
+                let table = ClientTable::deserialize(de::value::MapAccessDeserializer::new(map))?;
+
+                match (table.bin, table.image) {
+                    (Some(bin), None) => Ok(ClientSource::Binary {
+                        kind: table.kind,
+                        bin_span: bin.span(),
+                        bin: bin.into_inner(),
+                    }),
+                    (None, Some(image)) => Ok(ClientSource::Image {
+                        kind: table.kind,
+                        image,
+                    }),
+                    _ => Err(de::Error::custom(
+                        "client table needs exactly one of `bin` or `image`",
+                    )),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(Vis)
+    }
+}
+
+// Written by hand too, to describe forms, accepted by the deserializer, as
+// mutually exclusive alternatives.
+impl JsonSchema for ClientSource {
+    fn schema_name() -> Cow<'static, str> {
+        "ClientSource".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let kind = generator.subschema_for::<ClientKind>();
+
+        json_schema!({
+            "description": "Client identifier, running its default image, or table with `kind` and either `bin` or `image`.",
+            "oneOf": [
+                kind,
+                {
+                    "type": "object",
+                    "properties": {
+                        "kind": kind,
+                        "bin": {
+                            "description": "Path to local client binary.",
+                            "type": "string",
+                        },
+                    },
+                    "required": ["kind", "bin"],
+                    "additionalProperties": false,
+                },
+                {
+                    "type": "object",
+                    "properties": {
+                        "kind": kind,
+                        "image": {
+                            "description": "Docker image to run.",
+                            "type": "string",
+                        },
+                    },
+                    "required": ["kind", "image"],
+                    "additionalProperties": false,
+                },
+            ],
+        })
+    }
+}
+
+impl ClientSource {
+    /// Validate image reference, and bring it into canonical form.
+    fn normalize(self) -> Result<Self, ImageReferenceError> {
+        match self {
+            Self::Image { kind, image } => Ok(Self::Image {
+                kind,
+                image: image.parse::<ImageReference>()?.to_string(),
+            }),
+            source => Ok(source),
+        }
+    }
+
+    /// Docker image to run, `None` for nodes running local binary.
+    pub fn image(&self) -> Option<&str> {
+        match self {
+            Self::Default(kind) => Some(kind.default_image()),
+            Self::Image { image, .. } => Some(image),
+            Self::Binary { .. } => None,
+        }
+    }
+
+    pub fn kind(&self) -> ClientKind {
+        match self {
+            Self::Default(kind) => kind.clone(),
+            Self::Binary { kind, .. } => kind.clone(),
             Self::Image { kind, .. } => kind.clone(),
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Sensitive string value, which can be read from file or environment
+/// variable instead of being written inline, e.g.
+/// `jwt = { file = "secrets/jwt.hex" }` or `password = { env = "TOKEN" }`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum Secret {
+    Inline(String),
+    File { file: PathBuf },
+    Env { env: String },
+}
+
+impl Secret {
+    /// Read secret value. Relative file paths are resolved against `base_dir`.
+    fn read(secret: &Spanned<Secret>, base_dir: &Path) -> Result<String, ConfigError> {
+        match secret.get_ref() {
+            Self::Inline(value) => Ok(value.clone()),
+            Self::File { file } => {
+                let path = base_dir.join(file);
+
+                std::fs::read_to_string(&path)
+                    .map(|value| value.trim_end().to_owned())
+                    .map_err(|error| ConfigError::SecretFile {
+                        path,
+                        error,
+                        span: secret.span(),
+                    })
+            }
+            Self::Env { env } => std::env::var(env).map_err(|_| ConfigError::SecretEnv {
+                name: env.clone(),
+                span: secret.span(),
+            }),
+        }
+    }
+}
+
+/// Credentials of a private image registry.
+///
+/// Either `username` with `password`, or `token` should be provided.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct RegistryAuthConfig {
+    #[serde(default)]
+    username: Option<String>,
+
+    #[serde(default)]
+    #[schemars(with = "Option<Secret>")]
+    password: Option<Spanned<Secret>>,
+
+    #[serde(default)]
+    #[schemars(with = "Option<Secret>")]
+    token: Option<Spanned<Secret>>,
+}
+
+impl RegistryAuthConfig {
+    fn resolve(&self, base_dir: &Path) -> Result<RegistryCredentials, ConfigError> {
+        let read = |secret: &Option<Spanned<Secret>>| {
+            secret
+                .as_ref()
+                .map(|secret| Secret::read(secret, base_dir))
+                .transpose()
+        };
+
+        Ok(RegistryCredentials {
+            username: self.username.clone(),
+            password: read(&self.password)?,
+            token: read(&self.token)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 struct NodeConfig {
     /// Name of docker container.
     ///
@@ -199,11 +1176,13 @@ struct NodeConfig {
     /// providing `name: "ream"` and `count: 2`, will start 2 nodes with names
     /// "ream_0" and "ream_1".
     #[serde(default)]
+    #[schemars(with = "Option<String>")]
     name: Option<Spanned<String>>,
 
     /// Which client to use?
     ///
     /// This may be either shorthand alias
+    #[schemars(with = "ClientSource")]
     client: Spanned<ClientSource>,
 
     /// How many exactly same nodes to launch.
@@ -211,6 +1190,7 @@ struct NodeConfig {
     /// If you want to put few validators into node, then check
     /// `validator_count` parameter.
     #[serde(default = "default_count")]
+    #[schemars(with = "u64")]
     count: Spanned<u64>,
 
     /// How many validators should single node handle.
@@ -221,138 +1201,3572 @@ struct NodeConfig {
     /// `count: 3`, `validator_count: 5`, then network with 3 nodes ream_0,
     /// ream_1 and ream_2, with 5 validators on each node, so in total there
     /// would be 15 network participants.
-    #[serde(default = "default_validator_count")]
-    validator_count: u64,
+    ///
+    /// When omitted, client default is used: 4 for zeam, and 1 for other
+    /// clients.
+    #[serde(default)]
+    #[schemars(with = "Option<u64>")]
+    validator_count: Option<Spanned<u64>>,
 
     /// Any extra command-line arguments to be passed directly into node binary.
+    ///
+    /// Relative paths in these arguments are resolved by the client itself,
+    /// against `working_dir`.
     #[serde(default)]
-    extra_args: Vec<String>,
-}
+    #[schemars(with = "Vec<String>")]
+    extra_args: Vec<Spanned<String>>,
 
-/// default value, used for `NodeConfig.count`.
-fn default_count() -> Spanned<u64> {
-    Spanned::new(0..0, 1)
+    /// Tag of the client's default image, e.g. `"v0.3.0"` instead of
+    /// `latest`. Only applies, when `client` doesn't set `image` or `bin`.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    image_tag: Option<Spanned<String>>,
+
+    /// Working directory of the node process.
+    ///
+    /// Must be an absolute path inside the container, for nodes running an
+    /// image. For nodes running local `bin`, relative path is resolved against
+    /// directory of the config. When omitted, the image default, or the
+    /// current directory for `bin`, is used.
+    #[serde(default)]
+    #[schemars(with = "Option<PathBuf>")]
+    working_dir: Option<Spanned<PathBuf>>,
+
+    /// Logical group of the node (e.g. "honest" or "attackers").
+    ///
+    /// Commands accept `--group <name>` to operate on all nodes of a group at
+    /// once.
+    #[serde(default)]
+    group: Option<String>,
+
+    /// Resource limits of the node container, e.g. `{ nofile = 65536 }`.
+    ///
+    /// Single number sets both soft and hard limit, use
+    /// `{ soft = <n>, hard = <n> }` to set them separately.
+    #[serde(default)]
+    #[schemars(with = "BTreeMap<String, Ulimit>")]
+    ulimits: BTreeMap<Spanned<String>, Ulimit>,
+
+    /// Environment variables of the node, e.g. `{ RUST_LOG = "debug" }`.
+    ///
+    /// Merged over network-wide `env`, so that node values win.
+    #[serde(default)]
+    #[schemars(with = "BTreeMap<String, String>")]
+    env: BTreeMap<Spanned<String>, String>,
+
+    /// Nodes outside of this network, e.g. of a public devnet, to connect to,
+    /// as multiaddrs or ENRs.
+    ///
+    /// Nodes of the network find each other without this: by default, every
+    /// node connects to every other node (full mesh). When some nodes set
+    /// `bootnode = true`, other nodes connect only to them instead, and
+    /// bootnodes connect to each other.
+    #[serde(default)]
+    #[schemars(with = "Vec<String>")]
+    peers: Vec<Spanned<String>>,
+
+    /// Make other nodes of the network connect to this one, instead of to
+    /// each other.
+    #[serde(default)]
+    bootnode: bool,
+
+    /// Path of the HTTP endpoint on rpc port, which responds with 200 once
+    /// node is ready, e.g. `"/health"`.
+    ///
+    /// Defaults to health endpoint of the client. Clients without one are
+    /// considered ready as soon as they run.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    health_path: Option<Spanned<String>>,
+
+    /// URL of a beacon node to checkpoint sync from, e.g.
+    /// `"http://localhost:5052"`, instead of syncing from genesis.
+    ///
+    /// Passed to clients, which support checkpoint sync, and ignored by
+    /// others.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    checkpoint_sync_url: Option<Spanned<String>>,
+
+    /// Log verbosity of the client: `"error"`, `"warn"`, `"info"`, `"debug"`
+    /// or `"trace"`. Defaults to the client default.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    log_level: Option<Spanned<String>>,
+
+    /// Host paths, mounted into the node container, as
+    /// `"<host path>:<container path>[:ro]"`.
+    ///
+    /// Relative host paths are resolved against the config directory.
+    #[serde(default)]
+    #[schemars(with = "Vec<String>")]
+    volumes: Vec<Spanned<String>>,
+
+    /// Number of cpu cores, node container may use, e.g. `1.5`.
+    #[serde(default)]
+    #[schemars(with = "Option<f64>")]
+    cpus: Option<Spanned<f64>>,
+
+    /// Memory limit of the node container, e.g. `"512m"` or `"2g"`.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    memory: Option<Spanned<String>>,
+
+    /// Kernel parameters of the node container.
+    ///
+    /// Names contain dots, so they must be quoted, e.g.
+    /// `{ "net.core.somaxconn" = "1024" }`.
+    #[serde(default)]
+    sysctls: BTreeMap<String, String>,
+
+    /// JWT secret, used by the client to authenticate API requests.
+    ///
+    /// May be read from file with `{ file = "<path>" }`.
+    #[serde(default)]
+    #[schemars(with = "Option<Secret>")]
+    jwt: Option<Spanned<Secret>>,
+
+    /// Host address, which published ports of this node are bound to.
+    ///
+    /// Overrides network-wide `bind_address`.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    bind_address: Option<Spanned<String>>,
+
+    /// Nodes, which must be started and healthy before this one.
+    ///
+    /// Either names of nodes, or names of replicated nodes (with `count`),
+    /// meaning all of their replicas.
+    #[serde(default)]
+    #[schemars(with = "Vec<String>")]
+    depends_on: Vec<Spanned<String>>,
+
+    /// Time, given to the client to shut down gracefully before it is killed,
+    /// e.g. `"30s"`.
+    ///
+    /// Overrides network-wide `stop_timeout`.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    stop_timeout: Option<Spanned<String>>,
+
+    /// When docker restarts node container after it exits: `"no"`,
+    /// `"on-failure"`, `"unless-stopped"` or `"always"`. Defaults to `"no"`.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    restart_policy: Option<Spanned<String>>,
+
+    /// First epoch, in which validator keys of this node can sign.
+    ///
+    /// Defaults to 0, i.e. keys are usable from genesis.
+    #[serde(default)]
+    #[schemars(with = "Option<u64>")]
+    activation_epoch: Option<Spanned<u64>>,
+
+    /// Number of epochs, for which validator keys of this node can sign.
+    ///
+    /// Key generation time grows with this value, so short-lived test
+    /// networks can lower it to start faster. Defaults to 262144.
+    #[serde(default)]
+    #[schemars(with = "Option<u64>")]
+    num_active_epochs: Option<Spanned<u64>>,
+
+    /// Host port for peer-to-peer connections.
+    ///
+    /// With `count` larger than 1, each replica gets next port, i.e. `ream_0`
+    /// gets 9000, `ream_1` gets 9001 and so on. When omitted, first free port
+    /// starting from the client default is used.
+    #[serde(default)]
+    #[schemars(with = "Option<u16>")]
+    p2p_port: Option<Spanned<u16>>,
+
+    /// Host port of the client REST API, allocated same way as `p2p_port`.
+    #[serde(default)]
+    #[schemars(with = "Option<u16>")]
+    rpc_port: Option<Spanned<u16>>,
+
+    /// Host port of the client metrics endpoint, allocated same way as
+    /// `p2p_port`.
+    #[serde(default)]
+    #[schemars(with = "Option<u16>")]
+    metrics_port: Option<Spanned<u16>>,
 }
 
-/// default value, user for `NodeConfig.validator_count`
-fn default_validator_count() -> u64 {
-    1
+impl NodeConfig {
+    /// Validators per replica. Explicit value always wins over the client
+    /// default, which is spanned by the client.
+    fn validator_count(&self) -> Spanned<u64> {
+        self.validator_count.clone().unwrap_or_else(|| {
+            Spanned::new(
+                self.client.span(),
+                self.client
+                    .get_ref()
+                    .kind()
+                    .instantiate()
+                    .default_validator_count(),
+            )
+        })
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct NetworkConfig {
-    /// Name of the network.
-    name: String,
+/// Values, shared by all nodes of the network, from `[defaults]` table.
+///
+/// Node values override them, except for `extra_args`, `env` and `ulimits`,
+/// which are merged: default arguments go first, and node entries replace
+/// default entries with the same name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+struct NodeDefaults {
+    /// Validators per node, see `validator_count` of the node.
+    #[serde(default)]
+    #[schemars(with = "Option<u64>")]
+    validator_count: Option<Spanned<u64>>,
 
-    node: Vec<NodeConfig>,
-}
+    /// Tag of client default images, see `image_tag` of the node.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    image_tag: Option<Spanned<String>>,
 
-#[derive(Debug, Clone)]
-struct ResolvedNodeConfig {
-    def: NodeNameDefinition,
+    /// Arguments, passed to every node binary before its own `extra_args`.
+    #[serde(default)]
+    #[schemars(with = "Vec<String>")]
+    extra_args: Vec<Spanned<String>>,
+
+    /// Environment variables of every node.
+    #[serde(default)]
+    #[schemars(with = "BTreeMap<String, String>")]
+    env: BTreeMap<Spanned<String>, String>,
+
+    /// Resource limits of every node container.
+    #[serde(default)]
+    #[schemars(with = "BTreeMap<String, Ulimit>")]
+    ulimits: BTreeMap<Spanned<String>, Ulimit>,
+
+    /// Number of cpu cores, each node container may use.
+    #[serde(default)]
+    #[schemars(with = "Option<f64>")]
+    cpus: Option<Spanned<f64>>,
+
+    /// Memory limit of each node container.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    memory: Option<Spanned<String>>,
+
+    /// URL to checkpoint sync from, see `checkpoint_sync_url` of the node.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    checkpoint_sync_url: Option<Spanned<String>>,
+
+    /// Log verbosity of every node, see `log_level` of the node.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    log_level: Option<Spanned<String>>,
+
+    /// Host port for peer-to-peer connections.
+    ///
+    /// Ports are not shifted between nodes, so only one node may use default
+    /// port, others must set their own.
+    #[serde(default)]
+    #[schemars(with = "Option<u16>")]
+    p2p_port: Option<Spanned<u16>>,
+
+    /// Host port of the client REST API, see `p2p_port`.
+    #[serde(default)]
+    #[schemars(with = "Option<u16>")]
+    rpc_port: Option<Spanned<u16>>,
 
-    validators: Vec<usize>,
+    /// Host port of the client metrics endpoint, see `p2p_port`.
+    #[serde(default)]
+    #[schemars(with = "Option<u16>")]
+    metrics_port: Option<Spanned<u16>>,
 }
 
-#[derive(Debug, Clone)]
-struct ResolvedValidatorConfig {
-    private_key: Vec<u8>,
-    public_key: Vec<u8>,
+impl NodeDefaults {
+    /// Fill values, `node` doesn't set.
+    ///
+    /// Values keep their spans, so errors point to the node, or to
+    /// `[defaults]`, whichever value came from.
+    fn apply(&self, node: &mut NodeConfig) {
+        fn fill<T: Clone>(value: &mut Option<T>, default: &Option<T>) {
+            if value.is_none() {
+                value.clone_from(default);
+            }
+        }
+
+        fn merge<K: Ord + Clone, V: Clone>(
+            entries: &mut BTreeMap<K, V>,
+            defaults: &BTreeMap<K, V>,
+        ) {
+            for (key, value) in defaults {
+                entries.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+        }
+
+        fill(&mut node.validator_count, &self.validator_count);
+        fill(&mut node.image_tag, &self.image_tag);
+        fill(&mut node.cpus, &self.cpus);
+        fill(&mut node.memory, &self.memory);
+        fill(&mut node.checkpoint_sync_url, &self.checkpoint_sync_url);
+        fill(&mut node.log_level, &self.log_level);
+        fill(&mut node.p2p_port, &self.p2p_port);
+        fill(&mut node.rpc_port, &self.rpc_port);
+        fill(&mut node.metrics_port, &self.metrics_port);
+
+        merge(&mut node.env, &self.env);
+        merge(&mut node.ulimits, &self.ulimits);
+
+        if !self.extra_args.is_empty() {
+            let args = std::mem::take(&mut node.extra_args);
+            node.extra_args = self.extra_args.iter().cloned().chain(args).collect();
+        }
+    }
 }
 
+/// Validators of all replicas of a node, along with values they come from.
 #[derive(Debug, Clone)]
-pub struct ResolvedNetworkConfig {
-    validators: Vec<ResolvedValidatorConfig>,
-    nodes: HashMap<String, ResolvedNodeConfig>,
-    counters: HashMap<String, u64>,
+pub struct ValidatorShare {
+    pub count: Spanned<u64>,
+    pub validator_count: Spanned<u64>,
 }
 
-const NUM_ACTIVE_EPOCHS: usize = 262144;
+impl ValidatorShare {
+    pub fn total(&self) -> u64 {
+        self.count
+            .get_ref()
+            .saturating_mul(*self.validator_count.get_ref())
+    }
 
-impl ResolvedNetworkConfig {
-    fn resolve(&mut self, node: NodeConfig) -> Result<(), ConfigError> {
-        let count = *node.count.get_ref();
+    /// Labels, pointing at values of the share. Default `count` has no span,
+    /// so it isn't labeled.
+    fn labels(&self, sources: &Sources) -> Vec<Label<(String, Span)>> {
+        let mut labels = Vec::new();
 
-        if count == 0 {
-            return Err(ConfigError::InvalidCount(node.count.span()));
+        if !self.count.span().is_empty() {
+            labels.push(
+                Label::new(sources.locate(self.count.span()))
+                    .with_message(format!("{} replicas", self.count.get_ref())),
+            );
         }
+        labels.push(
+            Label::new(sources.locate(self.validator_count.span())).with_message(format!(
+                "{} validators each",
+                self.validator_count.get_ref()
+            )),
+        );
 
-        let (node_id, node_id_span) = node
-            .name
-            .as_ref()
-            .map(|v| (v.get_ref().clone(), NodeNameSource::Name(v.span())))
-            .unwrap_or_else(|| {
-                (
-                    node.client.get_ref().kind().to_string(),
-                    NodeNameSource::Kind(node.client.span()),
-                )
-            });
+        labels
+    }
+}
 
-        for _ in 0..count {
-            let mut validator_indices = Vec::new();
-            for _ in 0..node.validator_count {
-                validator_indices.push(self.validators.len());
+/// Validator shares of nodes, which host more than one validator, and total
+/// number of validators in the network.
+fn validator_shares(nodes: &[NodeConfig]) -> (u64, Vec<ValidatorShare>) {
+    let shares = nodes
+        .iter()
+        .map(|node| ValidatorShare {
+            count: node.count.clone(),
+            validator_count: node.validator_count(),
+        })
+        .collect::<Vec<_>>();
 
-                // let (private_key, public_key) = generate_keypair(0, NUM_ACTIVE_EPOCHS);
-                let (private_key, public_key) = (Vec::new(), Vec::new());
+    let total = shares
+        .iter()
+        .fold(0u64, |total, share| total.saturating_add(share.total()));
+    let shares = shares
+        .into_iter()
+        .filter(|share| share.total() > 1)
+        .collect();
 
-                self.validators.push(ResolvedValidatorConfig {
-                    private_key: private_key,
-                    public_key: public_key,
-                });
-            }
+    (total, shares)
+}
 
-            let (name, def) = if count == 1 {
-                (
-                    node_id.clone(),
-                    NodeNameDefinition::Singular(node_id_span.clone()),
-                )
-            } else {
-                let index = *self
-                    .counters
-                    .entry(node_id.clone())
-                    .and_modify(|v| *v += 1)
-                    .or_insert(0);
-                (
-                    format!("{}_{index}", node_id.clone()),
-                    NodeNameDefinition::Prefix {
-                        prefix: node_id.clone(),
-                        prefix_span: node_id_span.clone(),
-                        count_span: node.count.span(),
-                    },
-                )
-            };
+/// Ulimit names, accepted by docker.
+const ULIMITS: [&str; 15] = [
+    "core",
+    "cpu",
+    "data",
+    "fsize",
+    "locks",
+    "memlock",
+    "msgqueue",
+    "nice",
+    "nofile",
+    "nproc",
+    "rss",
+    "rtprio",
+    "rttime",
+    "sigpending",
+    "stack",
+];
 
-            let resolved = ResolvedNodeConfig {
-                def: def.clone(),
-                validators: validator_indices,
-            };
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum Ulimit {
+    Both(i64),
+    Separate { soft: i64, hard: i64 },
+}
 
-            if let Some(old) = self.nodes.insert(name.clone(), resolved) {
-                return Err(ConfigError::DuplicateName {
-                    name,
-                    curr_def: def,
-                    prev_def: old.def,
-                });
-            }
+impl Ulimit {
+    pub fn soft(&self) -> i64 {
+        match self {
+            Self::Both(value) => *value,
+            Self::Separate { soft, .. } => *soft,
         }
+    }
 
-        Ok(())
+    pub fn hard(&self) -> i64 {
+        match self {
+            Self::Both(value) => *value,
+            Self::Separate { hard, .. } => *hard,
+        }
     }
 }
 
-impl NetworkConfig {
-    pub fn resolve(self) -> Result<ResolvedNetworkConfig, ConfigError> {
-        let mut resolved = ResolvedNetworkConfig {
-            nodes: HashMap::new(),
-            validators: Vec::new(),
-            counters: HashMap::new(),
-        };
+/// default value, used for `NodeConfig.count`.
+fn default_count() -> Spanned<u64> {
+    Spanned::new(0..0, 1)
+}
 
-        for node in self.node.into_iter() {
-            resolved.resolve(node)?;
-        }
+/// default value, used for `NetworkConfig.max_count`.
+fn default_max_count() -> u64 {
+    1024
+}
 
-        Ok(resolved)
+/// default value, used for `NetworkConfig.max_validator_count`.
+fn default_max_validator_count() -> u64 {
+    4096
+}
+
+/// Files, looked up in the current directory when `--config` is omitted, in
+/// order of preference.
+pub const DEFAULT_CONFIG_PATHS: [&str; 2] = ["network.toml", "liesas.toml"];
+
+/// Config file to use: `path` if given, or the first existing one of
+/// [`DEFAULT_CONFIG_PATHS`].
+///
+/// Reports missing config, and exits, when there is none.
+pub fn find_config(path: Option<&Path>) -> PathBuf {
+    // Empty path is the current directory, without `./` in front of found
+    // file name.
+    let message = match lookup_config(path, Path::new("")) {
+        Ok(path) => return path,
+        Err(message) => message,
+    };
+
+    if output::is_json() {
+        output::failure(Some(Failure::Usage), message, Vec::new());
+    }
+
+    eprintln!(
+        "{label} {message}",
+        label = "Error:".if_supports_color(Stream::Stderr, |text| text.red())
+    );
+    Failure::Usage.exit();
+}
+
+/// Same as [`find_config`], but looks up default config in `dir`, and returns
+/// message about missing config instead of exiting.
+fn lookup_config(path: Option<&Path>, dir: &Path) -> Result<PathBuf, String> {
+    if let Some(path) = path {
+        return Ok(path.to_owned());
+    }
+
+    DEFAULT_CONFIG_PATHS
+        .iter()
+        .map(|path| dir.join(path))
+        .find(|path| path.is_file())
+        .ok_or_else(|| {
+            format!(
+                "no config found, looked for {} in the current directory - pass `--config`, \
+                 or create one with `liesas init`",
+                DEFAULT_CONFIG_PATHS
+                    .map(|path| format!("`{path}`"))
+                    .join(" and ")
+            )
+        })
+}
+
+/// default value, used for `NetworkConfig.validator_warning_threshold`.
+fn default_validator_warning_threshold() -> u64 {
+    1024
+}
+
+/// Latest config version, supported by this binary.
+///
+/// Configs are versioned by liesas release - `version = "0.2.0"` means that
+/// config relies on features, introduced in liesas 0.2.0.
+pub fn supported_version() -> Version {
+    Version::parse(env!("CARGO_PKG_VERSION")).expect("crate version should be valid semver")
+}
+
+fn check_version(version: Option<&Spanned<Version>>) -> Result<(), ConfigError> {
+    let Some(version) = version else {
+        return Ok(());
+    };
+
+    if *version.get_ref() > supported_version() {
+        return Err(ConfigError::UnsupportedVersion {
+            required: version.get_ref().clone(),
+            span: version.span(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Top-level fields of config file, which determine how to parse the rest of
+/// it.
+///
+/// This is parsed before the full config, so configs written for newer liesas
+/// fail with upgrade prompt, instead of opaque deserialization error.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConfigHeader {
+    #[serde(default)]
+    version: Option<Spanned<Version>>,
+
+    #[serde(default)]
+    network: Option<IgnoredAny>,
+}
+
+impl ConfigHeader {
+    pub fn check(&self) -> Result<(), ConfigError> {
+        check_version(self.version.as_ref())
+    }
+
+    /// Whether config file defines multiple networks, via `[[network]]` tables.
+    pub fn has_networks(&self) -> bool {
+        self.network.is_some()
+    }
+}
+
+/// File, included by the network config, with nodes only.
+#[derive(Debug, Clone, Deserialize)]
+struct IncludedConfig {
+    #[serde(default)]
+    include: Vec<Spanned<PathBuf>>,
+
+    #[serde(default)]
+    node: Vec<NodeConfig>,
+}
+
+/// Config file, defining multiple networks via `[[network]]` tables.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiNetworkConfig {
+    network: Vec<NetworkConfig>,
+}
+
+#[derive(Debug, Error)]
+pub enum SelectNetworkError {
+    #[error("config doesn't define any network")]
+    Empty,
+
+    #[error(
+        "config defines multiple networks, select one with `--network <name>` (available: {})",
+        available.join(", ")
+    )]
+    Ambiguous { available: Vec<String> },
+
+    #[error(
+        "network `{name}` is not defined in config (available: {})",
+        available.join(", ")
+    )]
+    Unknown {
+        name: String,
+        available: Vec<String>,
+    },
+}
+
+/// All networks, defined in a single config file.
+#[derive(Debug, Clone)]
+pub enum ConfigFile {
+    Single(NetworkConfig),
+    Multiple(MultiNetworkConfig),
+}
+
+impl ConfigFile {
+    /// Pick network by name.
+    ///
+    /// Name may be omitted only when config defines exactly one network.
+    pub fn select(self, name: Option<&str>) -> Result<NetworkConfig, SelectNetworkError> {
+        let mut networks = match self {
+            Self::Single(network) => vec![network],
+            Self::Multiple(config) => config.network,
+        };
+
+        let available = || networks.iter().map(|v| v.name.clone()).collect();
+
+        match name {
+            Some(name) => match networks.iter().position(|v| v.name == name) {
+                Some(index) => Ok(networks.swap_remove(index)),
+                None => Err(SelectNetworkError::Unknown {
+                    name: name.to_owned(),
+                    available: available(),
+                }),
+            },
+            None => match networks.len() {
+                0 => Err(SelectNetworkError::Empty),
+                1 => Ok(networks.swap_remove(0)),
+                _ => Err(SelectNetworkError::Ambiguous {
+                    available: available(),
+                }),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct NetworkConfig {
+    /// Minimal liesas version, required by this config.
+    ///
+    /// When omitted, config is assumed to be written for the current version.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    version: Option<Spanned<Version>>,
+
+    /// Name of the network.
+    name: String,
+
+    /// Duration of a single slot, in seconds.
+    #[serde(default = "default_seconds_per_slot")]
+    #[schemars(with = "u64")]
+    seconds_per_slot: Spanned<u64>,
+
+    /// How many slots make up a single epoch.
+    #[serde(default = "default_slots_per_epoch")]
+    #[schemars(with = "u64")]
+    slots_per_epoch: Spanned<u64>,
+
+    /// Genesis time, as unix timestamp in seconds.
+    #[serde(default)]
+    #[schemars(with = "Option<u64>")]
+    genesis_time: Option<Spanned<u64>>,
+
+    /// Delay between launch and genesis, e.g. `"2m"`, giving nodes time to
+    /// boot. Used when `genesis_time` isn't pinned, defaults to 30 seconds.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    genesis_delay: Option<Spanned<String>>,
+
+    /// Host address, which published ports of all nodes are bound to.
+    ///
+    /// Defaults to `0.0.0.0`, i.e. all interfaces.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    bind_address: Option<Spanned<String>>,
+
+    /// Shell command, executed on the host once all nodes are healthy, e.g.
+    /// to submit transactions or configure monitoring.
+    ///
+    /// Runs in the config directory, with node names and addresses exposed
+    /// through `LIESAS_*` environment variables.
+    #[serde(default)]
+    post_start: Option<String>,
+
+    /// Environment variables, passed to every node, e.g.
+    /// `{ LOG_FORMAT = "json" }`. Nodes may override them with own `env`.
+    #[serde(default)]
+    #[schemars(with = "BTreeMap<String, String>")]
+    env: BTreeMap<Spanned<String>, String>,
+
+    /// Time, given to clients to shut down gracefully before they are killed,
+    /// e.g. `"1m"`. Defaults to 10 seconds, at most 100 seconds.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    stop_timeout: Option<Spanned<String>>,
+
+    /// Upper bound for `count` of a single node, protecting from typos like
+    /// `count = 100000000000`. Raise it for legitimately large networks.
+    #[serde(default = "default_max_count")]
+    max_count: u64,
+
+    /// Upper bound for `validator_count` of a single node.
+    #[serde(default = "default_max_validator_count")]
+    max_validator_count: u64,
+
+    /// Total number of validators in the network, above which a warning is
+    /// shown, as generating their keys may take very long.
+    #[serde(default = "default_validator_warning_threshold")]
+    validator_warning_threshold: u64,
+
+    /// Global index of the first validator in this config.
+    ///
+    /// When network is split across multiple machines, each machine must own
+    /// disjoint range of validator indices, e.g. machine A with offset 0 and
+    /// 64 validators, and machine B with offset 64. Ranges of different
+    /// configs are not checked against each other, so keeping them disjoint
+    /// is up to you.
+    #[serde(default)]
+    validator_index_offset: usize,
+
+    /// Seed of validator key generation, making keys same on every run.
+    ///
+    /// Key of each validator is derived from the seed and its global index,
+    /// so it doesn't change when other nodes are added or removed. When
+    /// omitted, keys are random.
+    #[serde(default)]
+    seed: Option<u64>,
+
+    /// Skip nodes with `count = 0`, instead of rejecting them.
+    #[serde(default)]
+    allow_disabled: bool,
+
+    /// Credentials for private image registries, keyed by registry host, e.g.
+    /// `[registry."ghcr.io"]`.
+    #[serde(default)]
+    registry: BTreeMap<String, RegistryAuthConfig>,
+
+    /// Other files with `[[node]]` tables, e.g. `["nodes/extra.toml"]`.
+    ///
+    /// Paths are relative to the directory of the file, which includes them,
+    /// and included files may include other files themselves. Nodes of
+    /// included files go after nodes of the including file, in include order.
+    /// Node names stay unique across all files: defining the same name in two
+    /// files is an error, same as in a single file.
+    #[serde(default)]
+    #[schemars(with = "Vec<PathBuf>")]
+    include: Vec<Spanned<PathBuf>>,
+
+    /// Values, shared by all nodes, including nodes of included files, e.g.
+    /// `[defaults] validator_count = 4`.
+    #[serde(default)]
+    defaults: Option<NodeDefaults>,
+
+    #[serde(default)]
+    node: Vec<NodeConfig>,
+}
+
+/// default value, used for `NetworkConfig.seconds_per_slot`, matches lean
+/// chain spec.
+fn default_seconds_per_slot() -> Spanned<u64> {
+    Spanned::new(0..0, 4)
+}
+
+/// default value, used for `NetworkConfig.slots_per_epoch`, matches lean
+/// chain spec.
+fn default_slots_per_epoch() -> Spanned<u64> {
+    Spanned::new(0..0, 32)
+}
+
+/// Delay between launch and genesis, used when neither `genesis_time` nor
+/// `genesis_delay` is set.
+const DEFAULT_GENESIS_DELAY: Duration = Duration::from_secs(30);
+
+/// Grace period before client is killed, used when `stop_timeout` is not set.
+const DEFAULT_STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Bounds of `stop_timeout`: clients need at least a moment to flush their
+/// database, while docker `stop` request, which waits the whole grace period,
+/// must finish before its [`REQUEST_TIMEOUT`].
+const STOP_TIMEOUT_RANGE: (Duration, Duration) = (
+    Duration::from_secs(1),
+    REQUEST_TIMEOUT.saturating_sub(Duration::from_secs(20)),
+);
+
+/// Genesis, slot and epoch timing, shared by all nodes in the network.
+#[derive(Debug, Clone, Copy)]
+pub struct ChainTiming {
+    /// Genesis time, as unix timestamp in seconds.
+    pub genesis_time: u64,
+    pub seconds_per_slot: u64,
+    pub slots_per_epoch: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ResolvedNodeConfig {
+    pub def: NodeNameDefinition,
+
+    pub client: ClientSource,
+
+    /// Span of `client` in config, to point at the image in launch errors.
+    pub client_span: Span,
+
+    /// Global indices of validators, handled by this node.
+    pub validators: Vec<usize>,
+
+    pub extra_args: Vec<String>,
+
+    pub working_dir: Option<PathBuf>,
+
+    pub group: Option<String>,
+
+    pub ulimits: BTreeMap<String, Ulimit>,
+
+    pub sysctls: BTreeMap<String, String>,
+
+    /// Host paths, mounted into the container, in addition to ones mounted
+    /// by liesas.
+    pub volumes: Vec<Volume>,
+
+    /// Environment variables, network-wide ones merged with node ones.
+    pub env: BTreeMap<String, String>,
+
+    /// Number of cpu cores, container may use.
+    pub cpus: Option<f64>,
+
+    /// Memory limit of the container, in bytes.
+    pub memory: Option<u64>,
+
+    /// JWT secret, written next to validator keys of the node, see
+    /// [`KeyStore::store_jwt`].
+    pub jwt: Option<String>,
+
+    pub bind_address: IpAddr,
+
+    /// Names of nodes, which must be started and healthy before this one.
+    pub depends_on: Vec<String>,
+
+    /// Grace period between stop signal and kill.
+    pub stop_timeout: Duration,
+
+    pub restart_policy: RestartPolicy,
+
+    /// Epochs, in which validator keys of this node can sign.
+    pub key_lifetime: KeyLifetime,
+
+    /// Host ports of the node.
+    pub ports: NodePorts,
+
+    /// Addresses of external peers, from config.
+    pub peers: Vec<String>,
+
+    /// Whether other nodes connect to this one, instead of to each other.
+    pub bootnode: bool,
+
+    /// Path of the endpoint on rpc port, polled until node is ready, if it
+    /// has one.
+    pub health_path: Option<String>,
+
+    /// URL of a beacon node, this node checkpoint syncs from.
+    pub checkpoint_sync_url: Option<String>,
+
+    /// Log verbosity of the client, if set.
+    pub log_level: Option<LogLevel>,
+}
+
+/// Node, which has already taken a port, to report conflicts against.
+#[derive(Debug, Clone)]
+struct PortOwner {
+    node: String,
+    field: &'static str,
+    span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct ResolvedValidatorConfig {
+    pub keys: StoredKeypair,
+}
+
+/// Validator, which index is already assigned, but keys are not generated
+/// yet.
+#[derive(Debug, Clone)]
+struct PendingValidator {
+    node: String,
+    index: usize,
+    lifetime: KeyLifetime,
+    span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct ResolvedNetworkConfig {
+    name: String,
+    max_count: u64,
+    max_validator_count: u64,
+    validator_index_offset: usize,
+    seed: Option<u64>,
+    bind_address: IpAddr,
+    stop_timeout: Duration,
+    post_start: Option<String>,
+    env: BTreeMap<String, String>,
+    registries: Registries,
+    key_store: KeyStore,
+    progress: ProgressBar,
+    base_dir: PathBuf,
+    timing: ChainTiming,
+    validators: Vec<ResolvedValidatorConfig>,
+    /// Validators, reserved by nodes, but not generated yet.
+    pending: Vec<PendingValidator>,
+    /// Global index of the next reserved validator.
+    next_validator: usize,
+    /// Nodes in config declaration order, replicas ordered by index.
+    nodes: IndexMap<String, ResolvedNodeConfig>,
+    /// Order, in which nodes start, so that dependencies start first.
+    launch_order: Vec<String>,
+    /// Unresolved `depends_on` of each node.
+    references: Vec<(String, Vec<Spanned<String>>)>,
+    counters: HashMap<String, u64>,
+    /// Host ports, taken by already resolved nodes.
+    ports: HashMap<u16, PortOwner>,
+}
+
+const NUM_ACTIVE_EPOCHS: usize = 262144;
+
+/// Number of epochs, supported by the signature scheme.
+const KEY_LIFETIME: u64 = 1 << 32;
+
+/// Range of epochs, in which validator key can sign.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyLifetime {
+    pub activation_epoch: usize,
+    pub num_active_epochs: usize,
+}
+
+impl Default for KeyLifetime {
+    fn default() -> Self {
+        Self {
+            activation_epoch: 0,
+            num_active_epochs: NUM_ACTIVE_EPOCHS,
+        }
+    }
+}
+
+impl KeyLifetime {
+    fn new(
+        activation_epoch: Option<&Spanned<u64>>,
+        num_active_epochs: Option<&Spanned<u64>>,
+    ) -> Result<Self, ConfigError> {
+        let default = Self::default();
+        let start = activation_epoch.map_or(default.activation_epoch as u64, |v| *v.get_ref());
+        let count = num_active_epochs.map_or(default.num_active_epochs as u64, |v| *v.get_ref());
+
+        if let Some(num_active_epochs) = num_active_epochs
+            && count == 0
+        {
+            return Err(ConfigError::InvalidKeyLifetime {
+                reason: "`num_active_epochs` cannot equal to zero".to_owned(),
+                span: num_active_epochs.span(),
+            });
+        }
+
+        if start
+            .checked_add(count)
+            .is_none_or(|end| end > KEY_LIFETIME)
+        {
+            let span = num_active_epochs
+                .or(activation_epoch)
+                .map(Spanned::span)
+                .unwrap_or_default();
+
+            return Err(ConfigError::InvalidKeyLifetime {
+                reason: format!("keys would be active beyond epoch {KEY_LIFETIME}"),
+                span,
+            });
+        }
+
+        Ok(Self {
+            activation_epoch: start as usize,
+            num_active_epochs: count as usize,
+        })
+    }
+}
+
+/// Check, that local binary exists and can be executed. Relative paths are
+/// resolved by then, so the only relative ones are bare names, like `ream`,
+/// which are looked up in `PATH`.
+fn check_binary(bin: &Path) -> Result<(), String> {
+    if bin.is_absolute() {
+        return check_executable(bin);
+    }
+
+    std::env::split_paths(&std::env::var_os("PATH").unwrap_or_default())
+        .map(|dir| dir.join(bin))
+        .find(|path| check_executable(path).is_ok())
+        .map(|_| ())
+        .ok_or_else(|| "not found in PATH".to_owned())
+}
+
+fn check_executable(path: &Path) -> Result<(), String> {
+    let metadata = std::fs::metadata(path).map_err(|err| err.to_string())?;
+    if !metadata.is_file() {
+        return Err("not a file".to_owned());
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        if metadata.permissions().mode() & 0o111 == 0 {
+            return Err("file is not executable".to_owned());
+        }
+    }
+
+    Ok(())
+}
+
+impl ResolvedNetworkConfig {
+    /// Host port for `field` of node `name`, which is replica number
+    /// `replica`.
+    ///
+    /// Configured port is incremented by replica number, otherwise first free
+    /// port from `default` is taken. Default ports are reported at
+    /// `default_span`, as they are not written in the config.
+    fn allocate_port(
+        &mut self,
+        name: &str,
+        (field, configured): (&'static str, Option<&Spanned<u16>>),
+        default: u16,
+        replica: u64,
+        default_span: Span,
+    ) -> Result<u16, ConfigError> {
+        let (port, span) = match configured {
+            Some(base) => {
+                let port = u16::try_from(*base.get_ref() as u64 + replica).map_err(|_| {
+                    ConfigError::PortOutOfRange {
+                        field,
+                        base: *base.get_ref(),
+                        offset: replica,
+                        span: base.span(),
+                    }
+                })?;
+
+                (port, base.span())
+            }
+            None => {
+                let port = (default..=u16::MAX)
+                    .find(|port| !self.ports.contains_key(port))
+                    .ok_or(ConfigError::PortOutOfRange {
+                        field,
+                        base: default,
+                        offset: replica,
+                        span: default_span.clone(),
+                    })?;
+
+                (port, default_span)
+            }
+        };
+
+        if let Some(owner) = self.ports.get(&port) {
+            return Err(ConfigError::PortConflict {
+                port,
+                node: name.to_owned(),
+                field,
+                span,
+                prev_node: owner.node.clone(),
+                prev_field: owner.field,
+                prev_span: owner.span.clone(),
+            });
+        }
+
+        self.ports.insert(
+            port,
+            PortOwner {
+                node: name.to_owned(),
+                field,
+                span,
+            },
+        );
+
+        Ok(port)
+    }
+
+    /// Reserve `count` validators for node `name`, returning their global
+    /// indices.
+    ///
+    /// Keys are not generated until [`generate_keys`](Self::generate_keys).
+    fn reserve_validators(
+        &mut self,
+        name: &str,
+        count: u64,
+        lifetime: KeyLifetime,
+        source: &NodeNameSource,
+    ) -> Vec<usize> {
+        (0..count)
+            .map(|_| {
+                let index = self.next_validator;
+                self.next_validator += 1;
+                self.pending.push(PendingValidator {
+                    node: name.to_owned(),
+                    index,
+                    lifetime,
+                    span: source.span(),
+                });
+
+                index
+            })
+            .collect()
+    }
+
+    /// Generate keys of all reserved validators in parallel.
+    ///
+    /// Validators are stored in order of their indices, regardless of order,
+    /// in which generation finishes.
+    fn generate_keys(&mut self) -> Result<(), ConfigError> {
+        let pending = std::mem::take(&mut self.pending);
+        let key_store = &self.key_store;
+        let progress = &self.progress;
+        let seed = self.seed;
+
+        progress.reset();
+        progress.set_length(pending.len() as u64);
+
+        if key_store.generates_keys() {
+            info!("generating keys of {} validator(s)", pending.len());
+        }
+
+        let keys = pending
+            .par_iter()
+            .map(|validator| {
+                let (private_key, public_key) = if key_store.generates_keys() {
+                    let KeyLifetime {
+                        activation_epoch,
+                        num_active_epochs,
+                    } = validator.lifetime;
+                    let (public_key, private_key) = match seed {
+                        Some(seed) => generate_seeded_keypair(
+                            seed,
+                            validator.index,
+                            activation_epoch,
+                            num_active_epochs,
+                        ),
+                        None => generate_random_keypair(activation_epoch, num_active_epochs),
+                    };
+                    (private_key.to_bytes(), public_key.to_bytes())
+                } else {
+                    (Vec::new(), Vec::new())
+                };
+
+                let keys = key_store
+                    .store(&validator.node, validator.index, private_key, public_key)
+                    .map_err(|(path, error)| ConfigError::KeyStore {
+                        path,
+                        error,
+                        span: validator.span.clone(),
+                    });
+                progress.inc(1);
+                trace!(
+                    "stored key of validator {} of {}",
+                    validator.index, validator.node
+                );
+
+                keys
+            })
+            .collect::<Result<Vec<_>, _>>();
+        progress.finish_and_clear();
+
+        let keys = keys?;
+
+        self.validators.extend(
+            keys.into_iter()
+                .map(|keys| ResolvedValidatorConfig { keys }),
+        );
+
+        Ok(())
+    }
+
+    /// Make validators, reserved from now on, take global indices from
+    /// `index` up, since lower ones are taken outside of this config, e.g. by
+    /// replicas of earlier `scale` runs.
+    ///
+    /// Validators stay ordered by index, but are no longer contiguous.
+    pub fn skip_validators_below(&mut self, index: usize) {
+        self.next_validator = self.next_validator.max(index);
+    }
+
+    /// Add replica `index` to node group `prefix`, configured same as other
+    /// replicas of the group, but with freshly generated validators.
+    ///
+    /// Returns name of the new node, or `None` if there is no such group.
+    pub fn add_replica(&mut self, prefix: &str, index: u64) -> Result<Option<String>, ConfigError> {
+        let Some(mut node) = self
+            .nodes
+            .values()
+            .find(|node| matches!(&node.def, NodeNameDefinition::Prefix { prefix: p, .. } if p == prefix))
+            .cloned()
+        else {
+            return Ok(None);
+        };
+
+        let NodeNameDefinition::Prefix { prefix_span, .. } = &node.def else {
+            unreachable!("template is always a prefixed node");
+        };
+
+        let name = format!("{prefix}_{index}");
+        // Replica takes first free ports after the template, same as nodes
+        // without configured ports.
+        let span = prefix_span.span();
+        node.ports = NodePorts {
+            p2p: self.allocate_port(
+                &name,
+                ("p2p_port", None),
+                node.ports.p2p,
+                index,
+                span.clone(),
+            )?,
+            rpc: self.allocate_port(
+                &name,
+                ("rpc_port", None),
+                node.ports.rpc,
+                index,
+                span.clone(),
+            )?,
+            metrics: self.allocate_port(
+                &name,
+                ("metrics_port", None),
+                node.ports.metrics,
+                index,
+                span,
+            )?,
+        };
+        node.validators = self.reserve_validators(
+            &name,
+            node.validators.len() as u64,
+            node.key_lifetime,
+            &prefix_span.clone(),
+        );
+        self.generate_keys()?;
+        self.nodes.insert(name.clone(), node);
+
+        Ok(Some(name))
+    }
+
+    /// Expand `depends_on` references into node names, and compute launch
+    /// order, rejecting dependency cycles.
+    fn resolve_dependencies(&mut self) -> Result<(), Vec<ConfigError>> {
+        let mut spans = HashMap::new();
+        let mut unknown = Vec::new();
+
+        for (name, references) in std::mem::take(&mut self.references) {
+            let mut depends_on = Vec::new();
+
+            for reference in references {
+                let targets = self
+                    .nodes
+                    .iter()
+                    .filter(|(target, node)| {
+                        *target == reference.get_ref()
+                            || matches!(&node.def, NodeNameDefinition::Prefix { prefix, .. } if prefix == reference.get_ref())
+                    })
+                    .map(|(target, _)| target.clone())
+                    .collect::<Vec<_>>();
+
+                if targets.is_empty() {
+                    unknown.push(ConfigError::UnknownDependency {
+                        name: reference.get_ref().clone(),
+                        span: reference.span(),
+                    });
+                    continue;
+                }
+
+                for target in targets {
+                    spans.insert((name.clone(), target.clone()), reference.span());
+                    depends_on.push(target);
+                }
+            }
+
+            self.nodes[&name].depends_on = depends_on;
+        }
+
+        if !unknown.is_empty() {
+            return Err(unknown);
+        }
+
+        // Depth-first search in declaration order, so that launch order stays
+        // deterministic. Nodes on `path` are being visited, and reaching one
+        // of them again means there is a cycle.
+        fn visit(
+            name: &str,
+            nodes: &IndexMap<String, ResolvedNodeConfig>,
+            path: &mut Vec<String>,
+            order: &mut IndexSet<String>,
+            spans: &HashMap<(String, String), Span>,
+        ) -> Result<(), ConfigError> {
+            if order.contains(name) {
+                return Ok(());
+            }
+
+            if let Some(start) = path.iter().position(|v| v == name) {
+                let mut cycle = path[start..].to_vec();
+                cycle.push(name.to_owned());
+
+                return Err(ConfigError::DependencyCycle {
+                    span: spans[&(path.last().unwrap().clone(), name.to_owned())].clone(),
+                    cycle,
+                });
+            }
+
+            path.push(name.to_owned());
+            for dependency in &nodes[name].depends_on {
+                visit(dependency, nodes, path, order, spans)?;
+            }
+            path.pop();
+
+            order.insert(name.to_owned());
+            Ok(())
+        }
+
+        let mut order = IndexSet::new();
+        for name in self.nodes.keys() {
+            visit(name, &self.nodes, &mut Vec::new(), &mut order, &spans)
+                .map_err(|err| vec![err])?;
+        }
+
+        self.launch_order = order.into_iter().collect();
+        Ok(())
+    }
+
+    fn resolve(&mut self, node: NodeConfig) -> Result<(), ConfigError> {
+        let count = *node.count.get_ref();
+
+        if count == 0 {
+            return Err(ConfigError::InvalidCount(node.count.span()));
+        }
+
+        if let Some(validator_count) = &node.validator_count
+            && *validator_count.get_ref() == 0
+        {
+            return Err(ConfigError::InvalidValidatorCount(validator_count.span()));
+        }
+
+        let validator_count = node.validator_count();
+
+        // Check bounds before anything is allocated or generated.
+        for (field, value, limit) in [
+            ("count", &node.count, self.max_count),
+            (
+                "validator_count",
+                &validator_count,
+                self.max_validator_count,
+            ),
+        ] {
+            if *value.get_ref() > limit {
+                return Err(ConfigError::LimitExceeded {
+                    field,
+                    value: *value.get_ref(),
+                    limit,
+                    span: value.span(),
+                });
+            }
+        }
+
+        let client = node.client.get_ref().clone().normalize().map_err(|error| {
+            ConfigError::InvalidImage {
+                error,
+                span: node.client.span(),
+            }
+        })?;
+        // Bare names are looked up in `PATH` on launch, like shell does.
+        let client = match client {
+            ClientSource::Binary {
+                kind,
+                bin,
+                bin_span,
+            } if bin.components().count() > 1 || bin.is_absolute() => {
+                let bin = std::path::absolute(self.base_dir.join(&bin)).map_err(|err| {
+                    ConfigError::InvalidBinary {
+                        path: bin,
+                        reason: err.to_string(),
+                        span: bin_span.clone(),
+                    }
+                })?;
+
+                ClientSource::Binary {
+                    kind,
+                    bin,
+                    bin_span,
+                }
+            }
+            client => client,
+        };
+        let client = match (client, &node.image_tag) {
+            (client, None) => client,
+            (ClientSource::Default(kind), Some(tag)) => {
+                let image = kind
+                    .default_image()
+                    .parse::<ImageReference>()
+                    .and_then(|image| image.with_tag(tag.get_ref()))
+                    .map_err(|error| ConfigError::InvalidImage {
+                        error,
+                        span: tag.span(),
+                    })?;
+
+                ClientSource::Image {
+                    kind,
+                    image: image.to_string(),
+                }
+            }
+            (_, Some(tag)) => return Err(ConfigError::MisplacedImageTag(tag.span())),
+        };
+
+        if client.image().is_none() && !matches!(client, ClientSource::Binary { .. }) {
+            return Err(ConfigError::NothingToRun(node.client.span()));
+        }
+
+        // Local processes run on the host, so their working directory may be
+        // relative to the config, same as `bin`.
+        let working_dir = match (&node.working_dir, &client) {
+            (Some(dir), ClientSource::Binary { .. }) => {
+                let dir = self.base_dir.join(dir.get_ref());
+                Some(std::path::absolute(&dir).unwrap_or(dir))
+            }
+            (Some(dir), _) if !dir.get_ref().is_absolute() => {
+                return Err(ConfigError::RelativeWorkingDir(dir.span()));
+            }
+            (dir, _) => dir.as_ref().map(|dir| dir.get_ref().clone()),
+        };
+
+        if let Some(name) = node
+            .ulimits
+            .keys()
+            .find(|name| !ULIMITS.contains(&name.get_ref().as_str()))
+        {
+            return Err(ConfigError::UnknownUlimit {
+                name: name.get_ref().clone(),
+                span: name.span(),
+            });
+        }
+
+        if let Some(cpus) = &node.cpus
+            && !(*cpus.get_ref() > 0.0 && cpus.get_ref().is_finite())
+        {
+            return Err(ConfigError::InvalidCpus {
+                value: *cpus.get_ref(),
+                span: cpus.span(),
+            });
+        }
+
+        let mut env = self.env.clone();
+        env.extend(parse_env(&node.env)?);
+
+        let volumes = parse_volumes(&node.volumes, &self.base_dir)?;
+
+        for peer in &node.peers {
+            check_peer(peer.get_ref()).map_err(|reason| ConfigError::InvalidPeer {
+                value: peer.get_ref().clone(),
+                reason,
+                span: peer.span(),
+            })?;
+        }
+
+        if let Some(path) = &node.health_path
+            && !path.get_ref().starts_with('/')
+        {
+            return Err(ConfigError::InvalidHealthPath {
+                value: path.get_ref().clone(),
+                span: path.span(),
+            });
+        }
+
+        let checkpoint_sync_url = node
+            .checkpoint_sync_url
+            .as_ref()
+            .map(|url| {
+                check_url(url.get_ref())
+                    .map(|()| url.get_ref().clone())
+                    .map_err(|reason| ConfigError::InvalidCheckpointSyncUrl {
+                        value: url.get_ref().clone(),
+                        reason,
+                        span: url.span(),
+                    })
+            })
+            .transpose()?;
+
+        let memory = node
+            .memory
+            .as_ref()
+            .map(|memory| {
+                parse_memory(memory.get_ref()).ok_or_else(|| ConfigError::InvalidMemory {
+                    value: memory.get_ref().clone(),
+                    span: memory.span(),
+                })
+            })
+            .transpose()?;
+
+        let bind_address = match &node.bind_address {
+            Some(address) => parse_bind_address(address)?,
+            None => self.bind_address,
+        };
+
+        let stop_timeout = match &node.stop_timeout {
+            Some(timeout) => parse_stop_timeout(timeout)?,
+            None => self.stop_timeout,
+        };
+
+        let log_level = node
+            .log_level
+            .as_ref()
+            .map(|level| {
+                level
+                    .get_ref()
+                    .parse::<LogLevel>()
+                    .map_err(|_| ConfigError::UnknownLogLevel {
+                        value: level.get_ref().clone(),
+                        span: level.span(),
+                    })
+            })
+            .transpose()?;
+
+        let restart_policy = match &node.restart_policy {
+            Some(policy) => {
+                policy
+                    .get_ref()
+                    .parse()
+                    .map_err(|_| ConfigError::UnknownRestartPolicy {
+                        value: policy.get_ref().clone(),
+                        span: policy.span(),
+                    })?
+            }
+            None => RestartPolicy::No,
+        };
+
+        let key_lifetime = KeyLifetime::new(
+            node.activation_epoch.as_ref(),
+            node.num_active_epochs.as_ref(),
+        )?;
+
+        let jwt = node
+            .jwt
+            .as_ref()
+            .map(|jwt| Secret::read(jwt, &self.base_dir))
+            .transpose()?;
+
+        let ulimits = node
+            .ulimits
+            .iter()
+            .map(|(name, ulimit)| (name.get_ref().clone(), *ulimit))
+            .collect::<BTreeMap<_, _>>();
+
+        let (node_id, node_id_span) = node
+            .name
+            .as_ref()
+            .map(|v| (v.get_ref().clone(), NodeNameSource::Name(v.span())))
+            .unwrap_or_else(|| {
+                (
+                    node.client.get_ref().kind().to_string(),
+                    NodeNameSource::Kind(node.client.span()),
+                )
+            });
+
+        let client_impl = node.client.get_ref().kind().instantiate();
+        let client_ports = client_impl.default_ports();
+        let health_path = match &node.health_path {
+            Some(path) => Some(path.get_ref().clone()),
+            None => client_impl.health_path().map(str::to_owned),
+        };
+
+        for replica in 0..count {
+            let (name, def) = if count == 1 {
+                (
+                    node_id.clone(),
+                    NodeNameDefinition::Singular(node_id_span.clone()),
+                )
+            } else {
+                let index = *self
+                    .counters
+                    .entry(node_id.clone())
+                    .and_modify(|v| *v += 1)
+                    .or_insert(0);
+                (
+                    format!("{}_{index}", node_id.clone()),
+                    NodeNameDefinition::Prefix {
+                        prefix: node_id.clone(),
+                        prefix_span: node_id_span.clone(),
+                        count_span: node.count.span(),
+                    },
+                )
+            };
+
+            // Generated names are checked too, as prefix alone may be too
+            // short to be valid.
+            if !is_valid_name(&name) {
+                return Err(ConfigError::InvalidName {
+                    name,
+                    span: node_id_span.span(),
+                });
+            }
+
+            let container = container_name(&self.name, &name);
+            if !is_valid_name(&container) {
+                return Err(ConfigError::InvalidName {
+                    name: container,
+                    span: node_id_span.span(),
+                });
+            }
+            if container.len() > MAX_CONTAINER_NAME_LEN {
+                return Err(ConfigError::NameTooLong {
+                    name: container,
+                    limit: MAX_CONTAINER_NAME_LEN,
+                    span: node_id_span.span(),
+                });
+            }
+
+            if let Some(old) = self.nodes.get(&name) {
+                return Err(ConfigError::DuplicateName {
+                    name,
+                    curr_def: def,
+                    prev_def: old.def.clone(),
+                });
+            }
+
+            let ports = NodePorts {
+                p2p: self.allocate_port(
+                    &name,
+                    ("p2p_port", node.p2p_port.as_ref()),
+                    client_ports.p2p,
+                    replica,
+                    node.client.span(),
+                )?,
+                rpc: self.allocate_port(
+                    &name,
+                    ("rpc_port", node.rpc_port.as_ref()),
+                    client_ports.rpc,
+                    replica,
+                    node.client.span(),
+                )?,
+                metrics: self.allocate_port(
+                    &name,
+                    ("metrics_port", node.metrics_port.as_ref()),
+                    client_ports.metrics,
+                    replica,
+                    node.client.span(),
+                )?,
+            };
+
+            let validator_indices = self.reserve_validators(
+                &name,
+                *validator_count.get_ref(),
+                key_lifetime,
+                &node_id_span,
+            );
+
+            if let (Some(value), Some(secret)) = (&jwt, &node.jwt) {
+                self.key_store
+                    .store_jwt(&name, value)
+                    .map_err(|(path, error)| ConfigError::WriteSecret {
+                        path,
+                        error,
+                        span: secret.span(),
+                    })?;
+            }
+
+            let resolved = ResolvedNodeConfig {
+                def,
+                client: client.clone(),
+                client_span: node.client.span(),
+                validators: validator_indices,
+                extra_args: node
+                    .extra_args
+                    .iter()
+                    .map(|arg| arg.get_ref().clone())
+                    .collect(),
+                working_dir: working_dir.clone(),
+                group: node.group.clone(),
+                ulimits: ulimits.clone(),
+                sysctls: node.sysctls.clone(),
+                volumes: volumes.clone(),
+                env: env.clone(),
+                cpus: node.cpus.as_ref().map(|cpus| *cpus.get_ref()),
+                memory,
+                jwt: jwt.clone(),
+                bind_address,
+                depends_on: Vec::new(),
+                stop_timeout,
+                restart_policy,
+                key_lifetime,
+                ports,
+                peers: node
+                    .peers
+                    .iter()
+                    .map(|peer| peer.get_ref().clone())
+                    .collect(),
+                bootnode: node.bootnode,
+                health_path: health_path.clone(),
+                checkpoint_sync_url: checkpoint_sync_url.clone(),
+                log_level,
+            };
+
+            debug!(
+                "resolved {name}: {} with validators {:?}, ports {:?}",
+                resolved.client.kind(),
+                resolved.validators,
+                resolved.ports
+            );
+
+            self.references
+                .push((name.clone(), node.depends_on.clone()));
+            self.nodes.insert(name, resolved);
+        }
+
+        Ok(())
+    }
+}
+
+impl ResolvedNetworkConfig {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn timing(&self) -> ChainTiming {
+        self.timing
+    }
+
+    pub fn post_start(&self) -> Option<&str> {
+        self.post_start.as_deref()
+    }
+
+    pub fn base_dir(&self) -> &Path {
+        &self.base_dir
+    }
+
+    pub fn key_store(&self) -> &KeyStore {
+        &self.key_store
+    }
+
+    /// Name of the container, running `node`, see [`container_name`].
+    pub fn container_name(&self, node: &str) -> String {
+        container_name(&self.name, node)
+    }
+
+    /// Name of the docker network, see [`docker_network_name`].
+    pub fn docker_network(&self) -> String {
+        docker_network_name(&self.name)
+    }
+
+    /// Keep keys of nodes, added from now on, in `key_store`, e.g. to
+    /// generate keys only for new replicas of network, resolved without
+    /// them.
+    pub fn set_key_store(&mut self, key_store: KeyStore) {
+        self.key_store = key_store;
+    }
+
+    /// Address, on which `from` reaches `node` for peer-to-peer connections.
+    ///
+    /// Containers reach each other by container name on their default port,
+    /// so they have to share docker network. Local processes reach
+    /// containers on published host port, and are themselves reached on
+    /// host ports: directly by other processes, and through
+    /// [`HOST_GATEWAY`] by containers.
+    pub fn p2p_address(&self, from: &str, node: &str) -> String {
+        let resolved = &self.nodes[node];
+        let client = resolved.client.kind().instantiate();
+        let from_process = matches!(self.nodes[from].client, ClientSource::Binary { .. });
+
+        match (&resolved.client, from_process) {
+            (ClientSource::Binary { .. }, true) => {
+                client.p2p_address(&Ipv4Addr::LOCALHOST.to_string(), resolved.ports.p2p)
+            }
+            (ClientSource::Binary { .. }, false) => {
+                client.p2p_address(HOST_GATEWAY, resolved.ports.p2p)
+            }
+            (_, true) => {
+                client.p2p_address(&self.host_address(node).to_string(), resolved.ports.p2p)
+            }
+            (_, false) => {
+                client.p2p_address(&self.container_name(node), client.default_ports().p2p)
+            }
+        }
+    }
+
+    /// Whether any node of the network runs as local process, which
+    /// containers have to reach through [`HOST_GATEWAY`].
+    pub fn has_processes(&self) -> bool {
+        self.nodes
+            .values()
+            .any(|node| matches!(node.client, ClientSource::Binary { .. }))
+    }
+
+    /// Check, that binaries of local nodes can be executed.
+    ///
+    /// Binaries are needed only to launch nodes, so this is left to commands,
+    /// which do, and others keep working once binary is gone.
+    pub fn check_binaries(&self) -> Result<(), Vec<ConfigError>> {
+        let errors = self
+            .nodes
+            .values()
+            .filter_map(|node| match &node.client {
+                ClientSource::Binary { bin, bin_span, .. } => {
+                    check_binary(bin)
+                        .err()
+                        .map(|reason| ConfigError::InvalidBinary {
+                            path: bin.clone(),
+                            reason,
+                            span: bin_span.clone(),
+                        })
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Address, on which host ports of `node` are reachable from the host.
+    pub fn host_address(&self, node: &str) -> IpAddr {
+        match self.nodes[node].bind_address {
+            address if address.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+            address => address,
+        }
+    }
+
+    /// Addresses of peers, which `node` connects to on start: other nodes of
+    /// the network, followed by external `peers` of the node.
+    ///
+    /// Nodes form full mesh, unless some of them are bootnodes, in which case
+    /// every node connects to bootnodes only.
+    pub fn peers(&self, node: &str) -> Vec<String> {
+        let has_bootnodes = self.nodes.values().any(|node| node.bootnode);
+
+        self.nodes
+            .iter()
+            .filter(|(name, peer)| *name != node && (!has_bootnodes || peer.bootnode))
+            .map(|(name, _)| self.p2p_address(node, name))
+            .chain(self.nodes[node].peers.iter().cloned())
+            .collect()
+    }
+
+    /// Number of other nodes, `node` is connected to once network is up:
+    /// those it dials, see [`Self::peers`], and those dialing it.
+    pub fn expected_peers(&self, node: &str) -> u64 {
+        let has_bootnodes = self.nodes.values().any(|node| node.bootnode);
+        let is_bootnode = self.nodes[node].bootnode;
+
+        self.nodes
+            .iter()
+            .filter(|(name, peer)| {
+                *name != node && (!has_bootnodes || is_bootnode || peer.bootnode)
+            })
+            .count() as u64
+    }
+
+    /// Whether `prefix` names node group, i.e. node with `count` above one,
+    /// whose replicas are named `<prefix>_<index>`.
+    pub fn is_group(&self, prefix: &str) -> bool {
+        self.nodes.values().any(
+            |node| matches!(&node.def, NodeNameDefinition::Prefix { prefix: p, .. } if p == prefix),
+        )
+    }
+
+    pub fn registries(&self) -> &Registries {
+        &self.registries
+    }
+
+    /// Validators of this config, in order of their global indices, starting
+    /// from `validator_index_offset`. Indices are contiguous, unless
+    /// [`skip_validators_below`](Self::skip_validators_below) was used.
+    pub fn validators(&self) -> &[ResolvedValidatorConfig] {
+        &self.validators
+    }
+
+    pub fn nodes(&self) -> &IndexMap<String, ResolvedNodeConfig> {
+        &self.nodes
+    }
+
+    /// Names of all nodes, with dependencies always preceding their
+    /// dependents.
+    pub fn launch_order(&self) -> &[String] {
+        &self.launch_order
+    }
+
+    /// Global index of the first validator.
+    pub fn validator_index_offset(&self) -> usize {
+        self.validator_index_offset
+    }
+
+    /// Serialized public keys of all validators, ordered by global index,
+    /// starting from `validator_index_offset`.
+    ///
+    /// Keys, which were streamed to disk, are loaded back.
+    pub fn public_keys(&self) -> std::io::Result<Vec<Vec<u8>>> {
+        self.validators
+            .iter()
+            .map(|v| v.keys.public_key())
+            .collect()
+    }
+
+    /// Names of nodes, which belong to `group`, or all nodes if no group is
+    /// given, in declaration order.
+    pub fn node_names(&self, group: Option<&str>) -> Vec<String> {
+        self.nodes
+            .iter()
+            .filter(|(_, node)| group.is_none() || node.group.as_deref() == group)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+}
+
+fn parse_bind_address(address: &Spanned<String>) -> Result<IpAddr, ConfigError> {
+    address
+        .get_ref()
+        .parse()
+        .map_err(|_| ConfigError::InvalidBindAddress {
+            address: address.get_ref().clone(),
+            span: address.span(),
+        })
+}
+
+fn parse_duration(value: &Spanned<String>) -> Result<Duration, ConfigError> {
+    humantime::parse_duration(value.get_ref()).map_err(|error| ConfigError::InvalidDuration {
+        value: value.get_ref().clone(),
+        error,
+        span: value.span(),
+    })
+}
+
+/// Check that `url` is an absolute http(s) url.
+fn check_url(url: &str) -> Result<(), String> {
+    let parsed = reqwest::Url::parse(url).map_err(|err| err.to_string())?;
+
+    match parsed.scheme() {
+        "http" | "https" => Ok(()),
+        scheme => Err(format!(
+            "unsupported scheme `{scheme}`, expected `http` or `https`"
+        )),
+    }
+}
+
+/// Check that `peer` looks like multiaddr with host and port, or ENR.
+fn check_peer(peer: &str) -> Result<(), &'static str> {
+    if let Some(record) = peer.strip_prefix("enr:") {
+        let is_base64 = record
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        return match record.is_empty() || !is_base64 {
+            true => Err("ENR must be followed by base64 encoded record"),
+            false => Ok(()),
+        };
+    }
+
+    let Some(address) = peer.strip_prefix('/') else {
+        return Err("expected multiaddr or ENR");
+    };
+
+    let parts = address.split('/').collect::<Vec<_>>();
+    let host_is_valid = match parts[..] {
+        ["ip4", host, ..] => host.parse::<Ipv4Addr>().is_ok(),
+        ["ip6", host, ..] => host.parse::<std::net::Ipv6Addr>().is_ok(),
+        ["dns" | "dns4" | "dns6", host, ..] => !host.is_empty(),
+        _ => return Err("multiaddr must start with `/ip4`, `/ip6` or `/dns`"),
+    };
+    if !host_is_valid {
+        return Err("invalid host address");
+    }
+
+    match parts.get(2..4) {
+        Some(["tcp" | "udp", port]) if port.parse::<u16>().is_ok() => Ok(()),
+        _ => Err("host must be followed by `/udp/<port>` or `/tcp/<port>`"),
+    }
+}
+
+/// Parse volumes of a node, resolving relative host paths against
+/// `base_dir`.
+///
+/// Container paths must be distinct, and outside of [`CONFIG_DIR`], which is
+/// reserved for files, mounted by liesas.
+fn parse_volumes(volumes: &[Spanned<String>], base_dir: &Path) -> Result<Vec<Volume>, ConfigError> {
+    let mut targets = HashMap::<String, Span>::new();
+
+    volumes
+        .iter()
+        .map(|volume| {
+            let invalid = |reason| ConfigError::InvalidVolume {
+                value: volume.get_ref().clone(),
+                reason,
+                span: volume.span(),
+            };
+
+            let parts = volume.get_ref().split(':').collect::<Vec<_>>();
+            let (source, target, read_only) = match parts[..] {
+                [source, target] => (source, target, false),
+                [source, target, "ro"] => (source, target, true),
+                [source, target, "rw"] => (source, target, false),
+                [_, _, _] => return Err(invalid("mode must be either `ro` or `rw`")),
+                _ => return Err(invalid("expected `<host path>:<container path>[:ro]`")),
+            };
+
+            if source.is_empty() {
+                return Err(invalid("host path is empty"));
+            }
+            if !target.starts_with('/') {
+                return Err(invalid("container path must be absolute"));
+            }
+
+            let target = match target.trim_end_matches('/') {
+                "" => return Err(invalid("cannot mount over container root")),
+                target => target.to_owned(),
+            };
+
+            let is_reserved = target == CONFIG_DIR || target.starts_with(&format!("{CONFIG_DIR}/"));
+            if is_reserved {
+                return Err(ConfigError::VolumeConflict {
+                    target,
+                    span: volume.span(),
+                    prev_span: None,
+                });
+            }
+            if let Some(prev_span) = targets.insert(target.clone(), volume.span()) {
+                return Err(ConfigError::VolumeConflict {
+                    target,
+                    span: volume.span(),
+                    prev_span: Some(prev_span),
+                });
+            }
+
+            // Docker treats relative host paths as names of its own volumes.
+            let source = base_dir.join(source);
+            Ok(Volume {
+                source: std::path::absolute(&source).unwrap_or(source),
+                target,
+                read_only,
+            })
+        })
+        .collect()
+}
+
+/// Check names of environment variables, and drop their spans.
+fn parse_env(
+    env: &BTreeMap<Spanned<String>, String>,
+) -> Result<BTreeMap<String, String>, ConfigError> {
+    env.iter()
+        .map(|(name, value)| {
+            let is_valid = !name.get_ref().is_empty()
+                && !name
+                    .get_ref()
+                    .chars()
+                    .any(|c| c == '=' || c == '\0' || c.is_whitespace());
+            if !is_valid {
+                return Err(ConfigError::InvalidEnvName {
+                    name: name.get_ref().clone(),
+                    span: name.span(),
+                });
+            }
+
+            Ok((name.get_ref().clone(), value.clone()))
+        })
+        .collect()
+}
+
+/// Parse memory size in docker format, i.e. number of bytes with optional
+/// `b`, `k`, `m` or `g` unit, e.g. `512m`. Units are powers of 1024.
+fn parse_memory(value: &str) -> Option<u64> {
+    let value = value.trim().to_ascii_lowercase();
+    let (number, multiplier) = match value.char_indices().last()? {
+        (i, 'b') => (&value[..i], 1),
+        (i, 'k') => (&value[..i], 1 << 10),
+        (i, 'm') => (&value[..i], 1 << 20),
+        (i, 'g') => (&value[..i], 1 << 30),
+        _ => (value.as_str(), 1),
+    };
+
+    number
+        .parse::<u64>()
+        .ok()?
+        .checked_mul(multiplier)
+        .filter(|bytes| *bytes > 0)
+}
+
+fn parse_stop_timeout(value: &Spanned<String>) -> Result<Duration, ConfigError> {
+    let timeout = parse_duration(value)?;
+
+    let (min, max) = STOP_TIMEOUT_RANGE;
+    if !(min..=max).contains(&timeout) {
+        return Err(ConfigError::DurationOutOfRange {
+            field: "stop_timeout",
+            value: value.get_ref().clone(),
+            min,
+            max,
+            span: value.span(),
+        });
+    }
+
+    Ok(timeout)
+}
+
+/// Environment, in which config is resolved.
+#[derive(Debug, Clone)]
+pub struct ResolveOptions {
+    /// Where to keep generated validator keys.
+    pub key_store: KeyStore,
+
+    /// Directory, against which relative paths in config are resolved.
+    pub base_dir: PathBuf,
+
+    /// Progress bar, advanced once per generated keypair. Hidden by default.
+    pub progress: ProgressBar,
+
+    /// Upper bound for total number of validators in the network.
+    pub max_validators: Option<u64>,
+
+    /// Seed of key generation, overriding `seed` of the config.
+    pub seed: Option<u64>,
+}
+
+impl ResolveOptions {
+    /// Options for config, loaded from `path`.
+    pub fn new(path: &Path, key_store: KeyStore) -> Self {
+        Self {
+            key_store,
+            base_dir: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            progress: ProgressBar::hidden(),
+            max_validators: None,
+            seed: None,
+        }
+    }
+
+    pub fn with_progress(mut self, progress: ProgressBar) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    pub fn with_max_validators(mut self, limit: Option<u64>) -> Self {
+        self.max_validators = limit;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+impl NetworkConfig {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Merge `[defaults]` into every node. Must be called once all nodes,
+    /// including nodes of included files, are loaded.
+    fn apply_defaults(&mut self) {
+        if let Some(defaults) = self.defaults.take() {
+            for node in &mut self.node {
+                defaults.apply(node);
+            }
+        }
+    }
+
+    /// Pinned genesis time, or current time plus genesis delay.
+    fn genesis_time(&self) -> Result<u64, ConfigError> {
+        let delay = match (&self.genesis_time, &self.genesis_delay) {
+            (Some(time), Some(delay)) => {
+                return Err(ConfigError::ConflictingGenesis {
+                    time_span: time.span(),
+                    delay_span: delay.span(),
+                });
+            }
+            (Some(time), None) => return Ok(*time.get_ref()),
+            (None, Some(delay)) => parse_duration(delay)?,
+            (None, None) => DEFAULT_GENESIS_DELAY,
+        };
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        Ok((now + delay).as_secs())
+    }
+
+    pub fn resolve(
+        self,
+        options: ResolveOptions,
+    ) -> Result<ResolvedNetworkConfig, Vec<ConfigError>> {
+        // Errors are collected instead of returned right away, so that the
+        // whole config can be fixed in one go. Invalid network-wide values
+        // are replaced with defaults, to keep checking nodes.
+        let mut errors = Vec::new();
+
+        if let Err(err) = check_version(self.version.as_ref()) {
+            errors.push(err);
+        }
+
+        for (field, value) in [
+            ("seconds_per_slot", &self.seconds_per_slot),
+            ("slots_per_epoch", &self.slots_per_epoch),
+        ] {
+            if *value.get_ref() == 0 {
+                errors.push(ConfigError::InvalidTiming {
+                    field,
+                    span: value.span(),
+                });
+            }
+        }
+
+        if let Some(limit) = options.max_validators {
+            let (total, shares) = validator_shares(&self.node);
+
+            if total > limit {
+                errors.push(ConfigError::TooManyValidators {
+                    total,
+                    limit,
+                    shares,
+                });
+            }
+        }
+
+        let genesis_time = self.genesis_time().unwrap_or_else(|err| {
+            errors.push(err);
+            0
+        });
+
+        let registries = self
+            .registry
+            .iter()
+            .filter_map(|(host, auth)| match auth.resolve(&options.base_dir) {
+                Ok(auth) => Some((host.clone(), auth)),
+                Err(err) => {
+                    errors.push(err);
+                    None
+                }
+            })
+            .collect();
+
+        let bind_address = match self.bind_address.as_ref().map(parse_bind_address) {
+            Some(Ok(address)) => address,
+            Some(Err(err)) => {
+                errors.push(err);
+                IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+            }
+            None => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        };
+
+        let stop_timeout = match self.stop_timeout.as_ref().map(parse_stop_timeout) {
+            Some(Ok(timeout)) => timeout,
+            Some(Err(err)) => {
+                errors.push(err);
+                DEFAULT_STOP_TIMEOUT
+            }
+            None => DEFAULT_STOP_TIMEOUT,
+        };
+
+        let env = parse_env(&self.env).unwrap_or_else(|err| {
+            errors.push(err);
+            BTreeMap::new()
+        });
+
+        let mut resolved = ResolvedNetworkConfig {
+            name: self.name,
+            max_count: self.max_count,
+            max_validator_count: self.max_validator_count,
+            validator_index_offset: self.validator_index_offset,
+            seed: options.seed.or(self.seed),
+            bind_address,
+            stop_timeout,
+            post_start: self.post_start,
+            env,
+            registries: Registries::new(registries),
+            key_store: options.key_store,
+            progress: options.progress,
+            base_dir: options.base_dir,
+            timing: ChainTiming {
+                genesis_time,
+                seconds_per_slot: *self.seconds_per_slot.get_ref(),
+                slots_per_epoch: *self.slots_per_epoch.get_ref(),
+            },
+            nodes: IndexMap::new(),
+            launch_order: Vec::new(),
+            references: Vec::new(),
+            validators: Vec::new(),
+            pending: Vec::new(),
+            next_validator: self.validator_index_offset,
+            counters: HashMap::new(),
+            ports: HashMap::new(),
+        };
+
+        for node in self.node.into_iter() {
+            // Disabled nodes are skipped entirely, so they don't consume
+            // validator indices.
+            if self.allow_disabled && *node.count.get_ref() == 0 {
+                continue;
+            }
+
+            // Node with an error is left out, but the rest are still
+            // checked.
+            if let Err(err) = resolved.resolve(node) {
+                errors.push(err);
+            }
+        }
+
+        // References to nodes, which failed to resolve, would only produce
+        // misleading errors.
+        if errors.is_empty()
+            && let Err(err) = resolved.resolve_dependencies()
+        {
+            errors.extend(err);
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        debug!("launch order: {}", resolved.launch_order.join(", "));
+
+        // Generate keys last, so that any config error is reported without
+        // waiting for slow key generation.
+        resolved.generate_keys().map_err(|err| vec![err])?;
+
+        Ok(resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::{
+        container::{ContainerSpec, KEYS_DIR},
+        testing::resolve,
+        validator::KeyManifest,
+    };
+
+    #[test]
+    fn newer_version_asks_to_upgrade() {
+        let errors = resolve(
+            r#"
+            version = "999.0.0"
+            name = "devnet"
+
+            [[node]]
+            client = "ream"
+            "#,
+        )
+        .unwrap_err();
+
+        assert!(matches!(
+            &errors[..],
+            [ConfigError::UnsupportedVersion { required, .. }] if required.major == 999
+        ));
+        assert_eq!(errors[0].to_string(), "config requires liesas >= 999.0.0");
+    }
+
+    #[test]
+    fn current_version_resolves() {
+        let network = resolve(&format!(
+            r#"
+            version = "{}"
+            name = "devnet"
+
+            [[node]]
+            client = "ream"
+            "#,
+            supported_version()
+        ))
+        .unwrap();
+
+        assert_eq!(network.nodes().len(), 1);
+    }
+
+    #[test]
+    fn header_rejects_newer_version() {
+        let header: ConfigHeader = toml::from_str("version = \"999.0.0\"").unwrap();
+        assert!(header.check().is_err());
+
+        let header: ConfigHeader = toml::from_str("name = \"devnet\"").unwrap();
+        assert!(header.check().is_ok());
+    }
+
+    #[test]
+    fn zero_timing_is_rejected_at_value() {
+        let source = r#"
+            name = "devnet"
+            seconds_per_slot = 0
+            slots_per_epoch = 0
+
+            [[node]]
+            client = "ream"
+            "#;
+
+        let errors = resolve(source).unwrap_err();
+        let fields = errors
+            .iter()
+            .map(|err| match err {
+                ConfigError::InvalidTiming { field, span } => {
+                    assert_eq!(&source[span.clone()], "0");
+                    *field
+                }
+                err => panic!("unexpected error: {err}"),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(fields, ["seconds_per_slot", "slots_per_epoch"]);
+    }
+
+    #[test]
+    fn relative_working_dir_is_rejected() {
+        let source = r#"
+            name = "devnet"
+
+            [[node]]
+            client = "ream"
+            working_dir = "data"
+            "#;
+
+        let errors = resolve(source).unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ConfigError::RelativeWorkingDir(span)] if &source[span.clone()] == "\"data\""
+        ));
+    }
+
+    #[test]
+    fn relative_working_dir_of_binary_is_resolved_against_config() {
+        let network = resolve(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            client = { kind = "ream", bin = "/bin/sh" }
+            working_dir = "data"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            network.nodes()["ream"].working_dir,
+            Some(std::path::absolute("data").unwrap())
+        );
+    }
+
+    #[test]
+    fn selected_network_is_scoped() {
+        let source = r#"
+            [[network]]
+            name = "a"
+
+            [[network.node]]
+            name = "alpha"
+            client = "ream"
+
+            [[network]]
+            name = "b"
+
+            [[network.node]]
+            name = "beta"
+            client = "ream"
+            "#;
+
+        let file = || ConfigFile::Multiple(toml::from_str(source).unwrap());
+        let network = file()
+            .select(Some("a"))
+            .unwrap()
+            .resolve(ResolveOptions::new(
+                Path::new("network.toml"),
+                KeyStore::Skip,
+            ))
+            .unwrap();
+
+        assert_eq!(network.name(), "a");
+        assert_eq!(network.nodes().keys().collect::<Vec<_>>(), ["alpha"]);
+        assert_eq!(network.container_name("alpha"), "a-alpha");
+
+        assert!(matches!(
+            file().select(None),
+            Err(SelectNetworkError::Ambiguous { available }) if available == ["a", "b"]
+        ));
+    }
+
+    #[test]
+    fn invalid_image_is_reported_at_client() {
+        let source = r#"
+            name = "devnet"
+
+            [[node]]
+            client = { kind = "ream", image = "ream:bad tag" }
+            "#;
+
+        let errors = resolve(source).unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ConfigError::InvalidImage { error: ImageReferenceError::InvalidTag(_), span }]
+                if source[span.clone()].starts_with("{ kind")
+        ));
+    }
+
+    #[test]
+    fn unknown_ulimit_is_rejected_at_name() {
+        let source = r#"
+            name = "devnet"
+
+            [[node]]
+            client = "ream"
+            ulimits = { nofiles = 65536 }
+            "#;
+
+        let errors = resolve(source).unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ConfigError::UnknownUlimit { name, span }]
+                if name == "nofiles" && &source[span.clone()] == "nofiles"
+        ));
+    }
+
+    #[test]
+    fn jwt_is_read_from_file() {
+        let dir = std::env::temp_dir().join(format!("liesas-jwt-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("jwt.txt"), "0xsecret\n").unwrap();
+
+        let config: NetworkConfig = toml::from_str(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            client = "ream"
+            jwt = { file = "jwt.txt" }
+            "#,
+        )
+        .unwrap();
+        let network = config
+            .resolve(ResolveOptions::new(
+                &dir.join("network.toml"),
+                KeyStore::Disk(dir.join("keys")),
+            ))
+            .unwrap();
+
+        let node = &network.nodes()["ream"];
+        assert_eq!(node.jwt.as_deref(), Some("0xsecret"));
+        assert_eq!(
+            std::fs::read_to_string(dir.join("keys/ream/jwt.hex")).unwrap(),
+            "0xsecret"
+        );
+
+        // Ream is configured by file, once keys are mounted.
+        let spec = ContainerSpec::new(&network, "ream", node).unwrap();
+        let contents = &spec.config_file.unwrap().contents;
+        assert!(
+            contents.contains(&format!("jwt-secret: {KEYS_DIR}/jwt.hex")),
+            "{contents}"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_jwt_file_is_reported_at_field() {
+        let source = r#"
+            name = "devnet"
+
+            [[node]]
+            client = "ream"
+            jwt = { file = "missing/jwt.hex" }
+            "#;
+
+        let errors = resolve(source).unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ConfigError::SecretFile { span, .. }] if &source[span.clone()] == r#"{ file = "missing/jwt.hex" }"#
+        ));
+    }
+
+    #[test]
+    fn nodes_keep_declaration_order() {
+        let source = r#"
+            name = "devnet"
+
+            [[node]]
+            name = "zulu"
+            client = "ream"
+
+            [[node]]
+            name = "alpha"
+            client = "ream"
+            count = 3
+
+            [[node]]
+            name = "mike"
+            client = "ream"
+            "#;
+
+        let names = || {
+            resolve(source)
+                .unwrap()
+                .nodes()
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(), ["zulu", "alpha_0", "alpha_1", "alpha_2", "mike"]);
+        assert_eq!(names(), names());
+    }
+
+    #[test]
+    fn genesis_is_delayed_from_now() {
+        let now = || {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+        };
+
+        let before = now();
+        let network = resolve(
+            r#"
+            name = "devnet"
+            genesis_delay = "90s"
+
+            [[node]]
+            client = "ream"
+            "#,
+        )
+        .unwrap();
+        let after = now();
+
+        let genesis_time = network.timing().genesis_time;
+        assert!((before + 90..=after + 90).contains(&genesis_time));
+    }
+
+    #[test]
+    fn genesis_time_conflicts_with_delay() {
+        let source = r#"
+            name = "devnet"
+            genesis_time = 1700000000
+            genesis_delay = "90s"
+
+            [[node]]
+            client = "ream"
+            "#;
+
+        let errors = resolve(source).unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ConfigError::ConflictingGenesis { time_span, delay_span }]
+                if &source[time_span.clone()] == "1700000000"
+                    && &source[delay_span.clone()] == "\"90s\""
+        ));
+    }
+
+    #[test]
+    fn zero_count_is_rejected_by_default() {
+        let source = r#"
+            name = "devnet"
+
+            [[node]]
+            client = "ream"
+            count = 0
+            "#;
+
+        let errors = resolve(source).unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ConfigError::InvalidCount(span)] if &source[span.clone()] == "0"
+        ));
+    }
+
+    #[test]
+    fn disabled_node_is_skipped() {
+        let network = resolve(
+            r#"
+            name = "devnet"
+            allow_disabled = true
+
+            [[node]]
+            name = "alpha"
+            client = "ream"
+
+            [[node]]
+            name = "beta"
+            client = "ream"
+            count = 0
+
+            [[node]]
+            name = "gamma"
+            client = "ream"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            network.nodes().keys().collect::<Vec<_>>(),
+            ["alpha", "gamma"]
+        );
+        assert_eq!(network.nodes()["alpha"].validators, [0]);
+        assert_eq!(network.nodes()["gamma"].validators, [1]);
+    }
+
+    #[test]
+    fn absurd_count_fails_fast() {
+        let source = r#"
+            name = "devnet"
+
+            [[node]]
+            client = "ream"
+            count = 100000000000
+            "#;
+
+        let started = Instant::now();
+        let errors = resolve(source).unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        assert!(matches!(
+            &errors[..],
+            [ConfigError::LimitExceeded { field: "count", value: 100000000000, limit: 1024, span }]
+                if &source[span.clone()] == "100000000000"
+        ));
+    }
+
+    #[test]
+    fn validator_count_limit_is_overridable() {
+        let source = |limit| {
+            format!(
+                r#"
+                name = "devnet"
+                max_validator_count = {limit}
+
+                [[node]]
+                client = "ream"
+                validator_count = 5000
+                "#
+            )
+        };
+
+        let errors = resolve(&source(4096)).unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ConfigError::LimitExceeded {
+                field: "validator_count",
+                limit: 4096,
+                ..
+            }]
+        ));
+
+        let network = resolve(&source(5000)).unwrap();
+        assert_eq!(network.nodes()["ream"].validators.len(), 5000);
+    }
+
+    #[test]
+    fn validator_count_defaults_to_client_value() {
+        let network = resolve(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            client = "zeam"
+
+            [[node]]
+            client = "ream"
+
+            [[node]]
+            name = "explicit"
+            client = "zeam"
+            validator_count = 2
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(network.nodes()["zeam"].validators, [0, 1, 2, 3]);
+        assert_eq!(network.nodes()["ream"].validators, [4]);
+        assert_eq!(network.nodes()["explicit"].validators, [5, 6]);
+    }
+
+    #[test]
+    fn index_offset_shifts_global_indices() {
+        let network = resolve(
+            r#"
+            name = "devnet"
+            validator_index_offset = 100
+
+            [[node]]
+            name = "alpha"
+            client = "ream"
+            validator_count = 2
+
+            [[node]]
+            name = "beta"
+            client = "ream"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(network.validator_index_offset(), 100);
+        assert_eq!(network.nodes()["alpha"].validators, [100, 101]);
+        assert_eq!(network.nodes()["beta"].validators, [102]);
+
+        let manifest = KeyManifest::new(&network);
+        assert_eq!(
+            manifest.nodes[1].validators,
+            [PathBuf::from("beta/validator_102.json")]
+        );
+    }
+
+    #[test]
+    fn dependencies_precede_dependents() {
+        let network = resolve(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            name = "validator"
+            client = "ream"
+            depends_on = ["beacon"]
+
+            [[node]]
+            name = "beacon"
+            client = "ream"
+            depends_on = ["boot"]
+
+            [[node]]
+            name = "boot"
+            client = "ream"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(network.launch_order(), ["boot", "beacon", "validator"]);
+        assert_eq!(network.nodes()["validator"].depends_on, ["beacon"]);
+    }
+
+    #[test]
+    fn dependency_cycle_is_reported() {
+        let source = r#"
+            name = "devnet"
+
+            [[node]]
+            name = "alpha"
+            client = "ream"
+            depends_on = ["beta"]
+
+            [[node]]
+            name = "beta"
+            client = "ream"
+            depends_on = ["alpha"]
+            "#;
+
+        let errors = resolve(source).unwrap_err();
+        let [ConfigError::DependencyCycle { cycle, span }] = &errors[..] else {
+            panic!("expected dependency cycle, got {errors:?}");
+        };
+
+        assert_eq!(cycle, &["alpha", "beta", "alpha"]);
+        assert_eq!(&source[span.clone()], "\"alpha\"");
+        assert_eq!(
+            errors[0].to_string(),
+            "dependency cycle: alpha -> beta -> alpha"
+        );
+    }
+
+    #[test]
+    fn unreasonable_stop_timeout_is_rejected() {
+        let source = r#"
+            name = "devnet"
+
+            [[node]]
+            client = "ream"
+            stop_timeout = "2h"
+            "#;
+
+        let errors = resolve(source).unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ConfigError::DurationOutOfRange { field: "stop_timeout", span, .. }]
+                if &source[span.clone()] == "\"2h\""
+        ));
+    }
+
+    #[test]
+    fn stop_timeout_ends_before_docker_request() {
+        let source = |timeout: &str| {
+            format!(
+                r#"
+                name = "devnet"
+
+                [[node]]
+                client = "ream"
+                stop_timeout = "{timeout}"
+                "#
+            )
+        };
+
+        let network = resolve(&source("100s")).unwrap();
+        assert!(network.nodes()["ream"].stop_timeout < REQUEST_TIMEOUT);
+
+        let errors = resolve(&source("101s")).unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ConfigError::DurationOutOfRange {
+                field: "stop_timeout",
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn parallel_keys_match_sequential_baseline() {
+        let config: NetworkConfig = toml::from_str(
+            r#"
+            name = "devnet"
+            seed = 42
+
+            [[node]]
+            name = "ream"
+            client = "ream"
+            count = 2
+            validator_count = 3
+            num_active_epochs = 4
+            "#,
+        )
+        .unwrap();
+        let network = config
+            .resolve(ResolveOptions::new(
+                Path::new("network.toml"),
+                KeyStore::Memory,
+            ))
+            .unwrap();
+
+        assert_eq!(network.nodes()["ream_0"].validators, [0, 1, 2]);
+        assert_eq!(network.nodes()["ream_1"].validators, [3, 4, 5]);
+
+        let parallel = network
+            .validators()
+            .iter()
+            .map(|validator| validator.keys.public_key().unwrap())
+            .collect::<Vec<_>>();
+        let sequential = (0..6)
+            .map(|index| generate_seeded_keypair(42, index, 0, 4).0.to_bytes())
+            .collect::<Vec<_>>();
+        assert_eq!(parallel, sequential);
+    }
+
+    #[test]
+    fn peer_address_depends_on_dialing_node() {
+        let network = resolve(
+            r#"
+            name = "devnet"
+            bind_address = "10.0.0.5"
+
+            [[node]]
+            name = "alpha"
+            client = { kind = "ream", bin = "/bin/sh" }
+
+            [[node]]
+            name = "beta"
+            client = { kind = "ream", bin = "/bin/sh" }
+
+            [[node]]
+            name = "gamma"
+            client = "ream"
+
+            [[node]]
+            name = "delta"
+            client = "ream"
+            "#,
+        )
+        .unwrap();
+
+        let alpha = network.nodes()["alpha"].ports.p2p;
+        let gamma = network.nodes()["gamma"].ports.p2p;
+        assert_eq!(
+            network.p2p_address("beta", "alpha"),
+            format!("/ip4/127.0.0.1/udp/{alpha}/quic-v1")
+        );
+        assert_eq!(
+            network.p2p_address("gamma", "alpha"),
+            format!("/dns4/host.docker.internal/udp/{alpha}/quic-v1")
+        );
+        assert_eq!(
+            network.p2p_address("alpha", "gamma"),
+            format!("/ip4/10.0.0.5/udp/{gamma}/quic-v1")
+        );
+        assert_eq!(
+            network.p2p_address("delta", "gamma"),
+            "/dns4/devnet-gamma/udp/9000/quic-v1"
+        );
+    }
+
+    #[test]
+    fn zero_validator_count_is_rejected_at_value() {
+        let source = r#"
+            name = "devnet"
+
+            [[node]]
+            client = "ream"
+            validator_count = 0
+            "#;
+
+        let errors = resolve(source).unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ConfigError::InvalidValidatorCount(span)] if &source[span.clone()] == "0"
+        ));
+    }
+
+    #[test]
+    fn unsafe_node_names_are_rejected() {
+        for (name, suggestion) in [
+            ("my node!", Some("my_node_")),
+            ("nœud", Some("n_ud")),
+            (".hidden", Some("hidden")),
+        ] {
+            let source = format!(
+                r#"
+                name = "devnet"
+
+                [[node]]
+                name = "{name}"
+                client = "ream"
+                "#
+            );
+
+            let errors = resolve(&source).unwrap_err();
+            let [
+                ConfigError::InvalidName {
+                    name: invalid,
+                    span,
+                },
+            ] = &errors[..]
+            else {
+                panic!("{name:?} is accepted: {errors:?}");
+            };
+            assert_eq!(invalid, name);
+            assert_eq!(&source[span.clone()], format!("\"{name}\""));
+            assert_eq!(sanitize_name(name).as_deref(), suggestion);
+        }
+    }
+
+    #[test]
+    fn generated_names_are_checked() {
+        let source = r#"
+            name = "devnet"
+
+            [[node]]
+            name = "a b"
+            client = "ream"
+            count = 2
+            "#;
+
+        let errors = resolve(source).unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ConfigError::InvalidName { name, span }]
+                if name == "a b_0" && &source[span.clone()] == "\"a b\""
+        ));
+    }
+
+    #[test]
+    fn independent_errors_are_reported_together() {
+        let source = r#"
+            name = "devnet"
+            seconds_per_slot = 0
+
+            [[node]]
+            name = "alpha"
+            client = "ream"
+            count = 0
+
+            [[node]]
+            name = "beta"
+            client = "zeam"
+            validator_count = 0
+            "#;
+
+        let errors = resolve(source).unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [
+                ConfigError::InvalidTiming {
+                    field: "seconds_per_slot",
+                    ..
+                },
+                ConfigError::InvalidCount(_),
+                ConfigError::InvalidValidatorCount(_),
+            ]
+        ));
+    }
+
+    #[test]
+    fn default_config_is_looked_up_in_order() {
+        let dir = std::env::temp_dir().join(format!("liesas-find-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let message = lookup_config(None, &dir).unwrap_err();
+        assert!(
+            message.contains("`network.toml` and `liesas.toml`"),
+            "{message}"
+        );
+        assert!(message.contains("liesas init"), "{message}");
+
+        std::fs::write(dir.join("liesas.toml"), "").unwrap();
+        assert_eq!(lookup_config(None, &dir).unwrap(), dir.join("liesas.toml"));
+
+        std::fs::write(dir.join("network.toml"), "").unwrap();
+        assert_eq!(lookup_config(None, &dir).unwrap(), dir.join("network.toml"));
+
+        let explicit = Path::new("custom.toml");
+        assert_eq!(lookup_config(Some(explicit), &dir).unwrap(), explicit);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn container_names_are_scoped_by_network() {
+        let source = |network: &str| {
+            format!(
+                r#"
+                name = "{network}"
+
+                [[node]]
+                client = "ream"
+                count = 2
+                "#
+            )
+        };
+
+        let first = resolve(&source("first")).unwrap();
+        let second = resolve(&source("second")).unwrap();
+
+        assert_eq!(first.container_name("ream_0"), "first-ream_0");
+        assert_eq!(second.container_name("ream_0"), "second-ream_0");
+        assert_eq!(first.docker_network(), "liesas-first");
+    }
+
+    #[test]
+    fn too_long_container_name_is_rejected() {
+        let network = "n".repeat(60);
+        let source = format!(
+            r#"
+            name = "{network}"
+
+            [[node]]
+            name = "alpha"
+            client = "ream"
+            "#
+        );
+
+        let errors = resolve(&source).unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ConfigError::NameTooLong { name, limit: 63, span }]
+                if *name == format!("{network}-alpha") && &source[span.clone()] == "\"alpha\""
+        ));
+    }
+
+    #[test]
+    fn same_seed_gives_identical_keys() {
+        let keys = |seed: Option<u64>| {
+            let config: NetworkConfig = toml::from_str(
+                r#"
+                name = "devnet"
+                seed = 7
+
+                [[node]]
+                client = "ream"
+                validator_count = 2
+                num_active_epochs = 4
+                "#,
+            )
+            .unwrap();
+
+            config
+                .resolve(
+                    ResolveOptions::new(Path::new("network.toml"), KeyStore::Memory)
+                        .with_seed(seed),
+                )
+                .unwrap()
+                .validators()
+                .iter()
+                .map(|validator| {
+                    (
+                        validator.keys.public_key().unwrap(),
+                        validator.keys.private_key().unwrap(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(keys(None), keys(None));
+        assert_eq!(keys(None), keys(Some(7)));
+        assert_ne!(keys(None), keys(Some(8)));
+    }
+
+    #[test]
+    fn memory_is_parsed_with_units() {
+        assert_eq!(parse_memory("1024"), Some(1024));
+        assert_eq!(parse_memory("2b"), Some(2));
+        assert_eq!(parse_memory("4k"), Some(4 << 10));
+        assert_eq!(parse_memory("512m"), Some(512 << 20));
+        assert_eq!(parse_memory("2G"), Some(2 << 30));
+
+        for invalid in ["", "m", "0g", "1.5g", "12x", "-1m", "99999999999g"] {
+            assert_eq!(parse_memory(invalid), None, "{invalid:?}");
+        }
+    }
+
+    #[test]
+    fn invalid_memory_is_reported_at_value() {
+        let source = r#"
+            name = "devnet"
+
+            [[node]]
+            client = "ream"
+            memory = "lots"
+            "#;
+
+        let errors = resolve(source).unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ConfigError::InvalidMemory { value, span }]
+                if value == "lots" && &source[span.clone()] == "\"lots\""
+        ));
+    }
+
+    #[test]
+    fn node_env_overrides_network_env() {
+        let source = super::interpolate::interpolate(
+            r#"
+            name = "devnet"
+            env = { RUST_LOG = "info", LOG_FORMAT = "${LIESAS_TEST_UNSET_VARIABLE:-json}" }
+
+            [[node]]
+            name = "alpha"
+            client = "ream"
+            env = { RUST_LOG = "debug" }
+
+            [[node]]
+            name = "beta"
+            client = "zeam"
+            "#,
+        )
+        .unwrap();
+        let network = resolve(&source).unwrap();
+
+        assert_eq!(
+            network.nodes()["alpha"].env,
+            BTreeMap::from([
+                ("LOG_FORMAT".to_owned(), "json".to_owned()),
+                ("RUST_LOG".to_owned(), "debug".to_owned()),
+            ])
+        );
+        assert_eq!(network.nodes()["beta"].env["RUST_LOG"], "info");
+    }
+
+    #[test]
+    fn volumes_are_parsed_against_config_dir() {
+        let network = resolve(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            client = "ream"
+            volumes = ["data:/data", "/etc/ream:/etc/ream:ro"]
+            "#,
+        )
+        .unwrap();
+
+        let volumes = &network.nodes()["ream"].volumes;
+        assert_eq!(
+            volumes
+                .iter()
+                .map(|volume| (
+                    volume.source.clone(),
+                    volume.target.as_str(),
+                    volume.read_only
+                ))
+                .collect::<Vec<_>>(),
+            [
+                (std::path::absolute("data").unwrap(), "/data", false),
+                (PathBuf::from("/etc/ream"), "/etc/ream", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_volume_is_rejected() {
+        for (volume, expected) in [
+            ("/data", "expected `<host path>:<container path>[:ro]`"),
+            ("data:/data:rx", "mode must be either `ro` or `rw`"),
+            (":/data", "host path is empty"),
+            ("data:data", "container path must be absolute"),
+        ] {
+            let source = format!(
+                r#"
+                name = "devnet"
+
+                [[node]]
+                client = "ream"
+                volumes = ["{volume}"]
+                "#
+            );
+
+            let errors = resolve(&source).unwrap_err();
+            let [ConfigError::InvalidVolume { reason, span, .. }] = &errors[..] else {
+                panic!("{volume:?} is accepted: {errors:?}");
+            };
+            assert_eq!(reason, &expected);
+            assert_eq!(&source[span.clone()], format!("\"{volume}\""));
+        }
+    }
+
+    #[test]
+    fn conflicting_volume_targets_are_rejected() {
+        let source = r#"
+            name = "devnet"
+
+            [[node]]
+            client = "ream"
+            volumes = ["a:/data", "b:/data/"]
+            "#;
+
+        let errors = resolve(source).unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ConfigError::VolumeConflict { target, span, prev_span: Some(prev_span) }]
+                if target == "/data"
+                    && &source[span.clone()] == "\"b:/data/\""
+                    && &source[prev_span.clone()] == "\"a:/data\""
+        ));
+
+        let source = r#"
+            name = "devnet"
+
+            [[node]]
+            client = "ream"
+            volumes = ["keys:/liesas/keys"]
+            "#;
+
+        let errors = resolve(source).unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ConfigError::VolumeConflict { target, prev_span: None, .. }]
+                if target == "/liesas/keys"
+        ));
+    }
+
+    #[test]
+    fn unknown_restart_policy_is_rejected_at_value() {
+        let source = r#"
+            name = "devnet"
+
+            [[node]]
+            client = "ream"
+            restart_policy = "sometimes"
+            "#;
+
+        let errors = resolve(source).unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ConfigError::UnknownRestartPolicy { value, span }]
+                if value == "sometimes" && &source[span.clone()] == "\"sometimes\""
+        ));
+
+        let network = resolve(&source.replace("sometimes", "unless-stopped")).unwrap();
+        assert_eq!(
+            network.nodes()["ream"].restart_policy,
+            RestartPolicy::UnlessStopped
+        );
+    }
+
+    #[test]
+    fn node_values_override_defaults() {
+        let source = r#"
+            name = "devnet"
+
+            [defaults]
+            validator_count = 3
+            extra_args = ["--a"]
+            env = { A = "default", B = "default" }
+
+            [[node]]
+            name = "alpha"
+            client = "ream"
+            validator_count = 1
+            extra_args = ["--b"]
+            env = { B = "node" }
+
+            [[node]]
+            name = "beta"
+            client = "zeam"
+            "#;
+        let mut config: NetworkConfig = toml::from_str(source).unwrap();
+        config.apply_defaults();
+        let network = config
+            .resolve(ResolveOptions::new(
+                Path::new("network.toml"),
+                KeyStore::Skip,
+            ))
+            .unwrap();
+
+        let alpha = &network.nodes()["alpha"];
+        assert_eq!(alpha.validators.len(), 1);
+        assert_eq!(alpha.extra_args, ["--a", "--b"]);
+        assert_eq!(alpha.env["A"], "default");
+        assert_eq!(alpha.env["B"], "node");
+
+        let beta = &network.nodes()["beta"];
+        assert_eq!(beta.validators.len(), 3);
+        assert_eq!(beta.extra_args, ["--a"]);
+        assert_eq!(beta.env["B"], "default");
+    }
+
+    #[test]
+    fn default_value_errors_point_at_defaults() {
+        let source = r#"
+            name = "devnet"
+
+            [defaults]
+            validator_count = 0
+
+            [[node]]
+            name = "alpha"
+            client = "ream"
+
+            [[node]]
+            name = "beta"
+            client = "zeam"
+            validator_count = 2
+            "#;
+        let mut config: NetworkConfig = toml::from_str(source).unwrap();
+        config.apply_defaults();
+        let errors = config
+            .resolve(ResolveOptions::new(
+                Path::new("network.toml"),
+                KeyStore::Skip,
+            ))
+            .unwrap_err();
+
+        let [ConfigError::InvalidValidatorCount(span)] = &errors[..] else {
+            panic!("unexpected errors: {errors:?}");
+        };
+        assert_eq!(&source[span.clone()], "0");
+        assert!(span.start < source.find("[[node]]").unwrap());
+    }
+
+    #[test]
+    fn singular_name_collides_with_generated_one() {
+        let source = r#"
+            name = "devnet"
+
+            [[node]]
+            name = "ream_0"
+            client = "ream"
+
+            [[node]]
+            name = "ream"
+            client = "ream"
+            count = 2
+            "#;
+
+        let errors = resolve(source).unwrap_err();
+        let [
+            ConfigError::DuplicateName {
+                name,
+                curr_def,
+                prev_def,
+            },
+        ] = &errors[..]
+        else {
+            panic!("unexpected errors: {errors:?}");
+        };
+        assert_eq!(name, "ream_0");
+        assert_eq!(curr_def.replica_index(name), Some("0"));
+        assert_eq!(curr_def.collides_with_prefix(prev_def), Some("ream"));
+
+        let labels = [prev_def.labels(name, true), curr_def.labels(name, false)]
+            .concat()
+            .into_iter()
+            .map(|(span, message)| (&source[span], message))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            [
+                ("\"ream_0\"", "`ream_0` first defined here".to_owned()),
+                (
+                    "\"ream\"",
+                    "`ream_0` defined again here, generated from prefix `ream`".to_owned()
+                ),
+                ("2", "`count` makes `ream` a prefix".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn generated_name_collides_with_later_singular_one() {
+        let source = r#"
+            name = "devnet"
+
+            [[node]]
+            name = "ream"
+            client = "ream"
+            count = 2
+
+            [[node]]
+            name = "ream_1"
+            client = "ream"
+            "#;
+
+        let errors = resolve(source).unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ConfigError::DuplicateName {
+                name,
+                curr_def: NodeNameDefinition::Singular(NodeNameSource::Name(_)),
+                prev_def: NodeNameDefinition::Prefix { prefix, .. },
+            }] if name == "ream_1" && prefix == "ream"
+        ));
+    }
+
+    #[test]
+    fn malformed_checkpoint_sync_url_is_rejected() {
+        let source = r#"
+            name = "devnet"
+
+            [[node]]
+            client = "ream"
+            checkpoint_sync_url = "not a url"
+            "#;
+
+        let errors = resolve(source).unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ConfigError::InvalidCheckpointSyncUrl { value, span, .. }]
+                if value == "not a url" && &source[span.clone()] == "\"not a url\""
+        ));
+
+        assert!(check_url("ftp://checkpoint").is_err());
+        assert!(check_url("http://checkpoint:5052").is_ok());
+    }
+
+    #[test]
+    fn unknown_log_level_is_rejected_at_value() {
+        let source = r#"
+            name = "devnet"
+
+            [[node]]
+            client = "ream"
+            log_level = "verbose"
+            "#;
+
+        let errors = resolve(source).unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ConfigError::UnknownLogLevel { value, span }]
+                if value == "verbose" && &source[span.clone()] == "\"verbose\""
+        ));
+    }
+
+    #[test]
+    fn missing_binary_is_reported_at_bin_on_launch_only() {
+        let source = r#"
+            name = "devnet"
+
+            [[node]]
+            name = "alpha"
+            client = { kind = "ream", bin = "missing/ream" }
+            "#;
+
+        let network = resolve(source).unwrap();
+        let errors = network.check_binaries().unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ConfigError::InvalidBinary { span, .. }]
+                if &source[span.clone()] == "\"missing/ream\""
+        ));
+    }
+
+    #[test]
+    fn bare_binary_name_is_looked_up_in_path() {
+        let network = resolve(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            name = "alpha"
+            client = { kind = "ream", bin = "sh" }
+            "#,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            &network.nodes()["alpha"].client,
+            ClientSource::Binary { bin, .. } if bin == Path::new("sh")
+        ));
+        network.check_binaries().unwrap();
     }
 }
@@ -0,0 +1,286 @@
+use toml::Spanned;
+
+use crate::config::{
+    ClientSource, NetworkConfig, NodeConfig, default_count, default_max_count,
+    default_max_validator_count, default_seconds_per_slot, default_slots_per_epoch,
+    default_validator_warning_threshold,
+};
+
+/// Value, which doesn't come from any config file, so has no source span.
+fn unspanned<T>(value: T) -> Spanned<T> {
+    Spanned::new(0..0, value)
+}
+
+/// Builds [`NetworkConfig`] in code, instead of parsing it from TOML.
+///
+/// Built config goes through the same [`NetworkConfig::resolve`] as parsed
+/// one, but errors point at empty spans, since there is no source.
+///
+/// ```
+/// use std::path::Path;
+///
+/// use liesas_quickstart::{
+///     client::ClientKind,
+///     config::{ClientSource, NetworkConfigBuilder, NodeConfigBuilder, ResolveOptions},
+///     validator::KeyStore,
+/// };
+///
+/// let config = NetworkConfigBuilder::new("devnet")
+///     .default_validator_count(4)
+///     .add_node(NodeConfigBuilder::new(ClientSource::Default(ClientKind::Ream)).count(2))
+///     .add_node(
+///         NodeConfigBuilder::new(ClientSource::Default(ClientKind::Zeam))
+///             .name("zeam")
+///             .validator_count(1),
+///     )
+///     .build();
+///
+/// let network = config
+///     .resolve(ResolveOptions::new(
+///         Path::new("network.toml"),
+///         KeyStore::Skip,
+///     ))
+///     .unwrap();
+///
+/// let validators = network
+///     .nodes()
+///     .iter()
+///     .map(|(name, node)| (name.as_str(), node.validators.len()))
+///     .collect::<Vec<_>>();
+/// assert_eq!(validators, [("ream_0", 4), ("ream_1", 4), ("zeam", 1)]);
+/// assert_eq!(network.name(), "devnet");
+/// ```
+#[derive(Debug, Clone)]
+pub struct NetworkConfigBuilder {
+    config: NetworkConfig,
+    default_validator_count: Option<u64>,
+    nodes: Vec<NodeConfigBuilder>,
+}
+
+impl NetworkConfigBuilder {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            config: NetworkConfig {
+                version: None,
+                name: name.into(),
+                seconds_per_slot: default_seconds_per_slot(),
+                slots_per_epoch: default_slots_per_epoch(),
+                genesis_time: None,
+                genesis_delay: None,
+                bind_address: None,
+                post_start: None,
+                env: Default::default(),
+                stop_timeout: None,
+                max_count: default_max_count(),
+                max_validator_count: default_max_validator_count(),
+                validator_warning_threshold: default_validator_warning_threshold(),
+                validator_index_offset: 0,
+                seed: None,
+                allow_disabled: false,
+                registry: Default::default(),
+                include: Vec::new(),
+                defaults: None,
+                node: Vec::new(),
+            },
+            default_validator_count: None,
+            nodes: Vec::new(),
+        }
+    }
+
+    pub fn network_name(mut self, name: impl Into<String>) -> Self {
+        self.config.name = name.into();
+        self
+    }
+
+    pub fn seconds_per_slot(mut self, value: u64) -> Self {
+        self.config.seconds_per_slot = unspanned(value);
+        self
+    }
+
+    pub fn slots_per_epoch(mut self, value: u64) -> Self {
+        self.config.slots_per_epoch = unspanned(value);
+        self
+    }
+
+    pub fn genesis_time(mut self, timestamp: u64) -> Self {
+        self.config.genesis_time = Some(unspanned(timestamp));
+        self
+    }
+
+    /// Set environment variable of every node.
+    pub fn env(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.config.env.insert(unspanned(name.into()), value.into());
+        self
+    }
+
+    pub fn validator_index_offset(mut self, offset: usize) -> Self {
+        self.config.validator_index_offset = offset;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.config.seed = Some(seed);
+        self
+    }
+
+    /// Validator count of nodes, which don't set it explicitly. When not set,
+    /// client default is used.
+    pub fn default_validator_count(mut self, count: u64) -> Self {
+        self.default_validator_count = Some(count);
+        self
+    }
+
+    pub fn add_node(mut self, node: NodeConfigBuilder) -> Self {
+        self.nodes.push(node);
+        self
+    }
+
+    pub fn build(self) -> NetworkConfig {
+        let mut config = self.config;
+
+        config.node = self
+            .nodes
+            .into_iter()
+            .map(|node| {
+                let mut node = node.node;
+                if node.validator_count.is_none() {
+                    node.validator_count = self.default_validator_count.map(unspanned);
+                }
+
+                node
+            })
+            .collect();
+
+        config.apply_defaults();
+        config
+    }
+}
+
+/// Builds single node entry of [`NetworkConfigBuilder`].
+#[derive(Debug, Clone)]
+pub struct NodeConfigBuilder {
+    node: NodeConfig,
+}
+
+impl NodeConfigBuilder {
+    pub fn new(client: ClientSource) -> Self {
+        Self {
+            node: NodeConfig {
+                name: None,
+                client: unspanned(client),
+                count: default_count(),
+                validator_count: None,
+                extra_args: Vec::new(),
+                image_tag: None,
+                working_dir: None,
+                group: None,
+                ulimits: Default::default(),
+                sysctls: Default::default(),
+                peers: Vec::new(),
+                bootnode: false,
+                health_path: None,
+                checkpoint_sync_url: None,
+                log_level: None,
+                volumes: Vec::new(),
+                env: Default::default(),
+                cpus: None,
+                memory: None,
+                jwt: None,
+                bind_address: None,
+                depends_on: Vec::new(),
+                stop_timeout: None,
+                restart_policy: None,
+                activation_epoch: None,
+                num_active_epochs: None,
+                p2p_port: None,
+                rpc_port: None,
+                metrics_port: None,
+            },
+        }
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.node.name = Some(unspanned(name.into()));
+        self
+    }
+
+    pub fn count(mut self, count: u64) -> Self {
+        self.node.count = unspanned(count);
+        self
+    }
+
+    pub fn validator_count(mut self, count: u64) -> Self {
+        self.node.validator_count = Some(unspanned(count));
+        self
+    }
+
+    /// Connect to external peer, given as multiaddr or ENR.
+    pub fn peer(mut self, peer: impl Into<String>) -> Self {
+        self.node.peers.push(unspanned(peer.into()));
+        self
+    }
+
+    pub fn bootnode(mut self, bootnode: bool) -> Self {
+        self.node.bootnode = bootnode;
+        self
+    }
+
+    pub fn health_path(mut self, path: impl Into<String>) -> Self {
+        self.node.health_path = Some(unspanned(path.into()));
+        self
+    }
+
+    pub fn checkpoint_sync_url(mut self, url: impl Into<String>) -> Self {
+        self.node.checkpoint_sync_url = Some(unspanned(url.into()));
+        self
+    }
+
+    pub fn log_level(mut self, level: impl Into<String>) -> Self {
+        self.node.log_level = Some(unspanned(level.into()));
+        self
+    }
+
+    /// Mount volume, given as `"<host path>:<container path>[:ro]"`.
+    pub fn volume(mut self, volume: impl Into<String>) -> Self {
+        self.node.volumes.push(unspanned(volume.into()));
+        self
+    }
+
+    /// Set environment variable of the node, overriding network-wide one.
+    pub fn env(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.node.env.insert(unspanned(name.into()), value.into());
+        self
+    }
+
+    pub fn cpus(mut self, cpus: f64) -> Self {
+        self.node.cpus = Some(unspanned(cpus));
+        self
+    }
+
+    /// Memory limit, e.g. `"512m"`.
+    pub fn memory(mut self, memory: impl Into<String>) -> Self {
+        self.node.memory = Some(unspanned(memory.into()));
+        self
+    }
+
+    /// Restart policy of the container, e.g. `"unless-stopped"`.
+    pub fn restart_policy(mut self, policy: impl Into<String>) -> Self {
+        self.node.restart_policy = Some(unspanned(policy.into()));
+        self
+    }
+
+    pub fn extra_args(mut self, args: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.node.extra_args = args.into_iter().map(|arg| unspanned(arg.into())).collect();
+        self
+    }
+
+    pub fn group(mut self, group: impl Into<String>) -> Self {
+        self.node.group = Some(group.into());
+        self
+    }
+
+    pub fn depends_on(mut self, node: impl Into<String>) -> Self {
+        self.node.depends_on.push(unspanned(node.into()));
+        self
+    }
+}
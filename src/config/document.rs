@@ -0,0 +1,313 @@
+use std::{borrow::Cow, fmt, ops::Range};
+
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use toml::{
+    Spanned,
+    de::{DeArray, DeTable, DeValue},
+};
+
+use super::{ConfigError, interpolate::interpolate_value};
+
+/// Value of the document, with numbers kept as TOML text, which TOML document
+/// borrows, see [`Document::to_toml`].
+#[derive(Debug, Clone)]
+enum Node {
+    Boolean(bool),
+    Integer(String),
+    Float(String),
+    String(String),
+    Array(Vec<Spanned<Node>>),
+    Table(Vec<(Spanned<String>, Spanned<Node>)>),
+}
+
+impl Node {
+    /// Expand environment variables in strings of the value, found at
+    /// `span`.
+    fn interpolate(&mut self, span: Range<usize>) -> Result<(), ConfigError> {
+        match self {
+            Self::String(value) => *value = interpolate_value(value, span)?,
+            Self::Array(items) => {
+                for item in items {
+                    let span = item.span();
+                    item.get_mut().interpolate(span)?;
+                }
+            }
+            Self::Table(entries) => interpolate_entries(entries)?,
+            Self::Boolean(_) | Self::Integer(_) | Self::Float(_) => {}
+        }
+
+        Ok(())
+    }
+
+    fn to_toml(&self) -> DeValue<'_> {
+        match self {
+            Self::Boolean(value) => DeValue::Boolean(*value),
+            Self::Integer(text) | Self::Float(text) => DeValue::parse(text)
+                .expect("numbers are checked when read")
+                .into_inner(),
+            Self::String(value) => DeValue::String(Cow::Borrowed(value)),
+            Self::Array(items) => {
+                let mut array = DeArray::new();
+                for item in items {
+                    array.push(Spanned::new(item.span(), item.get_ref().to_toml()));
+                }
+                DeValue::Array(array)
+            }
+            Self::Table(entries) => DeValue::Table(table(entries)),
+        }
+    }
+}
+
+fn interpolate_entries(
+    entries: &mut [(Spanned<String>, Spanned<Node>)],
+) -> Result<(), ConfigError> {
+    for (_, value) in entries {
+        let span = value.span();
+        value.get_mut().interpolate(span)?;
+    }
+
+    Ok(())
+}
+
+fn table(entries: &[(Spanned<String>, Spanned<Node>)]) -> DeTable<'_> {
+    let mut table = DeTable::new();
+    for (key, value) in entries {
+        table.insert(
+            Spanned::new(key.span(), Cow::Borrowed(key.get_ref().as_str())),
+            Spanned::new(value.span(), value.get_ref().to_toml()),
+        );
+    }
+    table
+}
+
+/// Finds values of the document in its source, in document order.
+///
+/// Parsers of other formats don't report positions of values, so each scalar
+/// is searched for in the source after the previous one. This finds most of
+/// them, but not e.g. strings with escape sequences, which get empty span
+/// right after the previous value instead.
+struct Locator<'a> {
+    source: &'a str,
+
+    /// Offset of the source in the shared span space, see
+    /// [`Sources`](crate::codespan::Sources).
+    offset: usize,
+    cursor: usize,
+}
+
+impl Locator<'_> {
+    fn find(&mut self, text: &str) -> Range<usize> {
+        if let Some(start) = self.source[self.cursor..].find(text) {
+            self.cursor += start + text.len();
+            return self.offset + self.cursor - text.len()..self.offset + self.cursor;
+        }
+
+        self.here()
+    }
+
+    fn here(&self) -> Range<usize> {
+        self.offset + self.cursor..self.offset + self.cursor
+    }
+
+    /// Number, written as `text` in TOML.
+    fn number<E: de::Error>(
+        &mut self,
+        text: String,
+        node: fn(String) -> Node,
+    ) -> Result<Option<Spanned<Node>>, E> {
+        if DeValue::parse(&text).is_err() {
+            return Err(E::custom(format!(
+                "`{text}` can't be represented in config"
+            )));
+        }
+
+        Ok(Some(Spanned::new(self.find(&text), node(text))))
+    }
+}
+
+/// Key of a mapping, which is always a string in TOML.
+struct KeySeed<'a, 'b>(&'b mut Locator<'a>);
+
+impl<'de> DeserializeSeed<'de> for KeySeed<'_, '_> {
+    type Value = Spanned<String>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for KeySeed<'_, '_> {
+    type Value = Spanned<String>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("string key")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(Spanned::new(self.0.find(v), v.to_owned()))
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        self.visit_str(&v.to_string())
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        self.visit_str(&v.to_string())
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        self.visit_str(&v.to_string())
+    }
+}
+
+/// Any value of the document, `None` for `null`, which TOML doesn't have.
+struct ValueSeed<'a, 'b>(&'b mut Locator<'a>);
+
+impl<'de> DeserializeSeed<'de> for ValueSeed<'_, '_> {
+    type Value = Option<Spanned<Node>>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ValueSeed<'_, '_> {
+    type Value = Option<Spanned<Node>>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("config value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        let span = self.0.find(&v.to_string());
+        Ok(Some(Spanned::new(span, Node::Boolean(v))))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        self.0.number(v.to_string(), Node::Integer)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        self.0.number(v.to_string(), Node::Integer)
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        // Debug keeps fraction of whole numbers, so they stay floats.
+        let text = match v {
+            v if v.is_nan() => "nan".to_owned(),
+            v if v.is_infinite() => format!("{}inf", if v < 0.0 { "-" } else { "" }),
+            v => format!("{v:?}"),
+        };
+
+        self.0.number(text, Node::Float)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        let span = self.0.find(v);
+        Ok(Some(Spanned::new(span, Node::String(v.to_owned()))))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut start = None;
+        let mut items = Vec::new();
+
+        while let Some(item) = seq.next_element_seed(ValueSeed(&mut *self.0))? {
+            let item = item.ok_or_else(|| de::Error::custom("arrays can't contain null"))?;
+            start.get_or_insert(item.span().start);
+            items.push(item);
+        }
+
+        let end = self.0.here().end;
+        Ok(Some(Spanned::new(
+            start.unwrap_or(end)..end,
+            Node::Array(items),
+        )))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut start = None;
+        let mut entries = Vec::new();
+
+        while let Some(key) = map.next_key_seed(KeySeed(&mut *self.0))? {
+            start.get_or_insert(key.span().start);
+
+            // Null stands for missing value, same as omitted key in TOML.
+            if let Some(value) = map.next_value_seed(ValueSeed(&mut *self.0))? {
+                entries.push((key, value));
+            }
+        }
+
+        let end = self.0.here().end;
+        Ok(Some(Spanned::new(
+            start.unwrap_or(end)..end,
+            Node::Table(entries),
+        )))
+    }
+}
+
+/// Config document, read from a format other than TOML, which can be
+/// deserialized the same way as TOML config, including [`Spanned`] values.
+#[derive(Debug, Clone)]
+pub struct Document {
+    span: Range<usize>,
+    entries: Vec<(Spanned<String>, Spanned<Node>)>,
+}
+
+impl Document {
+    /// Read document from `deserializer` of any self-describing format.
+    ///
+    /// `source` is the text, `deserializer` reads, starting at `offset` in
+    /// the shared span space. Spans are best-effort, see [`Locator`].
+    pub fn read<'de, D: Deserializer<'de>>(
+        deserializer: D,
+        source: &str,
+        offset: usize,
+    ) -> Result<Self, D::Error> {
+        let mut locator = Locator {
+            source,
+            offset,
+            cursor: 0,
+        };
+
+        match ValueSeed(&mut locator).deserialize(deserializer)? {
+            Some(value) => {
+                let span = value.span();
+                match value.into_inner() {
+                    Node::Table(entries) => Ok(Self { span, entries }),
+                    _ => Err(de::Error::custom("config must be a mapping")),
+                }
+            }
+            // Empty document.
+            None => Ok(Self {
+                span: locator.here(),
+                entries: Vec::new(),
+            }),
+        }
+    }
+
+    /// Expand environment variables in string values of the document.
+    ///
+    /// TOML source is expanded as text instead, see
+    /// [`interpolate`](super::interpolate::interpolate), which can't be done
+    /// for formats with unquoted strings.
+    pub fn interpolate(&mut self) -> Result<(), ConfigError> {
+        interpolate_entries(&mut self.entries)
+    }
+
+    /// Document as TOML, to deserialize with [`toml::de::Deserializer`].
+    pub fn to_toml(&self) -> Spanned<DeTable<'_>> {
+        Spanned::new(self.span.clone(), table(&self.entries))
+    }
+}
@@ -0,0 +1,228 @@
+use std::{env, ops::Range};
+
+use crate::config::ConfigError;
+
+/// Kind of TOML string, which affects how substituted values are escaped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Quote {
+    /// `"..."` or `"""..."""`, supporting escape sequences.
+    Basic,
+
+    /// `'...'` or `'''...'''`, taken verbatim.
+    Literal,
+}
+
+/// Expand `${VAR}` and `${VAR:-default}` in string values of TOML `source`,
+/// using process environment. `$$` stands for literal `$`.
+///
+/// Only strings are expanded, so that commented out references don't need
+/// the variable to be set. Substituted values are escaped, so that
+/// they can't break out of the string they are in.
+pub fn interpolate(source: &str) -> Result<String, ConfigError> {
+    let mut output = String::with_capacity(source.len());
+    let mut rest = source;
+    let mut string: Option<(Quote, &str)> = None;
+
+    while let Some(c) = rest.chars().next() {
+        let offset = source.len() - rest.len();
+
+        match string {
+            None => {
+                if c == '#' {
+                    let end = rest.find('\n').unwrap_or(rest.len());
+                    output.push_str(&rest[..end]);
+                    rest = &rest[end..];
+                    continue;
+                }
+
+                let quote = ["\"\"\"", "'''", "\"", "'"]
+                    .into_iter()
+                    .find(|quote| rest.starts_with(quote));
+
+                if let Some(quote) = quote {
+                    let kind = if quote.starts_with('"') {
+                        Quote::Basic
+                    } else {
+                        Quote::Literal
+                    };
+
+                    string = Some((kind, quote));
+                    output.push_str(quote);
+                    rest = &rest[quote.len()..];
+                    continue;
+                }
+            }
+            Some((kind, quote)) => {
+                if kind == Quote::Basic && c == '\\' {
+                    // Keep escape sequence as is, including escaped quote.
+                    let len = rest[1..].chars().next().map_or(0, char::len_utf8) + 1;
+                    output.push_str(&rest[..len]);
+                    rest = &rest[len..];
+                    continue;
+                }
+
+                if rest.starts_with(quote) {
+                    string = None;
+                    output.push_str(quote);
+                    rest = &rest[quote.len()..];
+                    continue;
+                }
+
+                if let Some(after) = rest.strip_prefix("$$") {
+                    output.push('$');
+                    rest = after;
+                    continue;
+                }
+
+                if rest.starts_with("${") {
+                    let (value, len) = reference(rest, offset)?;
+
+                    match kind {
+                        Quote::Basic => {
+                            for c in value.chars() {
+                                match c {
+                                    '"' => output.push_str("\\\""),
+                                    '\\' => output.push_str("\\\\"),
+                                    '\n' => output.push_str("\\n"),
+                                    c => output.push(c),
+                                }
+                            }
+                        }
+                        Quote::Literal => output.push_str(&value),
+                    }
+
+                    rest = &rest[len..];
+                    continue;
+                }
+            }
+        }
+
+        output.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    Ok(output)
+}
+
+/// Value of reference `${...}` at the start of `rest`, which is at `offset`
+/// in the source, together with length of the reference.
+fn reference(rest: &str, offset: usize) -> Result<(String, usize), ConfigError> {
+    let after = &rest[2..];
+    let end = after
+        .find('}')
+        .filter(|&end| !after[..end].contains('\n'))
+        .ok_or(ConfigError::InvalidInterpolation(offset..offset + 2))?;
+
+    let reference = &after[..end];
+    let span = offset..offset + end + 3;
+
+    let (name, default) = match reference.split_once(":-") {
+        Some((name, default)) => (name, Some(default)),
+        None => (reference, None),
+    };
+
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(ConfigError::InvalidInterpolation(span));
+    }
+
+    let value = match (env::var(name).ok(), default) {
+        (Some(value), _) => value,
+        (None, Some(default)) => default.to_owned(),
+        (None, None) => {
+            return Err(ConfigError::UndefinedVariable {
+                name: name.to_owned(),
+                span,
+            });
+        }
+    };
+
+    Ok((value, end + 3))
+}
+
+/// Expand `${VAR}` and `${VAR:-default}` in string `value`, parsed from
+/// format other than TOML, which was found at `span` of the source.
+///
+/// Value is already unquoted, so substituted values need no escaping.
+/// Errors point at the reference, if value is written in the source as is,
+/// and at the whole value otherwise, e.g. when it has escape sequences.
+pub fn interpolate_value(value: &str, span: Range<usize>) -> Result<String, ConfigError> {
+    let verbatim = span.len() == value.len();
+    let mut output = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("$$") {
+            output.push('$');
+            rest = after;
+            continue;
+        }
+
+        if rest.starts_with("${") {
+            let offset = span.start + value.len() - rest.len();
+            let (value, len) = reference(rest, offset).map_err(|err| match err {
+                err if verbatim => err,
+                ConfigError::UndefinedVariable { name, .. } => ConfigError::UndefinedVariable {
+                    name,
+                    span: span.clone(),
+                },
+                _ => ConfigError::InvalidInterpolation(span.clone()),
+            })?;
+
+            output.push_str(&value);
+            rest = &rest[len..];
+            continue;
+        }
+
+        output.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Variable, which is never set in test environment.
+    const UNSET: &str = "LIESAS_TEST_UNSET_VARIABLE";
+
+    #[test]
+    fn undefined_variable_is_reported_at_reference() {
+        let source = format!("image = \"ream:${{{UNSET}}}\"\n");
+
+        let error = interpolate(&source).unwrap_err();
+        let ConfigError::UndefinedVariable { name, span } = &error else {
+            panic!("unexpected error: {error:?}");
+        };
+        assert_eq!(name, UNSET);
+        assert_eq!(&source[span.clone()], format!("${{{UNSET}}}"));
+    }
+
+    #[test]
+    fn default_is_used_for_undefined_variable() {
+        let source = format!("image = \"ream:${{{UNSET}:-v1}}\"\nbin = '${{{UNSET}:-a\"b}}'\n");
+
+        assert_eq!(
+            interpolate(&source).unwrap(),
+            "image = \"ream:v1\"\nbin = 'a\"b'\n"
+        );
+    }
+
+    #[test]
+    fn double_dollar_is_escaped() {
+        let source = format!("cmd = \"echo $${{{UNSET}}}\"\n");
+
+        assert_eq!(
+            interpolate(&source).unwrap(),
+            format!("cmd = \"echo ${{{UNSET}}}\"\n")
+        );
+    }
+
+    #[test]
+    fn comments_are_not_expanded() {
+        let source = format!("# image = \"${{{UNSET}}}\"\nname = \"devnet\"\n");
+
+        assert_eq!(interpolate(&source).unwrap(), source);
+    }
+}
@@ -0,0 +1,567 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use color_eyre::{
+    Result,
+    eyre::Context as _,
+    owo_colors::{OwoColorize, Stream},
+};
+use serde::de::DeserializeOwned;
+use strum::Display;
+use tokio::{fs::File, io::AsyncReadExt};
+use toml::Spanned;
+use tracing::{debug, info};
+
+use crate::{
+    codespan::{Sources, report_config_error, report_parse_error},
+    config::{
+        ConfigError, ConfigFile, ConfigHeader, IncludedConfig, NetworkConfig, NodeConfig, Span,
+        document::Document, interpolate::interpolate,
+    },
+    exit::Failure,
+    output,
+};
+
+/// `--config` value, which makes config read from stdin.
+pub const STDIN_CONFIG: &str = "-";
+
+/// Format of config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, ValueEnum)]
+#[strum(serialize_all = "lowercase")]
+pub enum ConfigFormat {
+    Toml,
+    Yaml,
+    Json,
+}
+
+impl ConfigFormat {
+    /// Format of config at `path`, by its extension. Anything, but `.yml`,
+    /// `.yaml` and `.json`, is TOML.
+    pub fn detect(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("yml" | "yaml") => Self::Yaml,
+            Some("json") => Self::Json,
+            _ => Self::Toml,
+        }
+    }
+}
+
+/// Error, found while parsing config file, whichever format it is written in.
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub message: String,
+
+    /// Location of the error within the file, if known.
+    pub span: Option<Span>,
+}
+
+impl From<toml::de::Error> for ParseError {
+    fn from(error: toml::de::Error) -> Self {
+        Self {
+            message: error.message().to_owned(),
+            span: error.span(),
+        }
+    }
+}
+
+impl From<serde_yaml::Error> for ParseError {
+    fn from(error: serde_yaml::Error) -> Self {
+        Self {
+            message: strip_location(error.to_string()),
+            span: error
+                .location()
+                .map(|location| location.index()..location.index()),
+        }
+    }
+}
+
+impl ParseError {
+    /// Error of parsing JSON `source`, which reports line and column only.
+    fn json(error: serde_json::Error, source: &str) -> Self {
+        // Line is zero, when error isn't related to particular position.
+        let span = (error.line() > 0).then(|| {
+            let line_start = source
+                .split_inclusive('\n')
+                .take(error.line() - 1)
+                .map(str::len)
+                .sum::<usize>();
+            let index = (line_start + error.column().saturating_sub(1)).min(source.len());
+            index..index
+        });
+
+        Self {
+            message: strip_location(error.to_string()),
+            span,
+        }
+    }
+}
+
+/// Message of YAML or JSON error, without location, which is reported
+/// separately.
+fn strip_location(message: String) -> String {
+    match message.rsplit_once(" at line ") {
+        Some((message, _)) => message.to_owned(),
+        None => message,
+    }
+}
+
+/// Parse `source` of config file at `path`, which starts at `offset` in the
+/// shared span space, see [`Sources`].
+///
+/// YAML and JSON are read into TOML document first, so that all formats are
+/// deserialized the same way, see [`Document`]. Environment variables are
+/// expanded in its string values, and errors of that are reported against
+/// `sources`.
+fn parse<T: DeserializeOwned>(
+    format: ConfigFormat,
+    path: &Path,
+    source: &str,
+    offset: usize,
+    sources: &Sources,
+) -> T {
+    let interpolate = |mut document: Document| match document.interpolate() {
+        Ok(()) => document,
+        Err(err) => report_config_error(sources, err),
+    };
+
+    // Errors of deserializing TOML document have spans, shifted by offset,
+    // while errors of other parsers are relative to the file.
+    let shifted = |error: toml::de::Error| {
+        let mut error = ParseError::from(error);
+        error.span = error
+            .span
+            .map(|span| span.start.saturating_sub(offset)..span.end.saturating_sub(offset));
+        error
+    };
+
+    let result = match format {
+        // Padding shifts spans by offset.
+        ConfigFormat::Toml => toml::de::from_str(&(" ".repeat(offset) + source)).map_err(shifted),
+        ConfigFormat::Yaml => {
+            Document::read(serde_yaml::Deserializer::from_str(source), source, offset)
+                .map_err(ParseError::from)
+                .map(interpolate)
+                .and_then(|document| {
+                    T::deserialize(toml::de::Deserializer::from(document.to_toml()))
+                        .map_err(shifted)
+                })
+        }
+        ConfigFormat::Json => {
+            let mut deserializer = serde_json::Deserializer::from_str(source);
+            Document::read(&mut deserializer, source, offset)
+                .and_then(|document| deserializer.end().map(|()| document))
+                .map_err(|err| ParseError::json(err, source))
+                .map(interpolate)
+                .and_then(|document| {
+                    T::deserialize(toml::de::Deserializer::from(document.to_toml()))
+                        .map_err(shifted)
+                })
+        }
+    };
+
+    match result {
+        Ok(value) => value,
+        Err(err) => report_parse_error(
+            "Invalid network configuration".to_owned(),
+            format,
+            path.to_owned(),
+            source.to_owned(),
+            err,
+        ),
+    }
+}
+
+/// Read config file and pick a single network from it, with nodes of
+/// included files appended.
+///
+/// Config is read from stdin, if `path` is [`STDIN_CONFIG`]. Its format is
+/// detected by extension, unless `format` is given.
+///
+/// Environment variables in string values are expanded first, see
+/// [`interpolate`]. Returns parsed network together with the expanded
+/// sources, which are needed to report errors found during resolution.
+/// Parse errors are reported directly, and terminate the process.
+pub async fn load_network_config(
+    path: &Path,
+    format: Option<ConfigFormat>,
+    network: Option<&str>,
+) -> Result<(NetworkConfig, Sources)> {
+    let mut buffer = String::new();
+    let name = if path == Path::new(STDIN_CONFIG) {
+        tokio::io::stdin()
+            .read_to_string(&mut buffer)
+            .await
+            .context("failed to read config from stdin")?;
+
+        // Name, under which config is shown in diagnostics.
+        Path::new("<stdin>")
+    } else {
+        let mut file = File::open(path)
+            .await
+            .context(format!("failed to read config at {path:?}"))?;
+        file.read_to_string(&mut buffer)
+            .await
+            .context("invalid network config")?;
+
+        path
+    };
+
+    let format = format.unwrap_or_else(|| ConfigFormat::detect(path));
+    let buffer = expand(name, format, buffer);
+    let mut sources = Sources::new(name.to_owned(), buffer.clone());
+
+    info!("loading {format} config from {name:?}");
+    let header: ConfigHeader = parse(format, name, &buffer, 0, &sources);
+
+    if let Err(err) = header.check() {
+        report_config_error(&sources, err);
+    }
+
+    let file = if header.has_networks() {
+        ConfigFile::Multiple(parse(format, name, &buffer, 0, &sources))
+    } else {
+        ConfigFile::Single(parse(format, name, &buffer, 0, &sources))
+    };
+
+    let mut config = match file.select(network) {
+        Ok(value) => value,
+        Err(err) => {
+            if output::is_json() {
+                output::failure(Some(Failure::Usage), err.to_string(), Vec::new());
+            }
+
+            eprintln!(
+                "{label} {err}",
+                label = "Error:".if_supports_color(Stream::Stderr, |text| text.red())
+            );
+            Failure::Usage.exit();
+        }
+    };
+
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    let mut stack = vec![fs::canonicalize(path).unwrap_or_else(|_| path.to_owned())];
+    let includes = std::mem::take(&mut config.include);
+
+    for include in &includes {
+        match load_included(base_dir, include, &mut stack, &mut sources) {
+            Ok(nodes) => config.node.extend(nodes),
+            Err(err) => report_config_error(&sources, err),
+        }
+    }
+
+    config.apply_defaults();
+    debug!(
+        "loaded network `{}` with {} node(s)",
+        config.name,
+        config.node.len()
+    );
+
+    Ok((config, sources))
+}
+
+/// Expand environment variables in TOML config at `path`, reporting invalid
+/// references against the original source.
+///
+/// Other formats are expanded once parsed, see [`parse`].
+fn expand(path: &Path, format: ConfigFormat, source: String) -> String {
+    if format != ConfigFormat::Toml {
+        return source;
+    }
+
+    match interpolate(&source) {
+        Ok(value) => value,
+        Err(err) => report_config_error(&Sources::new(path.to_owned(), source), err),
+    }
+}
+
+/// Read nodes of included config, and everything it includes in turn.
+///
+/// `stack` holds canonical paths of files, which are currently being
+/// included, to detect cycles.
+fn load_included(
+    base_dir: &Path,
+    include: &Spanned<PathBuf>,
+    stack: &mut Vec<PathBuf>,
+    sources: &mut Sources,
+) -> Result<Vec<NodeConfig>, ConfigError> {
+    let path = base_dir.join(include.get_ref());
+    let read_error = |error| ConfigError::IncludeFile {
+        path: path.clone(),
+        error,
+        span: include.span(),
+    };
+
+    let canonical = fs::canonicalize(&path).map_err(read_error)?;
+    if let Some(start) = stack.iter().position(|included| *included == canonical) {
+        let mut cycle = stack[start..].to_vec();
+        cycle.push(canonical);
+
+        return Err(ConfigError::IncludeCycle {
+            cycle,
+            span: include.span(),
+        });
+    }
+
+    // Included file may be written in other format, than the config.
+    let format = ConfigFormat::detect(&path);
+    let source = expand(
+        &path,
+        format,
+        fs::read_to_string(&path).map_err(read_error)?,
+    );
+    debug!("including {format} config from {path:?}");
+
+    // Parse file shifted to its offset in `sources`, so that spans of its
+    // values don't overlap with spans of other files.
+    let offset = sources.add(path.clone(), source.clone());
+    let included: IncludedConfig = parse(format, &path, &source, offset, sources);
+
+    stack.push(canonical);
+    let mut nodes = included.node;
+    for nested in &included.include {
+        nodes.extend(load_included(
+            path.parent().unwrap_or(Path::new("")),
+            nested,
+            stack,
+            sources,
+        )?);
+    }
+    stack.pop();
+
+    Ok(nodes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+    use crate::{config::ResolveOptions, validator::KeyStore};
+
+    const TOML: &str = r#"
+name = "devnet"
+
+[[node]]
+name = "alpha"
+client = "ream"
+validator_count = 2
+
+[[node]]
+client = { kind = "zeam", image = "blockblaz/zeam:v1" }
+count = 0
+"#;
+
+    const YAML: &str = r#"
+name: devnet
+node:
+  - name: alpha
+    client: ream
+    validator_count: 2
+  - client:
+      kind: zeam
+      image: blockblaz/zeam:v1
+    count: 0
+"#;
+
+    const JSON: &str = r#"{
+  "name": "devnet",
+  "node": [
+    { "name": "alpha", "client": "ream", "validator_count": 2 },
+    { "client": { "kind": "zeam", "image": "blockblaz/zeam:v1" }, "count": 0 }
+  ]
+}
+"#;
+
+    /// Write config `source` to a file `name` in a fresh temporary directory.
+    fn write_config(test: &str, name: &str, source: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("liesas-load-{test}-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join(name);
+        fs::write(&path, source).unwrap();
+        path
+    }
+
+    /// Load config at `path`, and resolve it into name, image and validators
+    /// of each node.
+    async fn load(
+        path: &Path,
+        format: Option<ConfigFormat>,
+    ) -> Result<Vec<(String, Option<String>, Vec<usize>)>, (Vec<ConfigError>, Sources)> {
+        let (config, sources) = load_network_config(path, format, None).await.unwrap();
+        let network = config
+            .resolve(ResolveOptions::new(path, KeyStore::Skip))
+            .map_err(|errors| (errors, sources))?;
+
+        Ok(network
+            .nodes()
+            .iter()
+            .map(|(name, node)| {
+                (
+                    name.clone(),
+                    node.client.image().map(str::to_owned),
+                    node.validators.clone(),
+                )
+            })
+            .collect())
+    }
+
+    #[tokio::test]
+    async fn yaml_config_is_parsed_like_toml() {
+        let toml = write_config(
+            "yaml",
+            "network.toml",
+            &TOML.replace("count = 0", "count = 1"),
+        );
+        let yaml = write_config(
+            "yaml",
+            "network.yaml",
+            &YAML.replace("count: 0", "count: 1"),
+        );
+
+        let expected = load(&toml, None).await.unwrap();
+        assert_eq!(expected.len(), 2);
+        assert_eq!(load(&yaml, None).await.unwrap(), expected);
+
+        fs::remove_dir_all(toml.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn yaml_errors_point_at_value() {
+        let path = write_config("yaml-span", "network.yml", YAML);
+
+        let (errors, sources) = load(&path, None).await.unwrap_err();
+        let [ConfigError::InvalidCount(span)] = &errors[..] else {
+            panic!("unexpected errors: {errors:?}");
+        };
+        let (file, span) = sources.locate(span.clone());
+        assert_eq!(file, path.display().to_string());
+        assert_eq!(&YAML[span], "0");
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn forced_format_overrides_extension() {
+        let toml = write_config(
+            "forced",
+            "network.toml",
+            &TOML.replace("count = 0", "count = 1"),
+        );
+        let expected = load(&toml, None).await.unwrap();
+
+        for (format, source) in [
+            (ConfigFormat::Toml, TOML.replace("count = 0", "count = 1")),
+            (ConfigFormat::Yaml, YAML.replace("count: 0", "count: 1")),
+            (
+                ConfigFormat::Json,
+                JSON.replace("\"count\": 0", "\"count\": 1"),
+            ),
+        ] {
+            let path = write_config("forced", "network.conf", &source);
+            assert_eq!(
+                load(&path, Some(format)).await.unwrap(),
+                expected,
+                "{format}"
+            );
+        }
+
+        fs::remove_dir_all(toml.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn json_config_is_resolved_like_toml() {
+        let toml = write_config(
+            "json",
+            "network.toml",
+            &TOML.replace("count = 0", "count = 1"),
+        );
+        let json = write_config(
+            "json",
+            "network.json",
+            &JSON.replace("\"count\": 0", "\"count\": 1"),
+        );
+
+        assert_eq!(
+            load(&json, None).await.unwrap(),
+            load(&toml, None).await.unwrap()
+        );
+
+        // Values keep their spans, even though JSON parser reports only line
+        // and column.
+        let (errors, sources) = load(&write_config("json", "invalid.json", JSON), None)
+            .await
+            .unwrap_err();
+        let [ConfigError::InvalidCount(span)] = &errors[..] else {
+            panic!("unexpected errors: {errors:?}");
+        };
+        assert_eq!(&JSON[sources.locate(span.clone()).1], "0");
+
+        fs::remove_dir_all(toml.parent().unwrap()).unwrap();
+    }
+
+    /// Variable, which is never set in test environment.
+    const UNSET: &str = "LIESAS_TEST_UNSET_VARIABLE";
+
+    #[tokio::test]
+    async fn yaml_strings_are_expanded_after_parsing() {
+        // Plain scalars, and apostrophes, which don't delimit strings as in
+        // TOML.
+        let yaml = r#"
+name: devnet
+node:
+  - name: alpha
+    client:
+      kind: zeam
+      image: blockblaz/zeam:${UNSET:-v2}
+  - name: beta
+    client:
+      kind: zeam
+      image: 'blockblaz/zeam:${UNSET:-v3}'
+    env:
+      NOTE: it's
+      QUOTED: 'it''s $$HOME'
+"#
+        .replace("UNSET", UNSET);
+        let path = write_config("yaml-expand", "network.yaml", &yaml);
+
+        let (config, _) = load_network_config(&path, None, None).await.unwrap();
+        let network = config
+            .resolve(ResolveOptions::new(&path, KeyStore::Skip))
+            .unwrap();
+        assert_eq!(
+            network.nodes()["alpha"].client.image(),
+            Some("blockblaz/zeam:v2")
+        );
+        assert_eq!(
+            network.nodes()["beta"].client.image(),
+            Some("blockblaz/zeam:v3")
+        );
+        assert_eq!(network.nodes()["beta"].env["NOTE"], "it's");
+        assert_eq!(network.nodes()["beta"].env["QUOTED"], "it's $HOME");
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn undefined_variable_in_yaml_is_reported_at_reference() {
+        let yaml =
+            "name: devnet\nnode:\n  - client:\n      kind: zeam\n      image: zeam:${UNSET}\n"
+                .replace("UNSET", UNSET);
+        let sources = Sources::new(PathBuf::from("network.yaml"), yaml.clone());
+
+        let mut document =
+            Document::read(serde_yaml::Deserializer::from_str(&yaml), &yaml, 0).unwrap();
+        let error = document.interpolate().unwrap_err();
+        let ConfigError::UndefinedVariable { span, .. } = &error else {
+            panic!("unexpected error: {error:?}");
+        };
+        assert_eq!(
+            &yaml[sources.locate(span.clone()).1],
+            format!("${{{UNSET}}}")
+        );
+    }
+}
@@ -0,0 +1,469 @@
+use ariadne::{Label, ReportKind};
+use thiserror::Error;
+
+use crate::{
+    client::{ClientKind, NodeSetting},
+    codespan::Sources,
+    config::{
+        ConfigError, NetworkConfig, ResolveOptions, ResolvedNetworkConfig, Span, ValidatorShare,
+        validator_shares,
+    },
+    container::ContainerSpec,
+    output,
+    process::ProcessSpec,
+};
+
+/// Suspicious, but valid config, which doesn't prevent network from launching.
+#[derive(Debug, Clone, Error)]
+pub enum ConfigWarning {
+    #[error("node `{name}` is disabled")]
+    DisabledNode { name: String, span: Span },
+
+    #[error("`{flag}` is already set by liesas")]
+    ManagedFlag { flag: String, span: Span },
+
+    #[error("`{setting}` is not supported by {client}")]
+    UnsupportedSetting {
+        setting: NodeSetting,
+        client: ClientKind,
+        span: Span,
+    },
+
+    #[error("network has {total} validators, generating their keys may take long")]
+    ManyValidators {
+        total: u64,
+        threshold: u64,
+        shares: Vec<ValidatorShare>,
+    },
+}
+
+impl ConfigWarning {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::DisabledNode { span, .. } => span.clone(),
+            Self::ManagedFlag { span, .. } => span.clone(),
+            Self::UnsupportedSetting { span, .. } => span.clone(),
+            Self::ManyValidators { shares, .. } => shares
+                .first()
+                .map_or(0..0, |share| share.validator_count.span()),
+        }
+    }
+
+    pub fn report(&self, sources: &Sources) -> ariadne::Report<'_, (String, Span)> {
+        match self {
+            Self::DisabledNode { name, span } => {
+                ariadne::Report::build(ReportKind::Warning, sources.locate(span.clone()))
+                    .with_config(output::report_config())
+                    .with_message(format!("Node `{name}` is disabled"))
+                    .with_label(
+                        Label::new(sources.locate(span.clone()))
+                            .with_message("node is skipped, because count is zero"),
+                    )
+                    .finish()
+            }
+            Self::ManagedFlag { flag, span } => {
+                ariadne::Report::build(ReportKind::Warning, sources.locate(span.clone()))
+                    .with_config(output::report_config())
+                    .with_message(format!("`{flag}` conflicts with flag, set by liesas"))
+                    .with_label(
+                        Label::new(sources.locate(span.clone()))
+                            .with_message("client would receive this flag twice"),
+                    )
+                    .with_help("remove it from `extra_args`, and configure it on the node instead")
+                    .finish()
+            }
+            Self::UnsupportedSetting {
+                setting,
+                client,
+                span,
+            } => ariadne::Report::build(ReportKind::Warning, sources.locate(span.clone()))
+                .with_config(output::report_config())
+                .with_message(format!("`{setting}` is ignored by {client}"))
+                .with_label(
+                    Label::new(sources.locate(span.clone()))
+                        .with_message("liesas doesn't know how to pass this to the client"),
+                )
+                .with_help("pass client's own flag in `extra_args` instead")
+                .finish(),
+            Self::ManyValidators {
+                total,
+                threshold,
+                shares,
+            } => ariadne::Report::build(ReportKind::Warning, sources.locate(self.span()))
+                .with_config(output::report_config())
+                .with_message(format!("Network has {total} validators"))
+                .with_labels(shares.iter().flat_map(|share| share.labels(sources)))
+                .with_note(
+                    "a key is generated for every validator before launch, and each XMSS key \
+                     takes noticeable time, so this may take very long",
+                )
+                .with_help(format!(
+                    "this is above `validator_warning_threshold` of {threshold}, raise it if \
+                     this is intended"
+                ))
+                .finish(),
+        }
+    }
+}
+
+/// Everything, that is needed to launch a single node.
+#[derive(Debug, Clone)]
+pub struct NodePlan {
+    pub name: String,
+
+    /// Container to create, `None` for nodes running local binary.
+    pub container: Option<ContainerSpec>,
+
+    /// Local process to spawn, `None` for nodes running in container.
+    pub process: Option<ProcessSpec>,
+
+    /// Global indices of validators, handled by this node.
+    pub validators: Vec<usize>,
+}
+
+/// Everything, that is needed to launch the network, in launch order.
+#[derive(Debug)]
+pub struct NetworkPlan {
+    pub network: ResolvedNetworkConfig,
+    pub nodes: Vec<NodePlan>,
+}
+
+impl NetworkPlan {
+    /// Launch only nodes of `group`, see
+    /// [`ResolvedNetworkConfig::node_names`].
+    ///
+    /// Returns `false`, if no node belongs to `group`.
+    pub fn retain_group(&mut self, group: &str) -> bool {
+        let names = self.network.node_names(Some(group));
+        self.nodes.retain(|node| names.contains(&node.name));
+
+        !self.nodes.is_empty()
+    }
+}
+
+/// Find suspicious parts of the config.
+///
+/// This is cheap, and is meant to run before [`plan`], so that warnings are
+/// shown before keys are generated.
+pub fn check(config: &NetworkConfig) -> Vec<ConfigWarning> {
+    let mut warnings = Vec::new();
+
+    if config.allow_disabled {
+        for node in config.node.iter().filter(|node| *node.count.get_ref() == 0) {
+            let name = match &node.name {
+                Some(name) => name.get_ref().clone(),
+                None => node.client.get_ref().kind().to_string(),
+            };
+
+            warnings.push(ConfigWarning::DisabledNode {
+                name,
+                span: node.count.span(),
+            });
+        }
+    }
+
+    for node in &config.node {
+        let kind = node.client.get_ref().kind();
+        let client = kind.instantiate();
+
+        for (setting, value) in [
+            (NodeSetting::LogLevel, &node.log_level),
+            (NodeSetting::CheckpointSyncUrl, &node.checkpoint_sync_url),
+        ] {
+            // Value from `[defaults]` is shared by many nodes, but is
+            // reported once.
+            if let Some(value) = value
+                && !client.supported_settings().contains(&setting)
+                && !warnings.iter().any(|warning: &ConfigWarning| {
+                    matches!(warning, ConfigWarning::UnsupportedSetting { span, .. } if *span == value.span())
+                })
+            {
+                warnings.push(ConfigWarning::UnsupportedSetting {
+                    setting,
+                    client: kind.clone(),
+                    span: value.span(),
+                });
+            }
+        }
+
+        for arg in &node.extra_args {
+            let flag = arg.get_ref().split('=').next().unwrap_or_default();
+
+            if client.managed_flags().contains(&flag) {
+                warnings.push(ConfigWarning::ManagedFlag {
+                    flag: flag.to_owned(),
+                    span: arg.span(),
+                });
+            }
+        }
+    }
+
+    let (total, shares) = validator_shares(&config.node);
+    if total > config.validator_warning_threshold {
+        warnings.push(ConfigWarning::ManyValidators {
+            total,
+            threshold: config.validator_warning_threshold,
+            shares,
+        });
+    }
+
+    warnings
+}
+
+/// Resolve network and compute what should be launched, without touching
+/// docker.
+///
+/// With [`KeyStore::Memory`](crate::validator::KeyStore::Memory), nothing is
+/// written to the filesystem either, so the whole pipeline can be checked
+/// without side effects.
+pub fn plan(
+    config: NetworkConfig,
+    options: ResolveOptions,
+) -> Result<NetworkPlan, Vec<ConfigError>> {
+    let network = config.resolve(options)?;
+
+    let mut errors = Vec::new();
+    let nodes = network
+        .launch_order()
+        .iter()
+        .map(|name| {
+            let node = &network.nodes()[name];
+            let container = ContainerSpec::new(&network, name, node);
+
+            // Peers of every node are only known once the whole network is
+            // resolved, so config files are written here, rather than along
+            // with the keys.
+            if let Some(spec) = &container
+                && let Err((path, error)) = spec.write_config_file(name, network.key_store())
+            {
+                errors.push(ConfigError::WriteConfigFile {
+                    path,
+                    error,
+                    span: node.client_span.clone(),
+                });
+            }
+
+            NodePlan {
+                name: name.clone(),
+                container,
+                process: ProcessSpec::new(&network, name, node),
+                validators: node.validators.clone(),
+            }
+        })
+        .collect();
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(NetworkPlan { network, nodes })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, path::Path};
+
+    use super::*;
+    use crate::{container::GROUP_LABEL, validator::KeyStore};
+
+    #[test]
+    fn group_selects_only_its_nodes() {
+        let config: NetworkConfig = toml::from_str(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            name = "alice"
+            client = "ream"
+            group = "honest"
+
+            [[node]]
+            name = "mallory"
+            client = "ream"
+            group = "attackers"
+
+            [[node]]
+            name = "bob"
+            client = "ream"
+            group = "honest"
+            "#,
+        )
+        .unwrap();
+
+        let mut plan = plan(
+            config,
+            ResolveOptions::new(Path::new("network.toml"), KeyStore::Skip),
+        )
+        .unwrap();
+
+        assert!(plan.retain_group("honest"));
+        let names = plan.nodes.iter().map(|node| &node.name).collect::<Vec<_>>();
+        assert_eq!(names, ["alice", "bob"]);
+
+        for node in &plan.nodes {
+            let spec = node.container.as_ref().unwrap();
+            assert_eq!(spec.labels[GROUP_LABEL], "honest");
+        }
+
+        assert!(!plan.retain_group("observers"));
+    }
+
+    #[test]
+    fn representative_network_is_planned() {
+        let config: NetworkConfig = toml::from_str(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            name = "boot"
+            client = "ream"
+            validator_count = 2
+
+            [[node]]
+            name = "replica"
+            client = "ream"
+            count = 2
+            extra_args = ["--http-port=1"]
+            "#,
+        )
+        .unwrap();
+
+        let warnings = check(&config);
+        assert!(matches!(
+            &warnings[..],
+            [ConfigWarning::ManagedFlag { flag, .. }] if flag == "--http-port"
+        ));
+
+        let plan = plan(
+            config,
+            ResolveOptions::new(Path::new("network.toml"), KeyStore::Skip),
+        )
+        .unwrap();
+
+        let names = plan.nodes.iter().map(|node| &node.name).collect::<Vec<_>>();
+        assert_eq!(names, ["boot", "replica_0", "replica_1"]);
+
+        let validators = plan
+            .nodes
+            .iter()
+            .map(|node| node.validators.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(validators, [vec![0, 1], vec![2], vec![3]]);
+
+        let mut host_ports = Vec::new();
+        for node in &plan.nodes {
+            assert!(node.process.is_none());
+
+            let spec = node.container.as_ref().unwrap();
+            assert_eq!(spec.name, format!("devnet-{}", node.name));
+            assert!(spec.image.contains("ream"));
+            host_ports.extend(spec.ports.iter().map(|port| port.host_port));
+        }
+
+        let unique = host_ports.iter().collect::<HashSet<_>>();
+        assert_eq!(unique.len(), host_ports.len(), "host ports must not clash");
+    }
+
+    #[test]
+    fn user_peer_flag_conflicts_with_managed_one() {
+        let source = r#"
+            name = "devnet"
+
+            [[node]]
+            client = "ream"
+            extra_args = ["--bootnodes=/ip4/10.0.0.1/udp/9000/quic-v1", "--foo"]
+            "#;
+        let config: NetworkConfig = toml::from_str(source).unwrap();
+
+        let warnings = check(&config);
+        let [warning @ ConfigWarning::ManagedFlag { flag, .. }] = &warnings[..] else {
+            panic!("expected single managed flag warning, got {warnings:?}");
+        };
+
+        assert_eq!(flag, "--bootnodes");
+        assert_eq!(
+            &source[warning.span()],
+            "\"--bootnodes=/ip4/10.0.0.1/udp/9000/quic-v1\""
+        );
+    }
+
+    #[test]
+    fn config_file_is_written_next_to_keys() {
+        let dir = std::env::temp_dir().join(format!("liesas-plan-{}", std::process::id()));
+        let config: NetworkConfig = toml::from_str(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            name = "boot"
+            client = "ream"
+            bootnode = true
+            num_active_epochs = 4
+
+            [[node]]
+            name = "peer"
+            client = "ream"
+            num_active_epochs = 4
+            "#,
+        )
+        .unwrap();
+
+        let plan = plan(
+            config,
+            ResolveOptions::new(&dir.join("network.toml"), KeyStore::Disk(dir.clone())),
+        )
+        .unwrap();
+
+        let spec = plan.nodes[1].container.as_ref().unwrap();
+        assert_eq!(
+            spec.args[spec.args.len() - 2..],
+            ["--config", "/liesas/keys/ream.yaml"]
+        );
+
+        let contents = std::fs::read_to_string(dir.join("peer/ream.yaml")).unwrap();
+        assert!(contents.contains("socket-port: 9000"), "{contents}");
+        assert!(
+            contents.contains("/dns4/devnet-boot/udp/9000/quic-v1"),
+            "{contents}"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn settings_dropped_by_client_are_warned_about() {
+        let source = r#"
+            name = "devnet"
+
+            [defaults]
+            log_level = "debug"
+
+            [[node]]
+            client = "zeam"
+            count = 2
+            checkpoint_sync_url = "http://checkpoint:5052"
+
+            [[node]]
+            client = "ream"
+            checkpoint_sync_url = "http://checkpoint:5052"
+            "#;
+        let mut config: NetworkConfig = toml::from_str(source).unwrap();
+        config.apply_defaults();
+
+        let warnings = check(&config)
+            .into_iter()
+            .map(|warning| match warning {
+                ConfigWarning::UnsupportedSetting { setting, span, .. } => (setting, &source[span]),
+                warning => panic!("unexpected warning: {warning:?}"),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            warnings,
+            [
+                (NodeSetting::LogLevel, "\"debug\""),
+                (NodeSetting::CheckpointSyncUrl, "\"http://checkpoint:5052\""),
+            ]
+        );
+    }
+}
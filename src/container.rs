@@ -0,0 +1,634 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write as _,
+    io,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use bollard::{
+    models::{
+        ContainerCreateBody, HostConfig, PortBinding, ResourcesUlimits, RestartPolicyNameEnum,
+    },
+    query_parameters::{CreateContainerOptions, CreateContainerOptionsBuilder},
+};
+
+use strum::{Display, EnumString, VariantNames};
+use tracing::debug;
+
+use crate::{
+    client::{ConfigFile, NodeContext},
+    config::{ResolvedNetworkConfig, ResolvedNodeConfig, Ulimit},
+    validator::{JWT_FILE, KeyStore},
+};
+
+/// Set to `true` on every container, created by liesas, to find them
+/// without knowing the config.
+pub const MANAGED_LABEL: &str = "liesas.managed";
+pub const NETWORK_LABEL: &str = "liesas.network";
+pub const NODE_LABEL: &str = "liesas.node";
+pub const CLIENT_LABEL: &str = "liesas.client";
+pub const VALIDATORS_LABEL: &str = "liesas.validators";
+pub const GROUP_LABEL: &str = "liesas.group";
+
+/// Host name, on which containers reach local processes of the network.
+pub const HOST_GATEWAY: &str = "host.docker.internal";
+
+/// Directory inside the container, where liesas mounts keys, genesis and
+/// rendered node config.
+pub const CONFIG_DIR: &str = "/liesas";
+
+/// Directory inside the container, where validator keys of the node are
+/// mounted.
+pub const KEYS_DIR: &str = "/liesas/keys";
+
+/// Path inside the container, where network genesis is mounted.
+pub const GENESIS_PATH: &str = "/liesas/genesis.json";
+
+/// Container port, published on the host.
+#[derive(Debug, Clone)]
+pub struct PublishedPort {
+    pub host_ip: IpAddr,
+    pub host_port: u16,
+    pub container_port: u16,
+    pub protocol: Protocol,
+}
+
+/// Transport protocol of published port.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum Protocol {
+    Tcp,
+
+    /// Used by peer-to-peer port, as lean clients talk QUIC.
+    Udp,
+}
+
+impl PublishedPort {
+    /// Container port with protocol, e.g. `9000/udp`, as docker keys port
+    /// bindings.
+    pub fn container_key(&self) -> String {
+        format!("{}/{}", self.container_port, self.protocol)
+    }
+
+    /// Port in `docker run --publish` form,
+    /// `<host ip>:<host port>:<port>/<protocol>`.
+    pub fn to_publish(&self) -> String {
+        let host_ip = match self.host_ip {
+            IpAddr::V4(ip) => ip.to_string(),
+            IpAddr::V6(ip) => format!("[{ip}]"),
+        };
+
+        format!("{host_ip}:{}:{}", self.host_port, self.container_key())
+    }
+}
+
+/// When docker restarts container after it exits, same as `--restart` of
+/// `docker run`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Display, EnumString, VariantNames)]
+#[strum(serialize_all = "kebab-case")]
+pub enum RestartPolicy {
+    #[default]
+    No,
+    OnFailure,
+    UnlessStopped,
+    Always,
+}
+
+impl RestartPolicy {
+    fn to_docker(self) -> bollard::models::RestartPolicy {
+        let name = match self {
+            Self::No => RestartPolicyNameEnum::NO,
+            Self::OnFailure => RestartPolicyNameEnum::ON_FAILURE,
+            Self::UnlessStopped => RestartPolicyNameEnum::UNLESS_STOPPED,
+            Self::Always => RestartPolicyNameEnum::ALWAYS,
+        };
+
+        bollard::models::RestartPolicy {
+            name: Some(name),
+            maximum_retry_count: None,
+        }
+    }
+}
+
+/// Host path, mounted into the container.
+#[derive(Debug, Clone)]
+pub struct Volume {
+    pub source: PathBuf,
+    pub target: String,
+    pub read_only: bool,
+}
+
+/// Everything, that is needed to create node container.
+///
+/// Both docker API request and printed `docker run` command are built from
+/// this, so they can't drift apart.
+#[derive(Debug, Clone)]
+pub struct ContainerSpec {
+    pub name: String,
+    pub image: String,
+    pub labels: BTreeMap<String, String>,
+    pub network: Option<String>,
+    pub ports: Vec<PublishedPort>,
+    pub volumes: Vec<Volume>,
+    pub env: BTreeMap<String, String>,
+    pub working_dir: Option<PathBuf>,
+    pub ulimits: BTreeMap<String, Ulimit>,
+    pub sysctls: BTreeMap<String, String>,
+
+    /// Extra `/etc/hosts` entries, as `<host>:<address>`.
+    pub extra_hosts: Vec<String>,
+
+    /// Number of cpu cores, container may use.
+    pub cpus: Option<f64>,
+
+    /// Memory limit, in bytes.
+    pub memory: Option<u64>,
+
+    /// Grace period between stop signal and kill, used by every `docker stop`
+    /// of the container.
+    pub stop_timeout: Duration,
+
+    pub restart_policy: RestartPolicy,
+
+    pub args: Vec<String>,
+
+    /// Config file, which client reads instead of flags. Written into the
+    /// key directory of the node, which is mounted at [`KEYS_DIR`].
+    pub config_file: Option<ConfigFile>,
+}
+
+impl ContainerSpec {
+    /// Spec of the container, running node `name`.
+    ///
+    /// Returns `None` if node has no image to run.
+    pub fn new(
+        network: &ResolvedNetworkConfig,
+        name: &str,
+        node: &ResolvedNodeConfig,
+    ) -> Option<Self> {
+        let image = node.client.image()?.to_owned();
+
+        let mut labels = BTreeMap::new();
+        labels.insert(MANAGED_LABEL.to_owned(), "true".to_owned());
+        labels.insert(NETWORK_LABEL.to_owned(), network.name().to_owned());
+        labels.insert(NODE_LABEL.to_owned(), name.to_owned());
+        labels.insert(CLIENT_LABEL.to_owned(), node.client.kind().to_string());
+        labels.insert(
+            VALIDATORS_LABEL.to_owned(),
+            node.validators.len().to_string(),
+        );
+        if let Some(group) = &node.group {
+            labels.insert(GROUP_LABEL.to_owned(), group.clone());
+        }
+
+        let volumes = network
+            .key_store()
+            .node_dir(name)
+            .map(|source| Volume {
+                source,
+                target: KEYS_DIR.to_owned(),
+                read_only: true,
+            })
+            .into_iter()
+            .collect::<Vec<_>>();
+        let keys_dir = (!volumes.is_empty()).then_some(KEYS_DIR);
+        let jwt_path = keys_dir
+            .filter(|_| node.jwt.is_some())
+            .map(|dir| format!("{dir}/{JWT_FILE}"));
+
+        let client = node.client.kind().instantiate();
+
+        // Client listens on its default ports inside the container, and only
+        // host side of the mapping is configured.
+        let container_ports = client.default_ports();
+        let ports = [
+            (node.ports.p2p, container_ports.p2p, Protocol::Udp),
+            (node.ports.rpc, container_ports.rpc, Protocol::Tcp),
+            (node.ports.metrics, container_ports.metrics, Protocol::Tcp),
+        ]
+        .into_iter()
+        .map(|(host_port, container_port, protocol)| PublishedPort {
+            host_ip: node.bind_address,
+            host_port,
+            container_port,
+            protocol,
+        })
+        .collect();
+
+        let peers = network.peers(name);
+        debug!(
+            "{name} connects to {} peer(s): {}",
+            peers.len(),
+            peers.join(", ")
+        );
+        let context = NodeContext {
+            name,
+            data_dir: None,
+            keys_dir,
+            ports: container_ports,
+            peers: &peers,
+            checkpoint_sync_url: node.checkpoint_sync_url.as_deref(),
+            log_level: node.log_level,
+            jwt_path: jwt_path.as_deref(),
+        };
+
+        // Config file lives next to the keys, so it can only be used when
+        // keys are mounted.
+        let config_file = keys_dir.and_then(|_| client.config_file(&context));
+
+        let mut args = client.timing_args(&network.timing());
+        match (&config_file, keys_dir) {
+            (Some(file), Some(dir)) => {
+                args.extend(["--config".to_owned(), format!("{dir}/{}", file.file_name)]);
+            }
+            _ => args.extend(client.build_args(&context)),
+        }
+        args.extend(node.extra_args.iter().cloned());
+
+        Some(Self {
+            name: network.container_name(name),
+            image,
+            labels,
+            network: Some(network.docker_network()),
+            ports,
+            volumes: volumes.into_iter().chain(node.volumes.clone()).collect(),
+            env: node.env.clone(),
+            working_dir: node.working_dir.clone(),
+            ulimits: node.ulimits.clone(),
+            sysctls: node.sysctls.clone(),
+            // Docker resolves `host-gateway` to the address of the host.
+            extra_hosts: network
+                .has_processes()
+                .then(|| format!("{HOST_GATEWAY}:host-gateway"))
+                .into_iter()
+                .collect(),
+            cpus: node.cpus,
+            memory: node.memory,
+            stop_timeout: node.stop_timeout,
+            restart_policy: node.restart_policy,
+            args,
+            config_file,
+        })
+    }
+
+    /// Write config file of the node into its key directory, if client is
+    /// configured by file.
+    ///
+    /// On failure, returns path which couldn't be written.
+    pub fn write_config_file(
+        &self,
+        node: &str,
+        key_store: &KeyStore,
+    ) -> Result<(), (PathBuf, io::Error)> {
+        match &self.config_file {
+            Some(file) => key_store.store_config_file(node, file),
+            None => Ok(()),
+        }
+    }
+
+    /// Mount genesis file at `path` into the container, at [`GENESIS_PATH`].
+    pub fn mount_genesis(&mut self, path: &Path) {
+        self.volumes.push(Volume {
+            source: path.to_owned(),
+            target: GENESIS_PATH.to_owned(),
+            read_only: true,
+        });
+    }
+
+    /// Options and body of docker `create_container` request.
+    pub fn create_request(&self) -> (CreateContainerOptions, ContainerCreateBody) {
+        let options = CreateContainerOptionsBuilder::default()
+            .name(&self.name)
+            .build();
+
+        let mut port_bindings = HashMap::<String, Option<Vec<PortBinding>>>::new();
+        for port in &self.ports {
+            port_bindings
+                .entry(port.container_key())
+                .or_default()
+                .get_or_insert_default()
+                .push(PortBinding {
+                    host_ip: Some(port.host_ip.to_string()),
+                    host_port: Some(port.host_port.to_string()),
+                });
+        }
+
+        let host_config = HostConfig {
+            port_bindings: Some(port_bindings),
+            binds: Some(self.volumes.iter().map(Volume::to_bind).collect()),
+            ulimits: Some(
+                self.ulimits
+                    .iter()
+                    .map(|(name, limit)| ResourcesUlimits {
+                        name: Some(name.clone()),
+                        soft: Some(limit.soft()),
+                        hard: Some(limit.hard()),
+                    })
+                    .collect(),
+            ),
+            sysctls: Some(self.sysctls.clone().into_iter().collect()),
+            extra_hosts: Some(self.extra_hosts.clone()),
+            nano_cpus: self.cpus.map(|cpus| (cpus * 1e9) as i64),
+            memory: self.memory.map(|bytes| bytes as i64),
+            network_mode: self.network.clone(),
+            restart_policy: Some(self.restart_policy.to_docker()),
+            ..Default::default()
+        };
+
+        let body = ContainerCreateBody {
+            image: Some(self.image.clone()),
+            cmd: Some(self.args.clone()),
+            env: Some(self.env.iter().map(|(k, v)| format!("{k}={v}")).collect()),
+            labels: Some(self.labels.clone().into_iter().collect()),
+            exposed_ports: Some(
+                self.ports
+                    .iter()
+                    .map(PublishedPort::container_key)
+                    .collect(),
+            ),
+            working_dir: self
+                .working_dir
+                .as_ref()
+                .map(|dir| dir.display().to_string()),
+            host_config: Some(host_config),
+            stop_timeout: Some(self.stop_timeout.as_secs() as i64),
+            ..Default::default()
+        };
+
+        (options, body)
+    }
+
+    /// Equivalent `docker run` command line, with arguments quoted for POSIX
+    /// shell.
+    pub fn to_docker_run(&self) -> String {
+        let mut command = String::from("docker run --detach");
+
+        let mut flag = |name: &str, value: &str| {
+            write!(command, " {name} {}", shell_quote(value)).unwrap();
+        };
+
+        flag("--name", &self.name);
+        if let Some(network) = &self.network {
+            flag("--network", network);
+        }
+        for (key, value) in &self.labels {
+            flag("--label", &format!("{key}={value}"));
+        }
+        for port in &self.ports {
+            flag("--publish", &port.to_publish());
+        }
+        for volume in &self.volumes {
+            flag("--volume", &volume.to_bind());
+        }
+        for (key, value) in &self.env {
+            flag("--env", &format!("{key}={value}"));
+        }
+        if let Some(dir) = &self.working_dir {
+            flag("--workdir", &dir.display().to_string());
+        }
+        for (name, limit) in &self.ulimits {
+            flag(
+                "--ulimit",
+                &format!("{name}={}:{}", limit.soft(), limit.hard()),
+            );
+        }
+        for (key, value) in &self.sysctls {
+            flag("--sysctl", &format!("{key}={value}"));
+        }
+        for host in &self.extra_hosts {
+            flag("--add-host", host);
+        }
+        if let Some(cpus) = self.cpus {
+            flag("--cpus", &cpus.to_string());
+        }
+        if let Some(memory) = self.memory {
+            flag("--memory", &memory.to_string());
+        }
+        flag("--stop-timeout", &self.stop_timeout.as_secs().to_string());
+        if self.restart_policy != RestartPolicy::No {
+            flag("--restart", &self.restart_policy.to_string());
+        }
+
+        for arg in std::iter::once(&self.image).chain(&self.args) {
+            write!(command, " {}", shell_quote(arg)).unwrap();
+        }
+
+        command
+    }
+}
+
+impl Volume {
+    pub fn to_bind(&self) -> String {
+        let mut bind = format!("{}:{}", self.source.display(), self.target);
+        if self.read_only {
+            bind.push_str(":ro");
+        }
+
+        bind
+    }
+}
+
+/// Quote `value` for POSIX shell, leaving it as is when it contains no
+/// special characters.
+pub fn shell_quote(value: &str) -> String {
+    let is_plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c));
+
+    if is_plain {
+        value.to_owned()
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::resolve;
+
+    /// Spec of container, running `node` of network from TOML `source`.
+    fn spec(source: &str, node: &str) -> ContainerSpec {
+        let network = resolve(source).unwrap();
+        ContainerSpec::new(&network, node, &network.nodes()[node]).unwrap()
+    }
+
+    #[test]
+    fn working_dir_is_set() {
+        let spec = spec(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            client = "ream"
+            working_dir = "/data"
+            "#,
+            "ream",
+        );
+
+        let (_, body) = spec.create_request();
+        assert_eq!(body.working_dir.as_deref(), Some("/data"));
+        assert!(spec.to_docker_run().contains(" --workdir /data "));
+    }
+
+    #[test]
+    fn nofile_ulimit_reaches_host_config() {
+        let spec = spec(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            client = "ream"
+            ulimits = { nofile = 65536 }
+            sysctls = { "net.core.somaxconn" = "1024" }
+            "#,
+            "ream",
+        );
+
+        let (_, body) = spec.create_request();
+        let host_config = body.host_config.unwrap();
+        let ulimits = host_config.ulimits.unwrap();
+        assert_eq!(ulimits.len(), 1);
+        assert_eq!(ulimits[0].name.as_deref(), Some("nofile"));
+        assert_eq!(
+            (ulimits[0].soft, ulimits[0].hard),
+            (Some(65536), Some(65536))
+        );
+        assert_eq!(host_config.sysctls.unwrap()["net.core.somaxconn"], "1024");
+        assert!(
+            spec.to_docker_run()
+                .contains(" --ulimit nofile=65536:65536 ")
+        );
+    }
+
+    #[test]
+    fn docker_run_includes_image_and_args() {
+        let spec = spec(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            client = { kind = "ream", image = "ghcr.io/reamlabs/ream:dev" }
+            extra_args = ["--verbose"]
+            "#,
+            "ream",
+        );
+
+        let command = spec.to_docker_run();
+        assert!(command.starts_with("docker run "));
+        assert!(command.contains(" --name devnet-ream "));
+        assert!(command.ends_with(&format!(
+            " ghcr.io/reamlabs/ream:dev {}",
+            spec.args.join(" ")
+        )));
+        assert!(command.ends_with(" --verbose"));
+    }
+
+    #[test]
+    fn stop_timeout_is_grace_period_of_container() {
+        let source = r#"
+            name = "devnet"
+            stop_timeout = "20s"
+
+            [[node]]
+            name = "alpha"
+            client = "ream"
+            stop_timeout = "1m"
+
+            [[node]]
+            name = "beta"
+            client = "ream"
+            "#;
+
+        let alpha = spec(source, "alpha");
+        let (_, body) = alpha.create_request();
+        assert_eq!(body.stop_timeout, Some(60));
+        assert!(alpha.to_docker_run().contains("--stop-timeout 60"));
+
+        let (_, body) = spec(source, "beta").create_request();
+        assert_eq!(body.stop_timeout, Some(20));
+    }
+
+    #[test]
+    fn p2p_port_is_published_as_udp() {
+        let spec = spec(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            client = "ream"
+            "#,
+            "ream",
+        );
+
+        let (_, body) = spec.create_request();
+        let bindings = body.host_config.unwrap().port_bindings.unwrap();
+        assert!(bindings.contains_key("9000/udp"));
+        assert!(bindings.contains_key("5052/tcp"));
+        assert!(!bindings.contains_key("9000/tcp"));
+
+        let exposed = body.exposed_ports.unwrap();
+        assert!(exposed.contains(&"9000/udp".to_owned()));
+
+        let command = spec.to_docker_run();
+        assert!(command.contains(":9000:9000/udp"), "{command}");
+        assert!(command.contains(":5052:5052/tcp"), "{command}");
+    }
+
+    #[test]
+    fn container_is_labeled_with_node_metadata() {
+        let spec = spec(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            name = "alpha"
+            client = "ream"
+            validator_count = 3
+            "#,
+            "alpha",
+        );
+
+        let (_, body) = spec.create_request();
+        assert_eq!(
+            body.labels.unwrap(),
+            HashMap::from([
+                (MANAGED_LABEL.to_owned(), "true".to_owned()),
+                (NETWORK_LABEL.to_owned(), "devnet".to_owned()),
+                (NODE_LABEL.to_owned(), "alpha".to_owned()),
+                (CLIENT_LABEL.to_owned(), "ream".to_owned()),
+                (VALIDATORS_LABEL.to_owned(), "3".to_owned()),
+            ])
+        );
+    }
+
+    #[test]
+    fn containers_reach_processes_through_host_gateway() {
+        let source = r#"
+            name = "devnet"
+
+            [[node]]
+            name = "alpha"
+            client = { kind = "ream", bin = "/bin/sh" }
+
+            [[node]]
+            name = "beta"
+            client = "ream"
+            "#;
+
+        let spec = spec(source, "beta");
+        let (_, body) = spec.create_request();
+        assert_eq!(
+            body.host_config.unwrap().extra_hosts.unwrap(),
+            ["host.docker.internal:host-gateway"]
+        );
+        let command = spec.to_docker_run();
+        assert!(
+            command.contains("--add-host host.docker.internal:host-gateway"),
+            "{command}"
+        );
+    }
+}
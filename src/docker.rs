@@ -0,0 +1,710 @@
+use std::{collections::HashMap, env, fmt, str::FromStr, time::Duration};
+
+use bollard::{
+    ClientVersion, Docker,
+    errors::Error as BollardError,
+    models::{CreateImageInfo, NetworkCreateRequest},
+    query_parameters::{CreateImageOptionsBuilder, RemoveContainerOptionsBuilder},
+};
+use clap::{Args, ValueEnum};
+use futures_util::TryStreamExt;
+use thiserror::Error;
+use tracing::debug;
+
+use crate::{auth::Registries, exit::Failure};
+
+/// Request timeout, same as bollard's default.
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Delay before the first retry. Doubled after each failed attempt.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+#[cfg(unix)]
+const DEFAULT_DOCKER_HOST: &str = "unix:///var/run/docker.sock";
+
+#[cfg(windows)]
+const DEFAULT_DOCKER_HOST: &str = "npipe:////./pipe/docker_engine";
+
+/// Docker API version, in `<major>.<minor>` format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApiVersion {
+    major: usize,
+    minor: usize,
+}
+
+impl FromStr for ApiVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (major, minor) = s
+            .split_once('.')
+            .ok_or_else(|| format!("expected `<major>.<minor>`, got `{s}`"))?;
+
+        Ok(Self {
+            major: major
+                .parse()
+                .map_err(|_| format!("invalid major version `{major}`"))?,
+            minor: minor
+                .parse()
+                .map_err(|_| format!("invalid minor version `{minor}`"))?,
+        })
+    }
+}
+
+impl fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl From<ApiVersion> for ClientVersion {
+    fn from(value: ApiVersion) -> Self {
+        Self {
+            major_version: value.major,
+            minor_version: value.minor,
+        }
+    }
+}
+
+/// When to pull images of nodes before launching them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PullPolicy {
+    /// Pull only images, which are not present locally.
+    Missing,
+
+    /// Always pull, to pick up updates of mutable tags, like `latest`.
+    Always,
+
+    /// Never pull, failing if image is not present locally.
+    Never,
+}
+
+/// Flags, controlling connection to docker daemon.
+#[derive(Debug, Clone, Args)]
+pub struct DockerArgs {
+    /// Pin Docker API version (e.g. `1.41`), instead of negotiating it with
+    /// the daemon.
+    #[arg(long, global = true, env = "LIESAS_DOCKER_API_VERSION")]
+    docker_api_version: Option<ApiVersion>,
+
+    /// How many times to retry docker operations, which failed transiently.
+    #[arg(long, global = true, default_value_t = 3)]
+    retries: u32,
+}
+
+/// Docker failure, classified so that commands can decide whether to retry or
+/// roll back, and which exit code to use.
+#[derive(Debug, Error)]
+pub enum DockerError {
+    #[error("unsupported docker host `{0}`")]
+    UnsupportedHost(String),
+
+    #[error("docker daemon not reachable at {host}; is Docker running?")]
+    NotReachable {
+        host: String,
+        #[source]
+        source: BollardError,
+    },
+
+    #[error("docker daemon rejected pinned api version {version}: {message}")]
+    ApiVersionRejected {
+        version: ApiVersion,
+        message: String,
+    },
+
+    #[error("image {image} not found")]
+    ImageNotFound { image: String },
+
+    #[error("container {name} already exists")]
+    NameConflict { name: String },
+
+    #[error("port of {node} is already in use: {message}")]
+    PortInUse { node: String, message: String },
+
+    #[error("failed to pull image {image}")]
+    PullFailed {
+        image: String,
+        #[source]
+        source: BollardError,
+    },
+
+    #[error("failed to create container {node}")]
+    CreateFailed {
+        node: String,
+        #[source]
+        source: BollardError,
+    },
+
+    #[error("failed to start container {node}")]
+    StartFailed {
+        node: String,
+        #[source]
+        source: BollardError,
+    },
+
+    #[error("docker request failed while {operation}")]
+    Request {
+        operation: String,
+        #[source]
+        source: BollardError,
+    },
+}
+
+/// Whether `err` means daemon couldn't be reached at all.
+fn is_unreachable(err: &BollardError) -> bool {
+    matches!(
+        err,
+        BollardError::SocketNotFoundError(_)
+            | BollardError::IOError { .. }
+            | BollardError::HyperLegacyError { .. }
+            | BollardError::RequestTimeoutError
+    )
+}
+
+impl DockerError {
+    /// Failure class, determining exit code.
+    pub fn failure(&self) -> Failure {
+        match self {
+            Self::UnsupportedHost(_)
+            | Self::NotReachable { .. }
+            | Self::ApiVersionRejected { .. } => Failure::DockerUnavailable,
+            Self::ImageNotFound { .. }
+            | Self::NameConflict { .. }
+            | Self::PortInUse { .. }
+            | Self::PullFailed { .. }
+            | Self::CreateFailed { .. }
+            | Self::StartFailed { .. }
+            | Self::Request { .. } => Failure::Launch,
+        }
+    }
+
+    /// What user can do about the failure, if it is caused by environment
+    /// rather than by the network.
+    pub fn help(&self) -> Option<&'static str> {
+        match self {
+            Self::UnsupportedHost(_) => {
+                Some("`DOCKER_HOST` must start with `unix://`, `tcp://` or `http://`")
+            }
+            Self::NotReachable { .. } => {
+                Some("start Docker, or point `DOCKER_HOST` at a running daemon")
+            }
+            Self::ApiVersionRejected { .. } => {
+                Some("remove `--docker-api-version` to negotiate version with the daemon")
+            }
+            _ => None,
+        }
+    }
+
+    /// Classify failure of arbitrary request, made while `operation`.
+    pub fn request(operation: impl Into<String>, source: BollardError) -> Self {
+        if is_unreachable(&source) {
+            return Self::NotReachable {
+                host: docker_host(),
+                source,
+            };
+        }
+
+        Self::Request {
+            operation: operation.into(),
+            source,
+        }
+    }
+
+    /// Classify failure of pulling `image`.
+    pub fn pull(image: &str, source: BollardError) -> Self {
+        match &source {
+            BollardError::DockerResponseServerError {
+                status_code: 404, ..
+            } => Self::ImageNotFound {
+                image: image.to_owned(),
+            },
+            BollardError::DockerStreamError { error }
+                if error.contains("manifest unknown") || error.contains("not found") =>
+            {
+                Self::ImageNotFound {
+                    image: image.to_owned(),
+                }
+            }
+            err if is_unreachable(err) => Self::request("pulling image", source),
+            _ => Self::PullFailed {
+                image: image.to_owned(),
+                source,
+            },
+        }
+    }
+
+    /// Classify failure of creating container for `node` from `image`.
+    pub fn create(node: &str, image: &str, source: BollardError) -> Self {
+        match &source {
+            BollardError::DockerResponseServerError {
+                status_code: 404, ..
+            } => Self::ImageNotFound {
+                image: image.to_owned(),
+            },
+            BollardError::DockerResponseServerError {
+                status_code: 409, ..
+            } => Self::NameConflict {
+                name: node.to_owned(),
+            },
+            err if is_unreachable(err) => Self::request("creating container", source),
+            _ => Self::CreateFailed {
+                node: node.to_owned(),
+                source,
+            },
+        }
+    }
+
+    /// Classify failure of starting container of `node`.
+    pub fn start(node: &str, source: BollardError) -> Self {
+        match &source {
+            BollardError::DockerResponseServerError { message, .. }
+                if message.contains("port is already allocated")
+                    || message.contains("address already in use") =>
+            {
+                Self::PortInUse {
+                    node: node.to_owned(),
+                    message: message.clone(),
+                }
+            }
+            err if is_unreachable(err) => Self::request("starting container", source),
+            _ => Self::StartFailed {
+                node: node.to_owned(),
+                source,
+            },
+        }
+    }
+}
+
+fn docker_host() -> String {
+    env::var("DOCKER_HOST").unwrap_or_else(|_| DEFAULT_DOCKER_HOST.to_owned())
+}
+
+/// Whether operation, which failed with `err`, may succeed when retried.
+///
+/// Deterministic failures, like missing image or name conflict, are never
+/// retried.
+fn is_transient(err: &BollardError) -> bool {
+    const TRANSIENT_MESSAGES: [&str; 5] = [
+        "timeout",
+        "connection reset",
+        "connection refused",
+        "toomanyrequests",
+        "temporarily unavailable",
+    ];
+
+    let transient_message = |message: &str| {
+        let message = message.to_lowercase();
+        TRANSIENT_MESSAGES.iter().any(|v| message.contains(v))
+    };
+
+    match err {
+        BollardError::RequestTimeoutError
+        | BollardError::HyperResponseError { .. }
+        | BollardError::HyperLegacyError { .. }
+        | BollardError::IOError { .. } => true,
+        BollardError::DockerResponseServerError {
+            status_code: 429 | 502 | 503 | 504,
+            ..
+        } => true,
+        BollardError::DockerResponseServerError {
+            status_code: 500,
+            message,
+        } => transient_message(message),
+        BollardError::DockerStreamError { error } => transient_message(error),
+        _ => false,
+    }
+}
+
+/// Retry policy for docker operations, with exponential backoff.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    retries: u32,
+    initial_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Run `f`, retrying it while it fails with transient errors.
+    pub async fn run<T, F, Fut>(&self, operation: &str, mut f: F) -> Result<T, BollardError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, BollardError>>,
+    {
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 0;
+
+        loop {
+            match f().await {
+                Err(err) if attempt < self.retries && is_transient(&err) => {
+                    attempt += 1;
+                    debug!(
+                        "{operation} failed ({err}), retrying in {backoff:?} (attempt {attempt}/{})",
+                        self.retries
+                    );
+
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+impl RetryPolicy {
+    /// Policy, which fails on the first error.
+    pub fn no_retries() -> Self {
+        Self {
+            retries: 0,
+            initial_backoff: Duration::ZERO,
+        }
+    }
+}
+
+fn connect_with_version(host: &str, version: &ClientVersion) -> Result<Docker, DockerError> {
+    let docker = match host {
+        #[cfg(unix)]
+        h if h.starts_with("unix://") => {
+            Docker::connect_with_unix(h, REQUEST_TIMEOUT.as_secs(), version)
+        }
+        #[cfg(windows)]
+        h if h.starts_with("npipe://") => {
+            Docker::connect_with_named_pipe(h, REQUEST_TIMEOUT.as_secs(), version)
+        }
+        h if h.starts_with("tcp://") || h.starts_with("http://") => {
+            Docker::connect_with_http(h, REQUEST_TIMEOUT.as_secs(), version)
+        }
+        _ => return Err(DockerError::UnsupportedHost(host.to_owned())),
+    };
+
+    docker.map_err(|source| DockerError::NotReachable {
+        host: host.to_owned(),
+        source,
+    })
+}
+
+impl DockerArgs {
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            retries: self.retries,
+            initial_backoff: INITIAL_BACKOFF,
+        }
+    }
+
+    /// Connect to docker daemon at `DOCKER_HOST`, or platform default socket.
+    ///
+    /// Unless version is pinned, it is negotiated with the daemon.
+    pub async fn connect(&self) -> Result<Docker, DockerError> {
+        let host = docker_host();
+
+        let Some(version) = self.docker_api_version else {
+            return connect_with_version(&host, bollard::API_DEFAULT_VERSION)?
+                .negotiate_version()
+                .await
+                .map_err(|source| DockerError::NotReachable { host, source });
+        };
+
+        let docker = connect_with_version(&host, &version.into())?;
+
+        // Daemon only checks API version once request is made, so check it
+        // early to produce clear error.
+        match docker.version().await {
+            Ok(_) => Ok(docker),
+            Err(BollardError::DockerResponseServerError {
+                status_code: 400,
+                message,
+            }) => Err(DockerError::ApiVersionRejected { version, message }),
+            Err(source) => Err(DockerError::NotReachable { host, source }),
+        }
+    }
+}
+
+/// Whether `image` is present locally.
+pub async fn image_exists(docker: &Docker, image: &str) -> Result<bool, DockerError> {
+    match docker.inspect_image(image).await {
+        Ok(_) => Ok(true),
+        Err(BollardError::DockerResponseServerError {
+            status_code: 404, ..
+        }) => Ok(false),
+        Err(err) => Err(DockerError::request(format!("inspecting {image}"), err)),
+    }
+}
+
+/// Pull `image`, authenticating if it comes from a private registry.
+///
+/// `on_progress` is called with every progress update of the pull, e.g. of
+/// downloading a single layer.
+pub async fn pull_image(
+    docker: &Docker,
+    retry: RetryPolicy,
+    image: &str,
+    registries: &Registries,
+    on_progress: &(dyn Fn(&CreateImageInfo) + Sync),
+) -> Result<(), DockerError> {
+    retry
+        .run(&format!("pulling {image}"), || {
+            let options = CreateImageOptionsBuilder::default()
+                .from_image(image)
+                .build();
+
+            docker
+                .create_image(Some(options), None, registries.for_image(image))
+                .try_for_each(|info| {
+                    on_progress(&info);
+                    async { Ok(()) }
+                })
+        })
+        .await
+        .map_err(|source| DockerError::pull(image, source))
+}
+
+/// Create docker network `name` with `driver`, unless it already exists.
+///
+/// Returns `false` if existing network is reused, whatever its driver is.
+pub async fn ensure_network(
+    docker: &Docker,
+    retry: RetryPolicy,
+    name: &str,
+    driver: &str,
+    labels: HashMap<String, String>,
+) -> Result<bool, DockerError> {
+    match docker.inspect_network(name, None).await {
+        Ok(_) => return Ok(false),
+        Err(BollardError::DockerResponseServerError {
+            status_code: 404, ..
+        }) => {}
+        Err(err) => {
+            return Err(DockerError::request(
+                format!("inspecting network {name}"),
+                err,
+            ));
+        }
+    }
+
+    let request = NetworkCreateRequest {
+        name: name.to_owned(),
+        driver: Some(driver.to_owned()),
+        labels: Some(labels),
+        ..Default::default()
+    };
+    match retry
+        .run(&format!("creating network {name}"), || {
+            docker.create_network(request.clone())
+        })
+        .await
+    {
+        Ok(_) => Ok(true),
+        // Network may be created concurrently, e.g. by `scale`.
+        Err(BollardError::DockerResponseServerError {
+            status_code: 409, ..
+        }) => Ok(false),
+        Err(err) => Err(DockerError::request(
+            format!("creating network {name}"),
+            err,
+        )),
+    }
+}
+
+/// Remove docker network `name`, unless some container is still attached to
+/// it.
+///
+/// Returns `false` if network is kept, or there is no such network.
+pub async fn remove_network_if_unused(
+    docker: &Docker,
+    retry: RetryPolicy,
+    name: &str,
+) -> Result<bool, DockerError> {
+    let network = match docker.inspect_network(name, None).await {
+        Ok(network) => network,
+        Err(BollardError::DockerResponseServerError {
+            status_code: 404, ..
+        }) => return Ok(false),
+        Err(err) => {
+            return Err(DockerError::request(
+                format!("inspecting network {name}"),
+                err,
+            ));
+        }
+    };
+    if network
+        .containers
+        .is_some_and(|containers| !containers.is_empty())
+    {
+        return Ok(false);
+    }
+
+    match retry
+        .run(&format!("removing network {name}"), || {
+            docker.remove_network(name)
+        })
+        .await
+    {
+        Ok(()) => Ok(true),
+        // Container may be attached, or network removed, concurrently.
+        Err(BollardError::DockerResponseServerError {
+            status_code: 403 | 404 | 409,
+            ..
+        }) => Ok(false),
+        Err(err) => Err(DockerError::request(
+            format!("removing network {name}"),
+            err,
+        )),
+    }
+}
+
+/// Stop container `name`, giving client its `stop_timeout` to flush state,
+/// and remove it.
+///
+/// Returns `false` if there is no such container.
+pub async fn remove_container(
+    docker: &Docker,
+    retry: RetryPolicy,
+    name: &str,
+) -> Result<bool, DockerError> {
+    match retry
+        .run(&format!("stopping {name}"), || {
+            docker.stop_container(name, None)
+        })
+        .await
+    {
+        // 304 means that container is already stopped.
+        Ok(())
+        | Err(BollardError::DockerResponseServerError {
+            status_code: 304, ..
+        }) => {}
+        Err(BollardError::DockerResponseServerError {
+            status_code: 404, ..
+        }) => return Ok(false),
+        Err(err) => return Err(DockerError::request(format!("stopping {name}"), err)),
+    }
+
+    let options = RemoveContainerOptionsBuilder::default().force(true).build();
+    match retry
+        .run(&format!("removing {name}"), || {
+            docker.remove_container(name, Some(options.clone()))
+        })
+        .await
+    {
+        Ok(()) => Ok(true),
+        // Container may be removed concurrently, e.g. when started with
+        // `--rm`.
+        Err(BollardError::DockerResponseServerError {
+            status_code: 404, ..
+        }) => Ok(true),
+        Err(err) => Err(DockerError::request(format!("removing {name}"), err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bollard::{
+        API_DEFAULT_VERSION, models::ContainerCreateBody,
+        query_parameters::CreateContainerOptionsBuilder,
+    };
+
+    use super::*;
+    use crate::testing::MockServer;
+
+    #[test]
+    fn pinned_api_version_reaches_client() {
+        let version: ApiVersion = "1.41".parse().unwrap();
+        let docker = connect_with_version("http://127.0.0.1:1", &version.into()).unwrap();
+
+        let version = docker.client_version();
+        assert_eq!((version.major_version, version.minor_version), (1, 41));
+    }
+
+    #[test]
+    fn malformed_api_version_is_rejected() {
+        assert!("1".parse::<ApiVersion>().is_err());
+        assert!("1.x".parse::<ApiVersion>().is_err());
+    }
+
+    fn server_error(status_code: u16) -> BollardError {
+        BollardError::DockerResponseServerError {
+            status_code,
+            message: "create failed".to_owned(),
+        }
+    }
+
+    /// Run `policy`, failing with errors of `statuses` in order, then
+    /// succeeding. Returns the result and the number of attempts.
+    async fn attempts(policy: RetryPolicy, statuses: &[u16]) -> (Result<(), BollardError>, usize) {
+        let mut attempts = 0;
+        let result = policy
+            .run("creating container", || {
+                let result = match statuses.get(attempts) {
+                    Some(&status) => Err(server_error(status)),
+                    None => Ok(()),
+                };
+                attempts += 1;
+                async move { result }
+            })
+            .await;
+
+        (result, attempts)
+    }
+
+    #[tokio::test]
+    async fn transient_errors_are_retried() {
+        let policy = RetryPolicy {
+            retries: 3,
+            initial_backoff: Duration::from_millis(1),
+        };
+
+        let (result, count) = attempts(policy, &[503, 503]).await;
+        assert!(result.is_ok());
+        assert_eq!(count, 3);
+    }
+
+    #[tokio::test]
+    async fn deterministic_errors_are_not_retried() {
+        let policy = RetryPolicy {
+            retries: 3,
+            initial_backoff: Duration::from_millis(1),
+        };
+
+        let (result, count) = attempts(policy, &[409]).await;
+        assert!(result.is_err());
+        assert_eq!(count, 1);
+    }
+
+    #[tokio::test]
+    async fn name_conflict_is_classified() {
+        let server = MockServer::start(vec![(
+            409,
+            r#"{"message":"Conflict. The container name \"/devnet-alpha\" is already in use"}"#,
+        )])
+        .await;
+        let docker = Docker::connect_with_http(&server.url(""), 5, API_DEFAULT_VERSION).unwrap();
+
+        let source = docker
+            .create_container(
+                Some(
+                    CreateContainerOptionsBuilder::default()
+                        .name("devnet-alpha")
+                        .build(),
+                ),
+                ContainerCreateBody::default(),
+            )
+            .await
+            .unwrap_err();
+        let err = DockerError::create("devnet-alpha", "ream", source);
+
+        assert!(matches!(&err, DockerError::NameConflict { name } if name == "devnet-alpha"));
+        assert_eq!(err.failure(), Failure::Launch);
+    }
+
+    #[tokio::test]
+    async fn unreachable_daemon_is_classified() {
+        let docker =
+            Docker::connect_with_http("http://127.0.0.1:1", 1, API_DEFAULT_VERSION).unwrap();
+
+        let source = docker.ping().await.unwrap_err();
+        let err = DockerError::request("pinging daemon", source);
+
+        assert!(matches!(err, DockerError::NotReachable { .. }));
+        assert_eq!(err.failure(), Failure::DockerUnavailable);
+        assert!(err.help().is_some());
+    }
+}
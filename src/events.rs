@@ -0,0 +1,120 @@
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::PathBuf,
+    str::FromStr,
+    time::SystemTime,
+};
+
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::exit::Failure;
+
+/// Where lifecycle events are written: either a file, or already open file
+/// descriptor, given as `fd:<n>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventTarget {
+    Path(PathBuf),
+    Fd(i32),
+}
+
+impl FromStr for EventTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("fd:") {
+            Some(fd) => fd
+                .parse()
+                .map(Self::Fd)
+                .map_err(|_| format!("expected file descriptor number, got `{fd}`")),
+            None => Ok(Self::Path(s.into())),
+        }
+    }
+}
+
+/// Lifecycle event, written as a single JSON line with `event` field naming
+/// its kind.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    ResolveStarted {
+        network: String,
+    },
+    KeysGenerated {
+        count: usize,
+    },
+    NodeCreated {
+        name: String,
+        id: String,
+    },
+    NodeHealthy {
+        name: String,
+    },
+    StartComplete,
+    Error {
+        class: Option<Failure>,
+        message: String,
+    },
+}
+
+/// Destination of [`Event`]s, passed down to every step of the pipeline.
+///
+/// Failing to write an event never fails the command itself - the sink is
+/// silently disabled instead, e.g. when reader closes the pipe.
+#[derive(Default)]
+pub struct EventSink {
+    writer: Option<Box<dyn Write + Send>>,
+}
+
+impl EventSink {
+    /// Sink, which discards all events.
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    pub fn open(target: &EventTarget) -> io::Result<Self> {
+        let file = match target {
+            EventTarget::Path(path) => File::create(path)?,
+            #[cfg(unix)]
+            EventTarget::Fd(fd) => {
+                use std::os::fd::FromRawFd;
+
+                // SAFETY: descriptor is handed over by the parent process for
+                // exclusive use as the event stream, and is not used by
+                // anything else in this process.
+                unsafe { File::from_raw_fd(*fd) }
+            }
+            #[cfg(not(unix))]
+            EventTarget::Fd(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "file descriptors are supported only on unix",
+                ));
+            }
+        };
+
+        Ok(Self {
+            writer: Some(Box::new(file)),
+        })
+    }
+
+    pub fn emit(&mut self, event: Event) {
+        let Some(writer) = &mut self.writer else {
+            return;
+        };
+
+        let mut line = json!(event);
+        if let Value::Object(fields) = &mut line {
+            fields.insert(
+                "time".to_owned(),
+                json!(humantime::format_rfc3339_millis(SystemTime::now()).to_string()),
+            );
+        }
+
+        let written = writeln!(writer, "{line}").and_then(|()| writer.flush());
+        if written.is_err() {
+            self.writer = None;
+        }
+    }
+}
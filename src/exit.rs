@@ -0,0 +1,98 @@
+use serde::Serialize;
+use sysexits::ExitCode;
+
+use crate::{docker::DockerError, launch::LaunchError};
+
+/// Class of failure, which determines exit code of the process.
+///
+/// Each class maps to a distinct [`ExitCode`], so scripts can tell failures
+/// apart without parsing the output:
+///
+/// | class               | code | sysexits      |
+/// |---------------------|------|---------------|
+/// | usage error         | 64   | `Usage`       |
+/// | config parse error  | 65   | `DataErr`     |
+/// | docker unavailable  | 69   | `Unavailable` |
+/// | launch failure      | 73   | `CantCreat`   |
+/// | timeout             | 75   | `TempFail`    |
+/// | config semantic     | 78   | `Config`      |
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Failure {
+    /// Invalid command-line arguments.
+    Usage,
+
+    /// Config file is not a valid TOML, or doesn't match the config schema.
+    ConfigParse,
+
+    /// Config file parsed successfully, but describes an invalid network.
+    ConfigSemantic,
+
+    /// Docker daemon cannot be reached.
+    DockerUnavailable,
+
+    /// Node failed to launch.
+    Launch,
+
+    /// Operation didn't complete in time.
+    Timeout,
+}
+
+impl Failure {
+    pub fn exit_code(self) -> ExitCode {
+        match self {
+            Self::Usage => ExitCode::Usage,
+            Self::ConfigParse => ExitCode::DataErr,
+            Self::ConfigSemantic => ExitCode::Config,
+            Self::DockerUnavailable => ExitCode::Unavailable,
+            Self::Launch => ExitCode::CantCreat,
+            Self::Timeout => ExitCode::TempFail,
+        }
+    }
+
+    pub fn exit(self) -> ! {
+        self.exit_code().exit()
+    }
+
+    /// Class of error, returned by a command, `None` for errors which don't
+    /// belong to any class.
+    pub fn of(err: &color_eyre::Report) -> Option<Self> {
+        if let Some(err) = err.downcast_ref::<DockerError>() {
+            return Some(err.failure());
+        }
+
+        err.downcast_ref::<LaunchError>().map(LaunchError::failure)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bollard::errors::Error as BollardError;
+    use color_eyre::eyre::Report;
+
+    use super::*;
+
+    #[test]
+    fn classes_map_to_distinct_codes() {
+        assert_eq!(Failure::Usage.exit_code() as u8, 64);
+        assert_eq!(Failure::ConfigParse.exit_code() as u8, 65);
+        assert_eq!(Failure::ConfigSemantic.exit_code() as u8, 78);
+    }
+
+    #[test]
+    fn unreachable_daemon_is_unavailable() {
+        let err = DockerError::request(
+            "pinging docker daemon",
+            BollardError::SocketNotFoundError("/var/run/docker.sock".to_owned()),
+        );
+        let failure = Failure::of(&Report::new(err));
+
+        assert_eq!(failure, Some(Failure::DockerUnavailable));
+        assert_eq!(failure.unwrap().exit_code() as u8, 69);
+    }
+
+    #[test]
+    fn unclassified_error_has_no_class() {
+        assert_eq!(Failure::of(&Report::msg("something went wrong")), None);
+    }
+}
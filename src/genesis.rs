@@ -0,0 +1,137 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::{config::ResolvedNetworkConfig, registry::ValidatorRegistry};
+
+/// Genesis of the network, shared by all nodes.
+///
+/// Field names follow lean genesis config, so that clients can read it as is.
+/// Validators are listed by global index, see [`ValidatorRegistry`].
+#[derive(Debug, Clone, Serialize)]
+pub struct Genesis {
+    #[serde(rename = "GENESIS_TIME")]
+    pub genesis_time: u64,
+
+    #[serde(rename = "SECONDS_PER_SLOT")]
+    pub seconds_per_slot: u64,
+
+    #[serde(rename = "SLOTS_PER_EPOCH")]
+    pub slots_per_epoch: u64,
+
+    #[serde(flatten)]
+    pub registry: ValidatorRegistry,
+}
+
+impl Genesis {
+    /// Name of genesis file in output directory.
+    pub const FILE_NAME: &str = "genesis.json";
+
+    /// Genesis of `network`, reading public keys of its validators.
+    pub fn new(network: &ResolvedNetworkConfig) -> io::Result<Self> {
+        let timing = network.timing();
+
+        Ok(Self {
+            genesis_time: timing.genesis_time,
+            seconds_per_slot: timing.seconds_per_slot,
+            slots_per_epoch: timing.slots_per_epoch,
+            registry: ValidatorRegistry::new(network)?,
+        })
+    }
+
+    pub fn validator_count(&self) -> usize {
+        self.registry.len()
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Write genesis into `dir`, returning path of the written file.
+    pub fn write(&self, dir: &Path) -> io::Result<PathBuf> {
+        let path = dir.join(Self::FILE_NAME);
+        fs::write(&path, self.to_json()?)?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::{
+        client::ClientKind,
+        config::{
+            ClientSource, NetworkConfig, NetworkConfigBuilder, NodeConfigBuilder, ResolveOptions,
+        },
+        validator::KeyStore,
+    };
+
+    #[test]
+    fn timing_is_written_to_chain_config() {
+        let network = NetworkConfigBuilder::new("devnet")
+            .genesis_time(1_700_000_000)
+            .seconds_per_slot(2)
+            .slots_per_epoch(8)
+            .add_node(NodeConfigBuilder::new(ClientSource::Default(
+                ClientKind::Ream,
+            )))
+            .build()
+            .resolve(ResolveOptions::new(
+                Path::new("network.toml"),
+                KeyStore::Skip,
+            ))
+            .unwrap();
+
+        let genesis: serde_json::Value =
+            serde_json::from_str(&Genesis::new(&network).unwrap().to_json().unwrap()).unwrap();
+
+        assert_eq!(genesis["GENESIS_TIME"], 1_700_000_000);
+        assert_eq!(genesis["SECONDS_PER_SLOT"], 2);
+        assert_eq!(genesis["SLOTS_PER_EPOCH"], 8);
+    }
+
+    #[test]
+    fn validators_are_ordered_by_global_index() {
+        let config: NetworkConfig = toml::from_str(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            name = "alpha"
+            client = "ream"
+            validator_count = 2
+            num_active_epochs = 4
+
+            [[node]]
+            name = "beta"
+            client = "zeam"
+            validator_count = 1
+            num_active_epochs = 4
+            "#,
+        )
+        .unwrap();
+        let network = config
+            .resolve(ResolveOptions::new(
+                Path::new("network.toml"),
+                KeyStore::Memory,
+            ))
+            .unwrap();
+
+        let genesis: serde_json::Value =
+            serde_json::from_str(&Genesis::new(&network).unwrap().to_json().unwrap()).unwrap();
+        let entries = genesis["GENESIS_VALIDATORS"].as_array().unwrap();
+        assert_eq!(entries.len(), 3);
+
+        for node in network.nodes().values() {
+            for &index in &node.validators {
+                let key = network.validators()[index].keys.public_key().unwrap();
+                assert_eq!(entries[index], format!("0x{}", hex::encode(key)));
+            }
+        }
+    }
+}
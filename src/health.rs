@@ -0,0 +1,136 @@
+use std::{net::SocketAddr, time::Duration};
+
+use bollard::{Docker, models::HealthStatusEnum};
+use reqwest::StatusCode;
+use tokio::time::{Instant, sleep};
+
+use crate::config::{ClientSource, ResolvedNetworkConfig};
+
+/// Delay before the second probe attempt. Doubled after each failed attempt.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(250);
+
+/// Upper bound for delay between probe attempts.
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+/// How to find out, whether node is healthy.
+#[derive(Debug, Clone)]
+pub enum HealthProbe {
+    /// Poll client REST endpoint, until it responds with 200 or 206.
+    Http { url: String },
+
+    /// Client has no known health endpoint, so rely on container state.
+    Container { name: String },
+}
+
+impl HealthProbe {
+    /// Probe of `node`, polling its health path on host rpc port, see
+    /// [`Client::health_path`](crate::client::Client::health_path).
+    ///
+    /// Returns `None` for local processes without health path, which are
+    /// trusted to be healthy once spawned.
+    pub fn new(network: &ResolvedNetworkConfig, node: &str) -> Option<Self> {
+        let resolved = &network.nodes()[node];
+
+        match (&resolved.health_path, &resolved.client) {
+            (Some(path), _) => Some(Self::Http {
+                url: format!(
+                    "http://{}{path}",
+                    SocketAddr::new(network.host_address(node), resolved.ports.rpc)
+                ),
+            }),
+            (None, ClientSource::Binary { .. }) => None,
+            (None, _) => Some(Self::Container {
+                name: network.container_name(node),
+            }),
+        }
+    }
+
+    async fn check(&self, docker: &Docker, http: &reqwest::Client) -> bool {
+        match self {
+            Self::Http { url } => http.get(url).send().await.is_ok_and(|response| {
+                matches!(
+                    response.status(),
+                    StatusCode::OK | StatusCode::PARTIAL_CONTENT
+                )
+            }),
+            Self::Container { name } => {
+                let Ok(response) = docker.inspect_container(name, None).await else {
+                    return false;
+                };
+
+                let Some(state) = response.state else {
+                    return false;
+                };
+
+                // Containers without healthcheck are considered healthy as
+                // soon as they run.
+                match state.health.and_then(|health| health.status) {
+                    Some(HealthStatusEnum::HEALTHY) => true,
+                    Some(HealthStatusEnum::STARTING | HealthStatusEnum::UNHEALTHY) => false,
+                    _ => state.running.unwrap_or(false),
+                }
+            }
+        }
+    }
+}
+
+/// Probe node with exponential backoff, until it becomes healthy or `timeout`
+/// elapses.
+///
+/// Returns `true` if node became healthy in time.
+pub async fn wait_healthy(docker: &Docker, probe: &HealthProbe, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    let http = reqwest::Client::builder()
+        .timeout(MAX_BACKOFF)
+        .build()
+        .expect("http client without tls should always build");
+
+    let mut backoff = INITIAL_BACKOFF;
+
+    loop {
+        if probe.check(docker, &http).await {
+            return true;
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            return false;
+        }
+
+        sleep(backoff.min(deadline - now)).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockServer;
+
+    /// Client of daemon, which is never reached, as http probes don't use it.
+    fn docker() -> Docker {
+        Docker::connect_with_http("http://127.0.0.1:1", 1, bollard::API_DEFAULT_VERSION).unwrap()
+    }
+
+    #[tokio::test]
+    async fn healthy_only_after_ok() {
+        let server = MockServer::start(vec![(503, ""), (503, ""), (200, "")]).await;
+        let probe = HealthProbe::Http {
+            url: server.url("/eth/v1/node/health"),
+        };
+
+        assert!(wait_healthy(&docker(), &probe, Duration::from_secs(10)).await);
+        assert_eq!(server.requests(), 3);
+    }
+
+    #[tokio::test]
+    async fn unhealthy_until_timeout() {
+        let server = MockServer::start(vec![(503, "")]).await;
+        let probe = HealthProbe::Http {
+            url: server.url("/eth/v1/node/health"),
+        };
+
+        assert!(!wait_healthy(&docker(), &probe, Duration::from_millis(600)).await);
+        assert!(server.requests() > 1);
+    }
+}
@@ -0,0 +1,134 @@
+use std::{net::IpAddr, path::Path};
+
+use color_eyre::{
+    Result,
+    eyre::{Context as _, bail},
+};
+use tokio::process::Command;
+
+/// Address of a running node, exposed to hooks.
+#[derive(Debug, Clone)]
+pub struct NodeEndpoint {
+    pub name: String,
+    pub ip: IpAddr,
+    pub http_port: u16,
+}
+
+/// Name of the environment variable, holding `field` of node `name`, e.g.
+/// `LIESAS_NODE_REAM_0_IP`.
+fn node_var(name: &str, field: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    format!("LIESAS_NODE_{name}_{field}")
+}
+
+/// Environment, passed to the hook:
+///
+/// - `LIESAS_NETWORK` - name of the network;
+/// - `LIESAS_NODES` - space-separated node names, in declaration order;
+/// - `LIESAS_NODE_<NAME>_IP` and `LIESAS_NODE_<NAME>_HTTP_PORT` - address of
+///   each node, with name uppercased and non-alphanumerics replaced by `_`.
+pub fn hook_env(network: &str, nodes: &[NodeEndpoint]) -> Vec<(String, String)> {
+    let mut env = vec![
+        ("LIESAS_NETWORK".to_owned(), network.to_owned()),
+        (
+            "LIESAS_NODES".to_owned(),
+            nodes
+                .iter()
+                .map(|node| node.name.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+        ),
+    ];
+
+    for node in nodes {
+        env.push((node_var(&node.name, "IP"), node.ip.to_string()));
+        env.push((
+            node_var(&node.name, "HTTP_PORT"),
+            node.http_port.to_string(),
+        ));
+    }
+
+    env
+}
+
+/// Run `command` with `sh -c` in `base_dir`, once all nodes are healthy.
+///
+/// Fails if hook exits with non-zero status.
+pub async fn run_post_start(
+    command: &str,
+    base_dir: &Path,
+    network: &str,
+    nodes: &[NodeEndpoint],
+) -> Result<()> {
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(base_dir)
+        .envs(hook_env(network, nodes))
+        .status()
+        .await
+        .with_context(|| format!("failed to run post_start hook `{command}`"))?;
+
+    if !status.success() {
+        bail!("post_start hook `{command}` failed with {status}");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, net::Ipv4Addr};
+
+    use super::*;
+
+    fn endpoints() -> Vec<NodeEndpoint> {
+        ["ream_0", "ream-1"]
+            .into_iter()
+            .zip([5052, 5053])
+            .map(|(name, http_port)| NodeEndpoint {
+                name: name.to_owned(),
+                ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+                http_port,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn hook_receives_node_addresses() {
+        let command = r#"
+            test "$LIESAS_NETWORK" = devnet &&
+            test "$LIESAS_NODES" = "ream_0 ream-1" &&
+            test "$LIESAS_NODE_REAM_0_IP" = 127.0.0.1 &&
+            test "$LIESAS_NODE_REAM_0_HTTP_PORT" = 5052 &&
+            test "$LIESAS_NODE_REAM_1_IP" = 127.0.0.1 &&
+            test "$LIESAS_NODE_REAM_1_HTTP_PORT" = 5053
+        "#;
+
+        run_post_start(command, &env::temp_dir(), "devnet", &endpoints())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn failing_hook_is_error() {
+        let err = run_post_start("exit 3", &env::temp_dir(), "devnet", &endpoints())
+            .await
+            .unwrap_err();
+
+        assert!(
+            err.to_string()
+                .starts_with("post_start hook `exit 3` failed")
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use std::{fmt, str::FromStr};
+
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ImageReferenceError {
+    #[error("image reference is empty")]
+    Empty,
+
+    #[error("invalid registry `{0}`")]
+    InvalidRegistry(String),
+
+    #[error(
+        "invalid repository path component `{0}`, only lowercase letters, digits and separators are allowed"
+    )]
+    InvalidComponent(String),
+
+    #[error("invalid tag `{0}`")]
+    InvalidTag(String),
+
+    #[error("invalid digest `{0}`, expected `<algorithm>:<hex>`")]
+    InvalidDigest(String),
+}
+
+/// Docker image reference, in `[registry/]name[:tag][@digest]` format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageReference {
+    registry: Option<String>,
+    repository: String,
+    tag: Option<String>,
+    digest: Option<String>,
+}
+
+impl ImageReference {
+    /// Registry host, e.g. `ghcr.io`. `None` for images from Docker Hub.
+    pub fn registry(&self) -> Option<&str> {
+        self.registry.as_deref()
+    }
+
+    /// Same image, with `tag` instead of the original tag and digest.
+    pub fn with_tag(self, tag: &str) -> Result<Self, ImageReferenceError> {
+        if !is_valid_tag(tag) {
+            return Err(ImageReferenceError::InvalidTag(tag.to_owned()));
+        }
+
+        Ok(Self {
+            tag: Some(tag.to_owned()),
+            digest: None,
+            ..self
+        })
+    }
+}
+
+/// Whether first path component of a reference is a registry host, following
+/// the same rules as docker itself.
+fn is_registry(component: &str) -> bool {
+    component.contains('.') || component.contains(':') || component == "localhost"
+}
+
+fn is_valid_registry(registry: &str) -> bool {
+    let (host, port) = match registry.rsplit_once(':') {
+        Some((host, port)) => (host, Some(port)),
+        None => (registry, None),
+    };
+
+    !host.is_empty()
+        && host.split('.').all(|label| {
+            !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+        && port.is_none_or(|port| !port.is_empty() && port.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Path component consists of lowercase alphanumerics, separated by `.`, `_`,
+/// `__` or any number of `-`.
+fn is_valid_component(component: &str) -> bool {
+    let bytes = component.as_bytes();
+
+    let (Some(first), Some(last)) = (bytes.first(), bytes.last()) else {
+        return false;
+    };
+
+    let is_alnum = |c: &u8| c.is_ascii_lowercase() || c.is_ascii_digit();
+
+    is_alnum(first)
+        && is_alnum(last)
+        && bytes
+            .iter()
+            .all(|c| is_alnum(c) || matches!(c, b'.' | b'_' | b'-'))
+        && !component.contains("..")
+        && !component.contains("___")
+        && !component.contains("._")
+        && !component.contains("_.")
+}
+
+fn is_valid_tag(tag: &str) -> bool {
+    let mut chars = tag.chars();
+
+    tag.len() <= 128
+        && chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+fn is_valid_digest(digest: &str) -> bool {
+    let Some((algorithm, hex)) = digest.split_once(':') else {
+        return false;
+    };
+
+    !algorithm.is_empty()
+        && algorithm.chars().all(|c| {
+            c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '+' | '.' | '_' | '-')
+        })
+        && hex.len() >= 32
+        && hex.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+impl FromStr for ImageReference {
+    type Err = ImageReferenceError;
+
+    /// Parse reference, trimming surrounding whitespace and lowercasing
+    /// registry host, as docker treats it case-insensitively.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(ImageReferenceError::Empty);
+        }
+
+        let (rest, digest) = match s.split_once('@') {
+            Some((rest, digest)) => {
+                if !is_valid_digest(digest) {
+                    return Err(ImageReferenceError::InvalidDigest(digest.to_owned()));
+                }
+
+                (rest, Some(digest.to_lowercase()))
+            }
+            None => (s, None),
+        };
+
+        // Tag separator is the last colon after the last slash, so registry
+        // port is not confused with a tag.
+        let last_slash = rest.rfind('/').map_or(0, |v| v + 1);
+        let (name, tag) = match rest[last_slash..].rfind(':') {
+            Some(index) => {
+                let index = last_slash + index;
+                let tag = &rest[index + 1..];
+                if !is_valid_tag(tag) {
+                    return Err(ImageReferenceError::InvalidTag(tag.to_owned()));
+                }
+
+                (&rest[..index], Some(tag.to_owned()))
+            }
+            None => (rest, None),
+        };
+
+        let (registry, repository) = match name.split_once('/') {
+            Some((first, rest)) if is_registry(first) => {
+                if !is_valid_registry(first) {
+                    return Err(ImageReferenceError::InvalidRegistry(first.to_owned()));
+                }
+
+                (Some(first.to_lowercase()), rest)
+            }
+            _ => (None, name),
+        };
+
+        if let Some(component) = repository.split('/').find(|v| !is_valid_component(v)) {
+            return Err(ImageReferenceError::InvalidComponent(component.to_owned()));
+        }
+
+        Ok(Self {
+            registry,
+            repository: repository.to_owned(),
+            tag,
+            digest,
+        })
+    }
+}
+
+impl fmt::Display for ImageReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(registry) = &self.registry {
+            write!(f, "{registry}/")?;
+        }
+
+        f.write_str(&self.repository)?;
+
+        if let Some(tag) = &self.tag {
+            write!(f, ":{tag}")?;
+        }
+
+        if let Some(digest) = &self.digest {
+            write!(f, "@{digest}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIGEST: &str = "sha256:4bcd3c4e1c7d2f9b8a6e5d4c3b2a1f0e9d8c7b6a5f4e3d2c1b0a9f8e7d6c5b4a";
+
+    #[test]
+    fn tagged_reference_is_normalized() {
+        let image: ImageReference = " GHCR.io/reamlabs/ream:latest ".parse().unwrap();
+
+        assert_eq!(image.registry(), Some("ghcr.io"));
+        assert_eq!(image.to_string(), "ghcr.io/reamlabs/ream:latest");
+    }
+
+    #[test]
+    fn digest_reference_is_parsed() {
+        let source = format!("localhost:5000/ream@{DIGEST}");
+        let image: ImageReference = source.parse().unwrap();
+
+        assert_eq!(image.registry(), Some("localhost:5000"));
+        assert_eq!(image.to_string(), source);
+    }
+
+    #[test]
+    fn illegal_characters_are_rejected() {
+        assert_eq!(
+            "reamlabs/Re@m".parse::<ImageReference>(),
+            Err(ImageReferenceError::InvalidDigest("m".to_owned()))
+        );
+        assert_eq!(
+            "reamlabs/re$am:latest".parse::<ImageReference>(),
+            Err(ImageReferenceError::InvalidComponent("re$am".to_owned()))
+        );
+    }
+}
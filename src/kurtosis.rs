@@ -0,0 +1,110 @@
+use indexmap::IndexMap;
+use serde::Serialize;
+
+use crate::config::{ClientSource, NodeNameDefinition, ResolvedNetworkConfig};
+
+#[derive(Debug, Clone, Serialize)]
+struct Participant {
+    cl_type: &'static str,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cl_image: Option<String>,
+
+    count: usize,
+
+    validator_count: usize,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    cl_extra_params: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ChainParams {
+    seconds_per_slot: u64,
+}
+
+/// Kurtosis `network_params.yaml`, describing the same topology as resolved
+/// network.
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkParams {
+    participants: Vec<Participant>,
+    network_params: ChainParams,
+}
+
+impl NetworkParams {
+    /// Kurtosis groups nodes by participant, so prefixed replicas are
+    /// collapsed back into single participant with `count`.
+    pub fn new(network: &ResolvedNetworkConfig) -> Self {
+        let mut participants = IndexMap::<String, Participant>::new();
+
+        for (name, node) in network.nodes() {
+            let key = match &node.def {
+                NodeNameDefinition::Singular(_) => name.clone(),
+                NodeNameDefinition::Prefix { prefix, .. } => prefix.clone(),
+            };
+
+            participants
+                .entry(key)
+                .and_modify(|participant| participant.count += 1)
+                .or_insert_with(|| Participant {
+                    cl_type: node.client.kind().kurtosis_name(),
+                    cl_image: match &node.client {
+                        ClientSource::Image { image, .. } => Some(image.clone()),
+                        _ => None,
+                    },
+                    count: 1,
+                    validator_count: node.validators.len(),
+                    cl_extra_params: node.extra_args.clone(),
+                });
+        }
+
+        Self {
+            participants: participants.into_values().collect(),
+            network_params: ChainParams {
+                seconds_per_slot: network.timing().seconds_per_slot,
+            },
+        }
+    }
+
+    pub fn to_yaml(&self) -> Result<String, serde_yaml::Error> {
+        serde_yaml::to_string(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::resolve;
+
+    #[test]
+    fn replicas_collapse_into_participant() {
+        let network = resolve(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            client = "ream"
+            count = 3
+            validator_count = 2
+
+            [[node]]
+            client = { kind = "zeam", image = "zeam:dev" }
+            extra_args = ["--verbose"]
+            "#,
+        )
+        .unwrap();
+
+        let params = NetworkParams::new(&network);
+        assert_eq!(params.participants.len(), 2);
+
+        let ream = &params.participants[0];
+        assert_eq!((ream.cl_type, ream.count), ("ream", 3));
+        assert_eq!(ream.validator_count, 2);
+        assert_eq!(ream.cl_image, None);
+
+        let zeam = &params.participants[1];
+        assert_eq!((zeam.cl_type, zeam.count), ("zeam", 1));
+        assert_eq!(zeam.cl_image.as_deref(), Some("zeam:dev"));
+        assert_eq!(zeam.cl_extra_params, ["--verbose"]);
+    }
+}
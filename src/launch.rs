@@ -0,0 +1,700 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    io::{self, IsTerminal},
+    path::PathBuf,
+    time::Duration,
+};
+
+use bollard::Docker;
+use color_eyre::owo_colors::{OwoColorize, Stream};
+use futures_util::{StreamExt, stream::FuturesUnordered};
+use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
+use thiserror::Error;
+use tracing::{debug, info};
+
+use crate::{
+    config::{NetworkPlan, NodePlan},
+    container::{MANAGED_LABEL, NETWORK_LABEL},
+    docker::{
+        DockerError, PullPolicy, RetryPolicy, ensure_network, image_exists, pull_image,
+        remove_container, remove_network_if_unused,
+    },
+    events::{Event, EventSink},
+    exit::Failure,
+    health::{HealthProbe, wait_healthy},
+    output,
+    peers::{self, PeerTarget},
+    process,
+};
+
+/// How long to wait for a dependency to become healthy, before giving up on
+/// its dependents.
+const DEPENDENCY_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Error)]
+pub enum LaunchError {
+    #[error(transparent)]
+    Docker(#[from] DockerError),
+
+    #[error("failed to pull image of `{node}`")]
+    Pull {
+        node: String,
+        #[source]
+        source: DockerError,
+    },
+
+    #[error("`{dependency}`, required by `{node}`, didn't become healthy in {timeout:?}")]
+    DependencyUnhealthy {
+        node: String,
+        dependency: String,
+        timeout: Duration,
+    },
+
+    #[error("{} didn't become healthy in {timeout:?}", .nodes.iter().map(|node| format!("`{node}`")).collect::<Vec<_>>().join(", "))]
+    Unhealthy {
+        nodes: Vec<String>,
+        timeout: Duration,
+    },
+
+    #[error("nodes didn't connect in {timeout:?}: {}", .nodes.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+    UnderConnected {
+        nodes: Vec<peers::UnderConnected>,
+        timeout: Duration,
+    },
+
+    #[error("failed to spawn `{node}` from {bin:?}")]
+    Spawn {
+        node: String,
+        bin: PathBuf,
+        #[source]
+        error: io::Error,
+    },
+}
+
+impl LaunchError {
+    pub fn failure(&self) -> Failure {
+        match self {
+            Self::Docker(err) | Self::Pull { source: err, .. } => err.failure(),
+            Self::DependencyUnhealthy { .. }
+            | Self::Unhealthy { .. }
+            | Self::UnderConnected { .. } => Failure::Timeout,
+            Self::Spawn { .. } => Failure::Launch,
+        }
+    }
+}
+
+/// What node runs in.
+#[derive(Debug, Clone)]
+pub enum Instance {
+    /// Docker container, with given id.
+    Container(String),
+
+    /// Local process, with given pid.
+    Process(u32),
+}
+
+impl fmt::Display for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Container(id) => f.write_str(short_id(id)),
+            Self::Process(pid) => write!(f, "pid {pid}"),
+        }
+    }
+}
+
+/// Spinner, showing latest status of pulling `image`, hidden unless stderr is
+/// a terminal.
+fn pull_progress(image: &str) -> ProgressBar {
+    if output::is_json() || !io::stderr().is_terminal() {
+        return ProgressBar::hidden();
+    }
+
+    let progress = ProgressBar::new_spinner().with_style(
+        ProgressStyle::with_template("{spinner} pulling {prefix}: {wide_msg}")
+            .expect("progress template is valid"),
+    );
+    progress.set_prefix(image.to_owned());
+    progress.enable_steady_tick(Duration::from_millis(100));
+    progress
+}
+
+/// Container or process, created for a node.
+#[derive(Debug, Clone)]
+pub struct LaunchedNode {
+    pub name: String,
+    pub instance: Instance,
+}
+
+/// Creates and starts node containers and processes, remembering them so that
+/// they can be removed if launch fails midway.
+pub struct Launcher<'a> {
+    docker: &'a Docker,
+    retry: RetryPolicy,
+    network: String,
+    launched: Vec<LaunchedNode>,
+
+    /// Nodes, which were already seen healthy.
+    healthy: HashSet<String>,
+
+    /// Docker network, created by [`Launcher::create_network`], rather than
+    /// reused.
+    created_network: Option<String>,
+}
+
+impl<'a> Launcher<'a> {
+    pub fn new(docker: &'a Docker, retry: RetryPolicy, network: &str) -> Self {
+        Self {
+            docker,
+            retry,
+            network: network.to_owned(),
+            launched: Vec::new(),
+            healthy: HashSet::new(),
+            created_network: None,
+        }
+    }
+
+    /// Create docker network, containers of `plan` are attached to, or reuse
+    /// existing one. Nothing is created, if `plan` has no containers.
+    pub async fn create_network(
+        &mut self,
+        plan: &NetworkPlan,
+        driver: &str,
+    ) -> Result<(), LaunchError> {
+        if plan.nodes.iter().all(|node| node.container.is_none()) {
+            return Ok(());
+        }
+
+        let name = plan.network.docker_network();
+        let labels = HashMap::from([
+            (MANAGED_LABEL.to_owned(), "true".to_owned()),
+            (NETWORK_LABEL.to_owned(), self.network.clone()),
+        ]);
+        if ensure_network(self.docker, self.retry, &name, driver, labels).await? {
+            info!("created docker network {name}");
+            self.created_network = Some(name);
+        } else {
+            debug!("reusing docker network {name}");
+        }
+
+        Ok(())
+    }
+
+    /// Pull images of container nodes of `plan`, each distinct image once.
+    ///
+    /// Unless `policy` is [`PullPolicy::Always`], images which are already
+    /// present locally are not pulled.
+    pub async fn pull_images(
+        &self,
+        plan: &NetworkPlan,
+        policy: PullPolicy,
+    ) -> Result<(), LaunchError> {
+        if policy == PullPolicy::Never {
+            return Ok(());
+        }
+
+        let mut seen = HashSet::new();
+        for node in &plan.nodes {
+            let Some(spec) = &node.container else {
+                continue;
+            };
+            if !seen.insert(&spec.image) {
+                continue;
+            }
+
+            let pull = async {
+                if policy == PullPolicy::Missing && image_exists(self.docker, &spec.image).await? {
+                    return Ok(());
+                }
+
+                let progress = pull_progress(&spec.image);
+                let result = pull_image(
+                    self.docker,
+                    self.retry,
+                    &spec.image,
+                    plan.network.registries(),
+                    &|info| {
+                        let status = [info.id.as_deref(), info.status.as_deref()]
+                            .into_iter()
+                            .flatten()
+                            .collect::<Vec<_>>()
+                            .join(": ");
+                        let detail = info.progress_detail.as_ref();
+                        progress.set_message(match detail.and_then(|v| v.current.zip(v.total)) {
+                            Some((current, total)) => format!(
+                                "{status} {}/{}",
+                                HumanBytes(current as u64),
+                                HumanBytes(total as u64)
+                            ),
+                            None => status,
+                        });
+                    },
+                )
+                .await;
+                progress.finish_and_clear();
+
+                if result.is_ok() && !output::is_json() {
+                    eprintln!("pulled {}", spec.image);
+                }
+                result
+            };
+
+            pull.await.map_err(|source| LaunchError::Pull {
+                node: node.name.clone(),
+                source,
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Launch nodes of `plan` in launch order, waiting for dependencies of
+    /// each node to become healthy first.
+    ///
+    /// Nodes running local binary are spawned as processes, without docker.
+    /// On failure, nodes which were already launched are left running, use
+    /// [`Launcher::rollback`] to remove them.
+    pub async fn launch(
+        &mut self,
+        plan: &NetworkPlan,
+        events: &mut EventSink,
+    ) -> Result<(), LaunchError> {
+        for node in &plan.nodes {
+            for dependency in &plan.network.nodes()[&node.name].depends_on {
+                if self.healthy.contains(dependency) {
+                    continue;
+                }
+
+                let Some(probe) = HealthProbe::new(&plan.network, dependency) else {
+                    continue;
+                };
+
+                debug!("{} waits for {dependency} to become healthy", node.name);
+                if !wait_healthy(self.docker, &probe, DEPENDENCY_TIMEOUT).await {
+                    return Err(LaunchError::DependencyUnhealthy {
+                        node: node.name.clone(),
+                        dependency: dependency.clone(),
+                        timeout: DEPENDENCY_TIMEOUT,
+                    });
+                }
+
+                events.emit(Event::NodeHealthy {
+                    name: dependency.clone(),
+                });
+                self.healthy.insert(dependency.clone());
+            }
+
+            if let Some(spec) = &node.process {
+                info!("spawning {} from {:?}", node.name, spec.bin);
+                let pid = spec
+                    .spawn(&process::run_dir(&self.network))
+                    .map_err(|error| LaunchError::Spawn {
+                        node: node.name.clone(),
+                        bin: spec.bin.clone(),
+                        error,
+                    })?;
+
+                self.record(node, Instance::Process(pid), events);
+                continue;
+            }
+
+            let Some(spec) = &node.container else {
+                unreachable!("resolve rejects nodes with nothing to run");
+            };
+
+            info!("creating container {} from {}", spec.name, spec.image);
+            let (options, body) = spec.create_request();
+            let response = self
+                .retry
+                .run(&format!("creating {}", node.name), || {
+                    self.docker
+                        .create_container(Some(options.clone()), body.clone())
+                })
+                .await
+                .map_err(|err| DockerError::create(&spec.name, &spec.image, err))?;
+
+            // Remember container as soon as it exists, so that rollback
+            // removes it even if it fails to start.
+            self.record(node, Instance::Container(response.id), events);
+
+            self.retry
+                .run(&format!("starting {}", node.name), || {
+                    self.docker.start_container(&spec.name, None)
+                })
+                .await
+                .map_err(|err| DockerError::start(&spec.name, err))?;
+        }
+
+        Ok(())
+    }
+
+    /// Replace container of `node` with a fresh one, e.g. to pick up updated
+    /// image.
+    pub async fn relaunch(
+        &mut self,
+        node: &NodePlan,
+        events: &mut EventSink,
+    ) -> Result<(), LaunchError> {
+        let Some(spec) = &node.container else {
+            return Ok(());
+        };
+
+        remove_container(self.docker, self.retry, &spec.name).await?;
+        self.launched.retain(|launched| launched.name != node.name);
+        self.healthy.remove(&node.name);
+
+        let (options, body) = spec.create_request();
+        let response = self
+            .retry
+            .run(&format!("creating {}", node.name), || {
+                self.docker
+                    .create_container(Some(options.clone()), body.clone())
+            })
+            .await
+            .map_err(|err| DockerError::create(&spec.name, &spec.image, err))?;
+        self.record(node, Instance::Container(response.id), events);
+
+        self.retry
+            .run(&format!("starting {}", node.name), || {
+                self.docker.start_container(&spec.name, None)
+            })
+            .await
+            .map_err(|err| DockerError::start(&spec.name, err))?;
+
+        Ok(())
+    }
+
+    /// Remember launched node, and announce it.
+    fn record(&mut self, node: &NodePlan, instance: Instance, events: &mut EventSink) {
+        let id = match &instance {
+            Instance::Container(id) => id.clone(),
+            Instance::Process(pid) => pid.to_string(),
+        };
+
+        if !output::is_json() {
+            println!("started {} ({instance})", node.name);
+        }
+        events.emit(Event::NodeCreated {
+            name: node.name.clone(),
+            id,
+        });
+        self.launched.push(LaunchedNode {
+            name: node.name.clone(),
+            instance,
+        });
+    }
+
+    /// Wait for all launched nodes to become healthy, printing status of
+    /// each node as soon as it is known.
+    ///
+    /// Nodes are probed concurrently, so `timeout` applies to all of them at
+    /// once.
+    pub async fn wait_healthy(
+        &mut self,
+        plan: &NetworkPlan,
+        timeout: Duration,
+        events: &mut EventSink,
+    ) -> Result<(), LaunchError> {
+        let docker = self.docker;
+        let mut probes = self
+            .launched
+            .iter()
+            .filter(|node| !self.healthy.contains(&node.name))
+            .filter_map(|node| Some((&node.name, HealthProbe::new(&plan.network, &node.name)?)))
+            .map(|(name, probe)| async move { (name, wait_healthy(docker, &probe, timeout).await) })
+            .collect::<FuturesUnordered<_>>();
+
+        let mut healthy = Vec::new();
+        let mut unhealthy = Vec::new();
+        while let Some((name, is_healthy)) = probes.next().await {
+            if !output::is_json() {
+                if is_healthy {
+                    println!(
+                        "{name} is {}",
+                        "healthy".if_supports_color(Stream::Stdout, |text| text.green())
+                    );
+                } else {
+                    println!(
+                        "{name} is {}",
+                        "unhealthy".if_supports_color(Stream::Stdout, |text| text.red())
+                    );
+                }
+            }
+
+            if is_healthy {
+                healthy.push(name.clone());
+            } else {
+                unhealthy.push(name.clone());
+            }
+        }
+        drop(probes);
+
+        for name in healthy {
+            events.emit(Event::NodeHealthy { name: name.clone() });
+            self.healthy.insert(name);
+        }
+
+        if !unhealthy.is_empty() {
+            unhealthy.sort();
+            return Err(LaunchError::Unhealthy {
+                nodes: unhealthy,
+                timeout,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Wait for launched nodes to connect to their peers, see
+    /// [`peers::verify_peers`]. Nodes of clients, which don't expose peer
+    /// count, are not checked.
+    pub async fn verify_peers(
+        &self,
+        plan: &NetworkPlan,
+        timeout: Duration,
+    ) -> Result<(), LaunchError> {
+        let targets = self
+            .launched
+            .iter()
+            .filter_map(|node| PeerTarget::new(&plan.network, &node.name))
+            .collect::<Vec<_>>();
+
+        let nodes = peers::verify_peers(&targets, timeout).await;
+        if !nodes.is_empty() {
+            return Err(LaunchError::UnderConnected { nodes, timeout });
+        }
+
+        if !output::is_json() {
+            println!(
+                "{} node(s) {}",
+                targets.len(),
+                "connected".if_supports_color(Stream::Stdout, |text| text.green())
+            );
+        }
+
+        Ok(())
+    }
+
+    pub fn nodes(&self) -> &[LaunchedNode] {
+        &self.launched
+    }
+
+    /// Roll back launch of `plan`, which was interrupted midway.
+    ///
+    /// Container may be created by the interrupted request, without being
+    /// remembered, so containers of `plan`, which weren't launched, are
+    /// removed too.
+    pub async fn rollback_interrupted(&mut self, plan: &NetworkPlan) {
+        for node in &plan.nodes {
+            let Some(spec) = &node.container else {
+                continue;
+            };
+            if self
+                .launched
+                .iter()
+                .any(|launched| launched.name == node.name)
+            {
+                continue;
+            }
+
+            if let Err(err) = remove_container(self.docker, self.retry, &spec.name).await {
+                warn_rollback(&err.into());
+            }
+        }
+
+        self.rollback().await;
+    }
+
+    /// Stop and remove all launched nodes, in reverse launch order.
+    ///
+    /// Removal is best-effort: failures are reported, but don't stop removal
+    /// of remaining containers.
+    pub async fn rollback(&mut self) {
+        while let Some(node) = self.launched.pop() {
+            let result = match node.instance {
+                Instance::Container(id) => remove_container(self.docker, self.retry, &id)
+                    .await
+                    .map_err(color_eyre::Report::from),
+                Instance::Process(_) => process::terminate(&self.network, &node.name)
+                    .await
+                    .map_err(color_eyre::Report::from),
+            };
+
+            match result {
+                Ok(_) if !output::is_json() => println!("removed {}", node.name),
+                Ok(_) => {}
+                Err(err) => warn_rollback(&err),
+            }
+        }
+
+        if let Some(network) = self.created_network.take()
+            && let Err(err) = remove_network_if_unused(self.docker, self.retry, &network).await
+        {
+            warn_rollback(&err.into());
+        }
+    }
+}
+
+/// Report failure to remove something during rollback, without stopping it.
+fn warn_rollback(err: &color_eyre::Report) {
+    if output::is_json() {
+        output::warn(output::Diagnostic::warning(format!("{err:#}")));
+    } else {
+        eprintln!(
+            "{} {err:#}",
+            "Warning:".if_supports_color(Stream::Stderr, |text| text.yellow())
+        );
+    }
+}
+
+/// Container id, shortened same way as docker CLI does.
+pub fn short_id(id: &str) -> &str {
+    &id[..id.len().min(12)]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::Path, process};
+
+    use bollard::API_DEFAULT_VERSION;
+    use serde_json::Value;
+
+    use super::*;
+    use crate::{
+        config::{NetworkConfig, ResolveOptions, plan},
+        events::EventTarget,
+        testing::MockServer,
+        validator::KeyStore,
+    };
+
+    #[tokio::test]
+    async fn events_of_two_node_start() {
+        let config: NetworkConfig = toml::from_str(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            name = "alpha"
+            client = "qlean"
+
+            [[node]]
+            name = "beta"
+            client = "qlean"
+            "#,
+        )
+        .unwrap();
+        let plan = plan(
+            config,
+            ResolveOptions::new(Path::new("network.toml"), KeyStore::Skip),
+        )
+        .unwrap();
+
+        let server = MockServer::start(vec![
+            (201, r#"{"Id":"aaaa","Warnings":[]}"#),
+            (204, ""),
+            (201, r#"{"Id":"bbbb","Warnings":[]}"#),
+            (204, ""),
+            (200, r#"{"Id":"aaaa","State":{"Running":true}}"#),
+        ])
+        .await;
+        let docker = Docker::connect_with_http(&server.url(""), 5, API_DEFAULT_VERSION).unwrap();
+
+        let path = env::temp_dir().join(format!("liesas-events-{}.jsonl", process::id()));
+        let mut events = EventSink::open(&EventTarget::Path(path.clone())).unwrap();
+
+        let mut launcher = Launcher::new(&docker, RetryPolicy::no_retries(), "devnet");
+        launcher.launch(&plan, &mut events).await.unwrap();
+        launcher
+            .wait_healthy(&plan, Duration::from_secs(5), &mut events)
+            .await
+            .unwrap();
+        drop(events);
+
+        let lines = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let events = lines
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        let kinds = events
+            .iter()
+            .map(|event| event["event"].as_str().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds,
+            [
+                "node_created",
+                "node_created",
+                "node_healthy",
+                "node_healthy"
+            ]
+        );
+        assert_eq!(events[0]["name"], "alpha");
+        assert_eq!(events[0]["id"], "aaaa");
+        assert_eq!(events[1]["name"], "beta");
+        assert_eq!(events[1]["id"], "bbbb");
+        assert!(events.iter().all(|event| event["time"].is_string()));
+    }
+
+    #[tokio::test]
+    async fn interrupted_launch_removes_started_and_pending_containers() {
+        let plan_of = |source: &str| {
+            plan(
+                toml::from_str::<NetworkConfig>(source).unwrap(),
+                ResolveOptions::new(Path::new("network.toml"), KeyStore::Skip),
+            )
+            .unwrap()
+        };
+        let started = plan_of(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            name = "alpha"
+            client = "ream"
+            "#,
+        );
+        let full = plan_of(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            name = "alpha"
+            client = "ream"
+
+            [[node]]
+            name = "beta"
+            client = "ream"
+            "#,
+        );
+
+        let server =
+            MockServer::start(vec![(201, r#"{"Id":"aaaa","Warnings":[]}"#), (204, "")]).await;
+        let docker = Docker::connect_with_http(&server.url(""), 5, API_DEFAULT_VERSION).unwrap();
+
+        // Launch is interrupted after `alpha` is started, while `beta` may
+        // already be created.
+        let mut launcher = Launcher::new(&docker, RetryPolicy::no_retries(), "devnet");
+        launcher
+            .launch(&started, &mut EventSink::disabled())
+            .await
+            .unwrap();
+        launcher.rollback_interrupted(&full).await;
+
+        let removed = (2..server.requests())
+            .map(|index| server.request(index).lines().next().unwrap().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(removed.len(), 4);
+        assert!(
+            removed[0].starts_with("post ") && removed[0].contains("/containers/devnet-beta/stop")
+        );
+        assert!(
+            removed[1].starts_with("delete ") && removed[1].contains("/containers/devnet-beta?")
+        );
+        assert!(removed[2].starts_with("post ") && removed[2].contains("/containers/aaaa/stop"));
+        assert!(removed[3].starts_with("delete ") && removed[3].contains("/containers/aaaa?"));
+        assert!(launcher.nodes().is_empty());
+    }
+}
@@ -0,0 +1,32 @@
+//! Library behind `liesas-quickstart` binary: parsing and resolving network
+//! configs, and launching them in docker.
+
+mod auth;
+pub mod client;
+mod codespan;
+pub mod commands;
+mod compose;
+pub mod config;
+mod container;
+pub mod docker;
+mod events;
+pub mod exit;
+mod genesis;
+mod health;
+mod hook;
+mod image;
+mod kurtosis;
+mod launch;
+pub mod output;
+mod peers;
+mod process;
+mod prometheus;
+mod prompt;
+mod registry;
+mod table;
+#[cfg(test)]
+mod testing;
+pub mod validator;
+mod watch;
+
+pub use commands::{Cli, Command};
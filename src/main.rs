@@ -1,20 +1,99 @@
-mod client;
-mod codespan;
-mod commands;
-mod config;
-mod validator;
+use std::error;
 
 use clap::Parser;
-use color_eyre::Result;
-pub use commands::{Cli, Command};
+use color_eyre::{
+    Result,
+    owo_colors::{OwoColorize, Stream},
+};
+use liesas_quickstart::{
+    Cli, Command,
+    docker::DockerError,
+    exit::Failure,
+    output::{self, OutputMode},
+};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Cli::parse();
+    let args = Cli::try_parse().unwrap_or_else(|err| {
+        if !err.use_stderr() {
+            // --help and --version are reported as errors too
+            err.exit();
+        }
 
-    match args.command() {
-        Command::Start(cmd) => cmd.run().await?,
+        if output::json_requested(&std::env::args().collect::<Vec<_>>()) {
+            output::init(OutputMode::Json);
+            let message = err.render().to_string();
+            let message = message.lines().next().unwrap_or_default();
+            output::failure(
+                Some(Failure::Usage),
+                message.trim_start_matches("error: ").to_owned(),
+                Vec::new(),
+            );
+        }
+
+        let _ = err.print();
+        Failure::Usage.exit();
+    });
+
+    output::init(args.output_mode());
+    output::init_color(args.color);
+    output::init_logging(args.verbose);
+
+    let result = match args.command() {
+        Command::Start(cmd) => cmd.run(&args.docker).await,
+        Command::Export(cmd) => cmd.run().await,
+        Command::Doctor(cmd) => cmd.run(&args.docker).await,
+        Command::Scale(cmd) => cmd.run(&args.docker).await,
+        Command::Logs(cmd) => cmd.run(&args.docker).await,
+        Command::Status(cmd) => cmd.run(&args.docker).await,
+        Command::Ps(cmd) => cmd.run(&args.docker).await,
+        Command::Restart(cmd) => cmd.run(&args.docker).await,
+        Command::Stop(cmd) => cmd.run(&args.docker).await,
+        Command::Clean(cmd) => cmd.run(&args.docker).await,
+        Command::Genesis(cmd) => cmd.run().await,
+        Command::Keys(cmd) => cmd.run().await,
+        Command::Validate(cmd) => cmd.run().await,
+        Command::Init(cmd) => cmd.run().await,
+        Command::Schema(cmd) => cmd.run().await,
+        Command::Compose(cmd) => cmd.run().await,
+        Command::Monitoring(cmd) => cmd.run().await,
+        Command::Completions(cmd) => cmd.run(),
     };
 
-    Ok(())
+    if let Err(err) = &result {
+        let class = Failure::of(err);
+
+        if output::is_json() {
+            output::failure(class, format!("{err:#}"), Vec::new());
+        }
+
+        // Environment problems get short message with guidance, instead of
+        // full report, which is only useful to debug liesas itself.
+        if let Some(err) = err.downcast_ref::<DockerError>()
+            && let Some(help) = err.help()
+        {
+            let cause = error::Error::source(err)
+                .map(|source| format!(" ({source})"))
+                .unwrap_or_default();
+
+            eprintln!(
+                "{} {err}{cause}",
+                "Error:".if_supports_color(Stream::Stderr, |text| text.red())
+            );
+            eprintln!(
+                "{} {help}",
+                "Help:".if_supports_color(Stream::Stderr, |text| text.cyan())
+            );
+            err.failure().exit();
+        }
+
+        if let Some(class) = class {
+            eprintln!("{err:?}");
+            class.exit();
+        }
+    } else if output::is_json() {
+        output::finish();
+    }
+
+    result
 }
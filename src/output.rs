@@ -0,0 +1,325 @@
+use std::{
+    env,
+    io::{self, IsTerminal},
+    ops::Range,
+    path::Path,
+    sync::{Mutex, OnceLock},
+};
+
+use clap::ValueEnum;
+use color_eyre::owo_colors;
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+use tracing_subscriber::{EnvFilter, filter::LevelFilter};
+
+use crate::exit::Failure;
+
+/// Target of liesas' own logs, which verbosity applies to. Logs of
+/// dependencies are only shown from `warn` up, unless `RUST_LOG` says
+/// otherwise.
+const LOG_TARGET: &str = env!("CARGO_CRATE_NAME");
+
+/// How user-facing output is presented.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputMode {
+    /// Human-readable text, diagnostics and progress.
+    #[default]
+    Human,
+
+    /// Single JSON document on stdout, describing the run or the error, and
+    /// nothing else.
+    Json,
+}
+
+/// When to color diagnostics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color output, which goes to a terminal, unless `NO_COLOR` is set.
+    #[default]
+    Auto,
+
+    Always,
+
+    Never,
+}
+
+static MODE: OnceLock<OutputMode> = OnceLock::new();
+
+static COLOR: OnceLock<ColorChoice> = OnceLock::new();
+
+/// Warnings, collected until the final document is written.
+static WARNINGS: Mutex<Vec<Diagnostic>> = Mutex::new(Vec::new());
+
+/// Result of successful run, written by [`finish`] once command exits, so
+/// that failure afterwards, e.g. while network is watched, is the only
+/// document.
+static RESULT: Mutex<Option<Value>> = Mutex::new(None);
+
+pub fn init(mode: OutputMode) {
+    let _ = MODE.set(mode);
+}
+
+pub fn is_json() -> bool {
+    MODE.get() == Some(&OutputMode::Json)
+}
+
+pub fn init_color(choice: ColorChoice) {
+    let _ = COLOR.set(choice);
+
+    // With `auto`, `if_supports_color` checks the stream and `NO_COLOR` by
+    // itself.
+    match choice {
+        ColorChoice::Auto => {}
+        ColorChoice::Always => owo_colors::set_override(true),
+        ColorChoice::Never => owo_colors::set_override(false),
+    }
+}
+
+/// Install logger, writing to stderr, so that stdout stays clean for json
+/// output.
+///
+/// Each `--verbose` raises level of liesas logs from `warn`, through `info`
+/// and `debug`, to `trace`. Without the flag, `RUST_LOG` is used when set.
+pub fn init_logging(verbose: u8) {
+    let level = match verbose {
+        0 => None,
+        1 => Some(LevelFilter::INFO),
+        2 => Some(LevelFilter::DEBUG),
+        _ => Some(LevelFilter::TRACE),
+    };
+
+    let filter = match level {
+        Some(level) => EnvFilter::new(format!("warn,{LOG_TARGET}={level}")),
+        None => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+    };
+
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(io::stderr)
+        .with_ansi(supports_color(&io::stderr()))
+        .without_time()
+        .init();
+}
+
+/// Whether output to `stream` should be colored, as set by `--color`.
+pub fn supports_color(stream: &impl IsTerminal) -> bool {
+    color_enabled(COLOR.get().copied().unwrap_or_default(), stream)
+}
+
+/// Whether output to `stream` should be colored with given `choice`.
+fn color_enabled(choice: ColorChoice, stream: &impl IsTerminal) -> bool {
+    match choice {
+        ColorChoice::Auto => {
+            env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && stream.is_terminal()
+        }
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+    }
+}
+
+/// Config for ariadne reports, which are all printed to stderr.
+pub fn report_config() -> ariadne::Config {
+    ariadne::Config::default().with_color(supports_color(&io::stderr()))
+}
+
+/// Whether command line asks for json output.
+///
+/// Used to report argument errors, which happen before mode is known.
+pub fn json_requested(args: &[String]) -> bool {
+    args.iter()
+        .any(|arg| arg == "--output=json" || arg == "--json")
+        || args
+            .windows(2)
+            .any(|v| v[0] == "--output" && v[1] == "json")
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Location {
+    file: String,
+    line: usize,
+    column: usize,
+    start: usize,
+    end: usize,
+}
+
+/// Machine-readable counterpart of ariadne report.
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    severity: &'static str,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    location: Option<Location>,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            severity: "error",
+            message: message.into(),
+            location: None,
+        }
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self {
+            severity: "warning",
+            message: message.into(),
+            location: None,
+        }
+    }
+
+    /// Point diagnostic at `span` of `source`, loaded from `file`.
+    pub fn at(mut self, file: &Path, source: &str, span: Option<Range<usize>>) -> Self {
+        self.location = span.map(|span| {
+            let before = &source[..span.start.min(source.len())];
+            let line_start = before.rfind('\n').map_or(0, |v| v + 1);
+
+            Location {
+                file: file.display().to_string(),
+                line: before.matches('\n').count() + 1,
+                column: before[line_start..].chars().count() + 1,
+                start: span.start,
+                end: span.end,
+            }
+        });
+
+        self
+    }
+}
+
+/// Record warning, to be included into the final document.
+pub fn warn(diagnostic: Diagnostic) {
+    WARNINGS.lock().unwrap().push(diagnostic);
+}
+
+/// The only document, printed to stdout in json mode.
+fn document(status: &str, fields: Map<String, Value>) -> Value {
+    let mut document = Map::new();
+    document.insert("status".to_owned(), json!(status));
+    document.extend(fields);
+    document.insert("warnings".to_owned(), json!(*WARNINGS.lock().unwrap()));
+
+    Value::Object(document)
+}
+
+fn success_document(result: Value) -> Value {
+    let fields = match result {
+        Value::Object(fields) => fields,
+        value => Map::from_iter([("result".to_owned(), value)]),
+    };
+
+    document("ok", fields)
+}
+
+fn failure_document(
+    class: Option<Failure>,
+    message: String,
+    diagnostics: Vec<Diagnostic>,
+) -> Value {
+    let exit_code = class.map_or(1, |class| i32::from(class.exit_code()));
+
+    let Value::Object(fields) = json!({
+        "class": class,
+        "exit_code": exit_code,
+        "message": message,
+        "diagnostics": diagnostics,
+    }) else {
+        unreachable!("json object literal is always an object");
+    };
+
+    document("error", fields)
+}
+
+/// Record result of successful run, written by [`finish`].
+pub fn success(result: Value) {
+    *RESULT.lock().unwrap() = Some(result);
+}
+
+/// Write document, describing successful run, if command has recorded its
+/// result.
+pub fn finish() {
+    if let Some(result) = RESULT.lock().unwrap().take() {
+        println!("{}", success_document(result));
+    }
+}
+
+/// Write document, describing failed run, instead of recorded result, and
+/// exit.
+///
+/// Failures without class exit with code 1.
+pub fn failure(class: Option<Failure>, message: String, diagnostics: Vec<Diagnostic>) -> ! {
+    let exit_code = class.map_or(1, |class| i32::from(class.exit_code()));
+
+    println!("{}", failure_document(class, message, diagnostics));
+    std::process::exit(exit_code);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    /// Parse `output` as stream of JSON documents, expecting exactly one.
+    fn single_document(output: &str) -> Value {
+        let mut documents = serde_json::Deserializer::from_str(output).into_iter::<Value>();
+        let document = documents.next().unwrap().unwrap();
+        assert!(documents.next().is_none(), "expected a single document");
+        assert_eq!(output.lines().count(), 1);
+
+        document
+    }
+
+    #[test]
+    fn failure_is_single_document() {
+        let source = "name = \"devnet\"\ncount = 0\n";
+        let diagnostic = Diagnostic::error("cannot equal to zero").at(
+            Path::new("network.toml"),
+            source,
+            Some(24..25),
+        );
+
+        let output = failure_document(
+            Some(Failure::ConfigSemantic),
+            "Invalid node configuration".to_owned(),
+            vec![diagnostic],
+        )
+        .to_string();
+        let document = single_document(&output);
+
+        assert_eq!(document["status"], "error");
+        assert_eq!(document["class"], "config_semantic");
+        assert_eq!(document["exit_code"], 78);
+        assert_eq!(document["diagnostics"][0]["location"]["line"], 2);
+        assert_eq!(document["diagnostics"][0]["location"]["column"], 9);
+    }
+
+    /// Ariadne report about zero count, rendered with `choice` of color.
+    fn render_report(choice: ColorChoice) -> String {
+        let source = "name = \"devnet\"\ncount = 0\n";
+        let mut output = Vec::new();
+
+        ariadne::Report::build(ariadne::ReportKind::Error, ("network.toml", 24..25))
+            .with_config(
+                ariadne::Config::default().with_color(color_enabled(choice, &io::stderr())),
+            )
+            .with_message("Invalid node configuration")
+            .with_label(
+                ariadne::Label::new(("network.toml", 24..25)).with_message("cannot equal to zero"),
+            )
+            .finish()
+            .write(("network.toml", ariadne::Source::from(source)), &mut output)
+            .unwrap();
+
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn never_color_has_no_escape_sequences() {
+        let report = render_report(ColorChoice::Never);
+        assert!(report.contains("cannot equal to zero"));
+        assert!(!report.contains('\x1b'), "{report:?}");
+
+        assert!(render_report(ColorChoice::Always).contains('\x1b'));
+    }
+}
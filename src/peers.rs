@@ -0,0 +1,199 @@
+use std::{fmt, net::SocketAddr, time::Duration};
+
+use serde::Deserialize;
+use serde_with::{DisplayFromStr, serde_as};
+use tokio::{
+    task::JoinSet,
+    time::{Instant, sleep},
+};
+use tracing::trace;
+
+use crate::config::ResolvedNetworkConfig;
+
+/// Delay between consecutive peer-count queries to the same node.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+struct PeerCount {
+    #[serde_as(as = "DisplayFromStr")]
+    connected: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct PeerCountResponse {
+    data: PeerCount,
+}
+
+/// Node, which should be checked for connectivity.
+#[derive(Debug, Clone)]
+pub struct PeerTarget {
+    pub node: String,
+    pub url: String,
+
+    /// Peer count, node should reach, see
+    /// [`ResolvedNetworkConfig::expected_peers`].
+    pub expected: u64,
+}
+
+impl PeerTarget {
+    /// Target of `node`, querying its peer count on host rpc port, see
+    /// [`Client::peer_count_path`](crate::client::Client::peer_count_path).
+    ///
+    /// Returns `None` for clients, which don't expose peer count.
+    pub fn new(network: &ResolvedNetworkConfig, node: &str) -> Option<Self> {
+        let resolved = &network.nodes()[node];
+        let path = resolved.client.kind().instantiate().peer_count_path()?;
+
+        Some(Self {
+            node: node.to_owned(),
+            url: format!(
+                "http://{}{path}",
+                SocketAddr::new(network.host_address(node), resolved.ports.rpc)
+            ),
+            expected: network.expected_peers(node),
+        })
+    }
+}
+
+/// Node, which didn't reach expected peer count in time.
+#[derive(Debug, Clone)]
+pub struct UnderConnected {
+    pub node: String,
+
+    /// Last reported peer count, or `None` if node never responded.
+    pub peers: Option<u64>,
+
+    pub expected: u64,
+}
+
+impl fmt::Display for UnderConnected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.peers {
+            Some(peers) => write!(
+                f,
+                "`{}` has {peers} of {} peer(s)",
+                self.node, self.expected
+            ),
+            None => write!(f, "`{}` didn't report its peers", self.node),
+        }
+    }
+}
+
+async fn query(http: &reqwest::Client, url: &str) -> Option<u64> {
+    let response = http.get(url).send().await.ok()?.error_for_status().ok()?;
+    let body: PeerCountResponse = response.json().await.ok()?;
+
+    Some(body.data.connected)
+}
+
+/// Poll every target until it reports at least expected number of peers, or
+/// `timeout` elapses.
+///
+/// Returns nodes, which didn't reach expected peer count in time.
+pub async fn verify_peers(targets: &[PeerTarget], timeout: Duration) -> Vec<UnderConnected> {
+    let deadline = Instant::now() + timeout;
+    let http = reqwest::Client::builder()
+        .timeout(POLL_INTERVAL)
+        .build()
+        .expect("http client without tls should always build");
+
+    let mut tasks = JoinSet::new();
+    for (index, target) in targets.iter().cloned().enumerate() {
+        let http = http.clone();
+
+        tasks.spawn(async move {
+            let mut peers = None;
+
+            loop {
+                if let Some(count) = query(&http, &target.url).await {
+                    trace!("{} has {count} peer(s)", target.node);
+                    if count >= target.expected {
+                        return (index, None);
+                    }
+
+                    peers = Some(count);
+                }
+
+                if Instant::now() >= deadline {
+                    return (
+                        index,
+                        Some(UnderConnected {
+                            node: target.node,
+                            peers,
+                            expected: target.expected,
+                        }),
+                    );
+                }
+
+                sleep(POLL_INTERVAL).await;
+            }
+        });
+    }
+
+    let mut failed = tasks
+        .join_all()
+        .await
+        .into_iter()
+        .filter_map(|(index, result)| result.map(|v| (index, v)))
+        .collect::<Vec<_>>();
+    failed.sort_by_key(|(index, _)| *index);
+
+    failed.into_iter().map(|(_, v)| v).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{MockServer, resolve};
+
+    fn target(node: &str, server: &MockServer) -> PeerTarget {
+        PeerTarget {
+            node: node.to_owned(),
+            url: server.url("/eth/v1/node/peer_count"),
+            expected: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn under_connected_node_is_flagged() {
+        let connected = MockServer::start(vec![(200, r#"{"data":{"connected":"1"}}"#)]).await;
+        let isolated = MockServer::start(vec![(200, r#"{"data":{"connected":"0"}}"#)]).await;
+
+        let targets = [target("alpha", &connected), target("beta", &isolated)];
+        let failed = verify_peers(&targets, Duration::from_millis(100)).await;
+
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].node, "beta");
+        assert_eq!(failed[0].peers, Some(0));
+        assert_eq!(failed[0].to_string(), "`beta` has 0 of 1 peer(s)");
+    }
+
+    #[test]
+    fn bootnodes_expect_every_node() {
+        let network = resolve(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            name = "boot"
+            client = "ream"
+            bootnode = true
+
+            [[node]]
+            name = "alpha"
+            client = "ream"
+
+            [[node]]
+            name = "beta"
+            client = "ream"
+            "#,
+        )
+        .unwrap();
+
+        let target = PeerTarget::new(&network, "boot").unwrap();
+        assert_eq!(target.expected, 2);
+        assert!(target.url.ends_with("/eth/v1/node/peer_count"));
+        assert_eq!(PeerTarget::new(&network, "alpha").unwrap().expected, 1);
+    }
+}
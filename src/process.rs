@@ -0,0 +1,425 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::{
+    client::NodeContext,
+    config::{ClientSource, ResolvedNetworkConfig, ResolvedNodeConfig},
+    container::shell_quote,
+    validator::JWT_FILE,
+};
+
+/// Environment variable, pointing local node at its validator keys.
+pub const KEYS_DIR_ENV: &str = "LIESAS_KEYS_DIR";
+
+/// Directory with run directories of all networks.
+fn runs_dir() -> PathBuf {
+    env::temp_dir().join("liesas-run")
+}
+
+/// Directory with records and logs of local processes of `network`.
+///
+/// It is kept outside of the key directory, so that records survive
+/// regeneration of keys, and `stop` can find processes of any run.
+pub fn run_dir(network: &str) -> PathBuf {
+    runs_dir().join(network)
+}
+
+/// Everything, that is needed to run node as a local process.
+#[derive(Debug, Clone)]
+pub struct ProcessSpec {
+    pub name: String,
+    pub bin: PathBuf,
+    pub env: BTreeMap<String, String>,
+    pub working_dir: Option<PathBuf>,
+
+    /// Grace period between `SIGTERM` and `SIGKILL`, when node is stopped.
+    pub stop_timeout: Duration,
+
+    pub args: Vec<String>,
+}
+
+/// Record of spawned process, written to `<run dir>/<node>.json`.
+#[derive(Debug, Serialize, Deserialize)]
+struct ProcessRecord {
+    pid: u32,
+
+    /// Start time of the process, see [`start_time`]. Process with recorded
+    /// pid, but other start time, is not the one, which was spawned.
+    #[serde(default)]
+    start_time: Option<u64>,
+
+    /// Stop timeout of the node, in milliseconds.
+    stop_timeout: u64,
+}
+
+impl ProcessRecord {
+    /// Whether pid of exited process is since reused by unrelated one, which
+    /// must not be signalled.
+    fn is_reused(&self) -> bool {
+        self.start_time.is_some() && start_time(self.pid) != self.start_time
+    }
+}
+
+impl ProcessSpec {
+    /// Spec of the local process, running node `name`.
+    ///
+    /// Returns `None` unless node runs local binary.
+    pub fn new(
+        network: &ResolvedNetworkConfig,
+        name: &str,
+        node: &ResolvedNodeConfig,
+    ) -> Option<Self> {
+        let ClientSource::Binary { kind, bin, .. } = &node.client else {
+            return None;
+        };
+
+        let keys_dir = network
+            .key_store()
+            .node_dir(name)
+            .map(|dir| dir.display().to_string());
+
+        let jwt_path = network
+            .key_store()
+            .node_dir(name)
+            .filter(|_| node.jwt.is_some())
+            .map(|dir| dir.join(JWT_FILE).display().to_string());
+
+        let mut env = node.env.clone();
+        if let Some(dir) = &keys_dir {
+            env.insert(KEYS_DIR_ENV.to_owned(), dir.clone());
+        }
+
+        // Local nodes share the filesystem, so each needs its own data
+        // directory.
+        let data_dir = run_dir(network.name()).join(name).display().to_string();
+
+        // There is no port mapping for local processes, so client must be
+        // told to listen on host ports directly.
+        let peers = network.peers(name);
+        debug!(
+            "{name} connects to {} peer(s): {}",
+            peers.len(),
+            peers.join(", ")
+        );
+        let context = NodeContext {
+            name,
+            data_dir: Some(&data_dir),
+            keys_dir: keys_dir.as_deref(),
+            ports: node.ports,
+            peers: &peers,
+            checkpoint_sync_url: node.checkpoint_sync_url.as_deref(),
+            log_level: node.log_level,
+            jwt_path: jwt_path.as_deref(),
+        };
+
+        let client = kind.instantiate();
+        let mut args = client.timing_args(&network.timing());
+        args.extend(client.build_args(&context));
+        args.extend(node.extra_args.iter().cloned());
+
+        Some(Self {
+            name: name.to_owned(),
+            bin: bin.clone(),
+            env,
+            working_dir: node.working_dir.clone(),
+            stop_timeout: node.stop_timeout,
+            args,
+        })
+    }
+
+    /// Equivalent shell command, for `--print-command`.
+    pub fn to_command_line(&self) -> String {
+        let mut command = String::new();
+
+        if let Some(dir) = &self.working_dir {
+            command.push_str(&format!(
+                "cd {} && ",
+                shell_quote(&dir.display().to_string())
+            ));
+        }
+        for (key, value) in &self.env {
+            command.push_str(&format!("{key}={} ", shell_quote(value)));
+        }
+        command.push_str(&shell_quote(&self.bin.display().to_string()));
+        for arg in &self.args {
+            command.push(' ');
+            command.push_str(&shell_quote(arg));
+        }
+
+        command
+    }
+
+    /// Spawn the process in background, with output written to
+    /// `<run dir>/<node>.log`, and record its pid, so that it can be
+    /// terminated later, even by another invocation.
+    ///
+    /// Returns pid of the spawned process.
+    pub fn spawn(&self, run_dir: &Path) -> io::Result<u32> {
+        fs::create_dir_all(run_dir)?;
+
+        let log = File::create(run_dir.join(format!("{}.log", self.name)))?;
+
+        let mut command = tokio::process::Command::new(&self.bin);
+        command
+            .args(&self.args)
+            .envs(&self.env)
+            .stdin(Stdio::null())
+            .stdout(log.try_clone()?)
+            .stderr(log);
+        if let Some(dir) = &self.working_dir {
+            command.current_dir(dir);
+        }
+        // Own process group keeps node out of reach of Ctrl-C in the
+        // terminal, so that detached nodes survive it.
+        #[cfg(unix)]
+        command.process_group(0);
+
+        let child = command.spawn()?;
+        let pid = child
+            .id()
+            .ok_or_else(|| io::Error::other("process exited immediately"))?;
+
+        let record = ProcessRecord {
+            pid,
+            start_time: start_time(pid),
+            stop_timeout: self.stop_timeout.as_millis() as u64,
+        };
+        fs::write(
+            record_path(run_dir, &self.name),
+            serde_json::to_vec(&record)?,
+        )?;
+
+        Ok(pid)
+    }
+}
+
+/// Start time of process `pid`, in clock ticks since boot, which tells it
+/// apart from later process, reusing the same pid.
+///
+/// Returns `None` if there is no such process, or `/proc` is not available.
+fn start_time(pid: u32) -> Option<u64> {
+    let stat = fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // Command name, in parentheses, may contain spaces, so fields are counted
+    // from its end. Start time is 22nd field, and state after the name is 3rd.
+    let (_, fields) = stat.rsplit_once(')')?;
+    fields.split_whitespace().nth(19)?.parse().ok()
+}
+
+fn record_path(run_dir: &Path, node: &str) -> PathBuf {
+    run_dir.join(format!("{node}.json"))
+}
+
+/// Nodes of all networks, which have recorded local process, as
+/// `(network, node)` pairs.
+pub fn tracked() -> io::Result<Vec<(String, String)>> {
+    let networks = match fs::read_dir(runs_dir()) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+
+    let mut nodes = Vec::new();
+    for network in networks {
+        let network = network?;
+        for entry in fs::read_dir(network.path())? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json")
+                && let Some(node) = path.file_stem()
+            {
+                nodes.push((
+                    network.file_name().to_string_lossy().into_owned(),
+                    node.to_string_lossy().into_owned(),
+                ));
+            }
+        }
+    }
+
+    Ok(nodes)
+}
+
+/// Terminate local process of `node`, together with its process group: send
+/// `SIGTERM`, and `SIGKILL` if it doesn't exit within its stop timeout.
+///
+/// Returns `false` if node has no recorded process, or it has already exited.
+pub async fn terminate(network: &str, node: &str) -> io::Result<bool> {
+    let path = record_path(&run_dir(network), node);
+    let Some(record) = read_record(&path)? else {
+        return Ok(false);
+    };
+
+    let terminated = !record.is_reused()
+        && signal::terminate(record.pid, Duration::from_millis(record.stop_timeout)).await?;
+    fs::remove_file(&path)?;
+
+    Ok(terminated)
+}
+
+/// Whether recorded local process of `node` is still running.
+pub fn is_running(network: &str, node: &str) -> io::Result<bool> {
+    let record = read_record(&record_path(&run_dir(network), node))?;
+
+    Ok(record.is_some_and(|record| !record.is_reused() && signal::alive(record.pid)))
+}
+
+/// Networks, which have run directory, see [`run_dir`].
+pub fn run_networks() -> io::Result<Vec<String>> {
+    match fs::read_dir(runs_dir()) {
+        Ok(entries) => entries
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+            .collect(),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err),
+    }
+}
+
+fn read_record(path: &Path) -> io::Result<Option<ProcessRecord>> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(unix)]
+mod signal {
+    use std::{io, time::Duration};
+
+    use nix::{
+        errno::Errno,
+        sys::{
+            signal::{Signal, killpg},
+            wait::{WaitPidFlag, waitpid},
+        },
+        unistd::Pid,
+    };
+
+    /// How often to check, whether terminated process has exited.
+    const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+    /// Whether any process of group `pgid` is still running.
+    ///
+    /// Leader of the group, spawned by this invocation, is reaped here,
+    /// otherwise it would stay around as zombie, and look alive.
+    fn group_alive(pgid: Pid) -> bool {
+        let _ = waitpid(pgid, Some(WaitPidFlag::WNOHANG));
+
+        killpg(pgid, None).is_ok()
+    }
+
+    /// Whether any process of group, spawned as `pid`, is still running.
+    pub fn alive(pid: u32) -> bool {
+        group_alive(Pid::from_raw(pid as i32))
+    }
+
+    /// Spawned processes lead their own process group, see
+    /// [`ProcessSpec::spawn`](super::ProcessSpec::spawn), so `pid` is also id
+    /// of the group, and signalling it reaches children of the node too.
+    pub async fn terminate(pid: u32, timeout: Duration) -> io::Result<bool> {
+        let pgid = Pid::from_raw(pid as i32);
+
+        if !group_alive(pgid) {
+            return Ok(false);
+        }
+
+        match killpg(pgid, Signal::SIGTERM) {
+            Ok(()) => {}
+            Err(Errno::ESRCH) => return Ok(false),
+            Err(err) => return Err(err.into()),
+        }
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        while group_alive(pgid) {
+            if tokio::time::Instant::now() >= deadline {
+                match killpg(pgid, Signal::SIGKILL) {
+                    Ok(()) | Err(Errno::ESRCH) => break,
+                    Err(err) => return Err(err.into()),
+                }
+            }
+
+            tokio::time::sleep(EXIT_POLL_INTERVAL).await;
+        }
+
+        Ok(true)
+    }
+}
+
+#[cfg(not(unix))]
+mod signal {
+    use std::{io, time::Duration};
+
+    pub fn alive(_pid: u32) -> bool {
+        false
+    }
+
+    pub async fn terminate(_pid: u32, _timeout: Duration) -> io::Result<bool> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "local processes can be stopped only on unix",
+        ))
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn reused_pid_is_not_signalled() {
+        let network = format!("liesas-test-{}", std::process::id());
+        let spec = ProcessSpec {
+            name: "alpha".to_owned(),
+            bin: PathBuf::from("sleep"),
+            env: BTreeMap::new(),
+            working_dir: None,
+            stop_timeout: Duration::from_secs(1),
+            args: vec!["30".to_owned()],
+        };
+        let pid = spec.spawn(&run_dir(&network)).unwrap();
+        let path = record_path(&run_dir(&network), "alpha");
+        let recorded = fs::read(&path).unwrap();
+
+        // Same pid, but started at other time, belongs to other process.
+        let mut record = serde_json::from_slice::<ProcessRecord>(&recorded).unwrap();
+        assert_eq!(record.start_time, start_time(pid));
+        record.start_time = record.start_time.map(|time| time + 1);
+        fs::write(&path, serde_json::to_vec(&record).unwrap()).unwrap();
+
+        assert!(!terminate(&network, "alpha").await.unwrap());
+        assert!(start_time(pid).is_some());
+
+        fs::write(&path, recorded).unwrap();
+        assert!(terminate(&network, "alpha").await.unwrap());
+        fs::remove_dir_all(run_dir(&network)).unwrap();
+    }
+
+    #[tokio::test]
+    async fn recorded_process_is_running_until_terminated() {
+        let network = format!("liesas-test-running-{}", std::process::id());
+        let spec = ProcessSpec {
+            name: "alpha".to_owned(),
+            bin: PathBuf::from("sleep"),
+            env: BTreeMap::new(),
+            working_dir: None,
+            stop_timeout: Duration::from_secs(1),
+            args: vec!["30".to_owned()],
+        };
+        spec.spawn(&run_dir(&network)).unwrap();
+
+        assert!(run_networks().unwrap().contains(&network));
+        assert!(is_running(&network, "alpha").unwrap());
+        assert!(terminate(&network, "alpha").await.unwrap());
+        assert!(!is_running(&network, "alpha").unwrap());
+
+        fs::remove_dir_all(run_dir(&network)).unwrap();
+    }
+}
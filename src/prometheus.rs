@@ -0,0 +1,155 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use indexmap::IndexMap;
+use serde::Serialize;
+
+use crate::config::ResolvedNetworkConfig;
+
+/// Metrics endpoint of a single node, labeled so that series of different
+/// nodes can be told apart.
+#[derive(Debug, Clone, Serialize)]
+struct StaticConfig {
+    targets: Vec<String>,
+    labels: BTreeMap<&'static str, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ScrapeJob {
+    job_name: String,
+    metrics_path: &'static str,
+    scrape_interval: String,
+    static_configs: Vec<StaticConfig>,
+}
+
+/// Prometheus `prometheus.yml`, scraping metrics of every node of the
+/// network from the host.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScrapeConfig {
+    scrape_configs: Vec<ScrapeJob>,
+}
+
+impl ScrapeConfig {
+    pub const FILE_NAME: &str = "prometheus.yml";
+
+    /// Metrics path is set per job, so nodes are grouped into one job per
+    /// client. Nodes of clients, which don't expose metrics, are left out.
+    pub fn new(network: &ResolvedNetworkConfig) -> Self {
+        let mut jobs = IndexMap::<String, ScrapeJob>::new();
+
+        for (name, node) in network.nodes() {
+            let kind = node.client.kind();
+            let Some(metrics_path) = kind.instantiate().metrics_path() else {
+                continue;
+            };
+
+            let target = StaticConfig {
+                targets: vec![format!(
+                    "{}:{}",
+                    network.host_address(name),
+                    node.ports.metrics
+                )],
+                labels: BTreeMap::from([
+                    ("network", network.name().to_owned()),
+                    ("node", name.clone()),
+                    ("client", kind.to_string()),
+                ]),
+            };
+
+            jobs.entry(kind.to_string())
+                .or_insert_with(|| ScrapeJob {
+                    job_name: format!("{}-{kind}", network.name()),
+                    metrics_path,
+                    // Most metrics change once per slot.
+                    scrape_interval: format!("{}s", network.timing().seconds_per_slot),
+                    static_configs: Vec::new(),
+                })
+                .static_configs
+                .push(target);
+        }
+
+        Self {
+            scrape_configs: jobs.into_values().collect(),
+        }
+    }
+
+    /// Number of scraped nodes.
+    pub fn target_count(&self) -> usize {
+        self.scrape_configs
+            .iter()
+            .map(|job| job.static_configs.len())
+            .sum()
+    }
+
+    pub fn to_yaml(&self) -> Result<String, serde_yaml::Error> {
+        serde_yaml::to_string(self)
+    }
+
+    /// Write config into `dir`, returning path of the written file.
+    pub fn write(&self, dir: &Path) -> io::Result<PathBuf> {
+        let path = dir.join(Self::FILE_NAME);
+        fs::write(&path, self.to_yaml().map_err(io::Error::other)?)?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::resolve;
+
+    #[test]
+    fn every_node_with_metrics_is_scraped_once() {
+        let network = resolve(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            name = "ream"
+            client = "ream"
+            count = 2
+
+            [[node]]
+            client = "zeam"
+            "#,
+        )
+        .unwrap();
+
+        let config = ScrapeConfig::new(&network);
+        assert_eq!(config.target_count(), 2);
+
+        let [job] = &config.scrape_configs[..] else {
+            panic!("expected single job, got {:?}", config.scrape_configs);
+        };
+        assert_eq!(job.job_name, "devnet-ream");
+        assert_eq!(job.metrics_path, "/metrics");
+
+        let targets = job
+            .static_configs
+            .iter()
+            .map(|target| (target.targets.clone(), target.labels["node"].as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            targets,
+            [
+                (
+                    vec![format!(
+                        "127.0.0.1:{}",
+                        network.nodes()["ream_0"].ports.metrics
+                    )],
+                    "ream_0"
+                ),
+                (
+                    vec![format!(
+                        "127.0.0.1:{}",
+                        network.nodes()["ream_1"].ports.metrics
+                    )],
+                    "ream_1"
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use clap::Args;
+use color_eyre::owo_colors::{OwoColorize, Stream};
+use thiserror::Error;
+
+/// Flags, shared by commands which remove containers, networks or keys.
+#[derive(Debug, Clone, Args)]
+pub struct ConfirmArgs {
+    /// Don't ask for confirmation before removing anything.
+    #[arg(short = 'y', long)]
+    yes: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum ConfirmError {
+    #[error("refusing to {action} without confirmation, pass `--yes` to proceed")]
+    NotInteractive { action: String },
+
+    #[error("failed to read confirmation")]
+    Io(#[from] io::Error),
+}
+
+impl ConfirmArgs {
+    /// Ask user whether to proceed with `action`.
+    ///
+    /// Prompt is shown only when stdin is a TTY. Non-interactive invocations
+    /// must pass `--yes`, otherwise this fails instead of waiting for input,
+    /// that will never come.
+    pub fn confirm(&self, action: &str) -> Result<bool, ConfirmError> {
+        let stdin = io::stdin();
+        self.ask(action, stdin.is_terminal(), &mut stdin.lock())
+    }
+
+    fn ask(
+        &self,
+        action: &str,
+        interactive: bool,
+        input: &mut impl BufRead,
+    ) -> Result<bool, ConfirmError> {
+        if self.yes {
+            return Ok(true);
+        }
+
+        if !interactive {
+            return Err(ConfirmError::NotInteractive {
+                action: action.to_owned(),
+            });
+        }
+
+        eprint!(
+            "{} {action}? (y/N) ",
+            "?".if_supports_color(Stream::Stderr, |text| text.yellow())
+        );
+        io::stderr().flush()?;
+
+        let mut answer = String::new();
+        input.read_line(&mut answer)?;
+
+        Ok(matches!(answer.trim(), "y" | "Y" | "yes" | "Yes"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn yes_bypasses_prompt() {
+        let args = ConfirmArgs { yes: true };
+        assert!(args.ask("remove keys", false, &mut io::empty()).unwrap());
+    }
+
+    #[test]
+    fn non_interactive_without_yes_fails() {
+        let args = ConfirmArgs { yes: false };
+        assert!(matches!(
+            args.ask("remove keys", false, &mut io::empty()),
+            Err(ConfirmError::NotInteractive { action }) if action == "remove keys"
+        ));
+    }
+
+    #[test]
+    fn only_yes_answer_confirms() {
+        let args = ConfirmArgs { yes: false };
+        assert!(
+            args.ask("remove keys", true, &mut "y\n".as_bytes())
+                .unwrap()
+        );
+        assert!(!args.ask("remove keys", true, &mut "\n".as_bytes()).unwrap());
+    }
+}
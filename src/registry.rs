@@ -0,0 +1,109 @@
+use leansig::serialization::Serializable;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{config::ResolvedNetworkConfig, validator::PublicKey};
+
+#[derive(Debug, Error)]
+pub enum RegistryError {
+    #[error("validator {index} public key is not valid hex")]
+    InvalidHex { index: usize },
+
+    #[error("validator {index} public key cannot be decoded")]
+    InvalidKey { index: usize },
+}
+
+/// Validator registry, in the format lean clients expect to find in genesis
+/// config.
+///
+/// Public keys are hex-encoded with `0x` prefix, and ordered by global
+/// validator index.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidatorRegistry {
+    #[serde(rename = "GENESIS_VALIDATORS")]
+    validators: Vec<String>,
+}
+
+impl ValidatorRegistry {
+    pub fn new(network: &ResolvedNetworkConfig) -> std::io::Result<Self> {
+        Ok(Self {
+            validators: network
+                .public_keys()?
+                .into_iter()
+                .map(|key| format!("0x{}", hex::encode(key)))
+                .collect(),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.validators.len()
+    }
+
+    /// Decode registry back into public keys, to make sure it is readable by
+    /// clients.
+    pub fn public_keys(&self) -> Result<Vec<PublicKey>, RegistryError> {
+        self.validators
+            .iter()
+            .enumerate()
+            .map(|(index, key)| {
+                let bytes = hex::decode(key.trim_start_matches("0x"))
+                    .map_err(|_| RegistryError::InvalidHex { index })?;
+
+                PublicKey::from_bytes(&bytes).map_err(|_| RegistryError::InvalidKey { index })
+            })
+            .collect()
+    }
+
+    pub fn to_yaml(&self) -> Result<String, serde_yaml::Error> {
+        serde_yaml::to_string(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::{
+        config::{NetworkConfig, ResolveOptions},
+        validator::KeyStore,
+    };
+
+    #[test]
+    fn registry_lists_every_validator_in_index_order() {
+        let config: NetworkConfig = toml::from_str(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            name = "alpha"
+            client = "ream"
+            validator_count = 2
+            num_active_epochs = 4
+
+            [[node]]
+            name = "beta"
+            client = "ream"
+            validator_count = 1
+            num_active_epochs = 4
+            "#,
+        )
+        .unwrap();
+        let network = config
+            .resolve(ResolveOptions::new(
+                Path::new("network.toml"),
+                KeyStore::Memory,
+            ))
+            .unwrap();
+
+        let registry = ValidatorRegistry::new(&network).unwrap();
+        assert_eq!(registry.len(), 3);
+
+        for (entry, validator) in registry.validators.iter().zip(network.validators()) {
+            let key = validator.keys.public_key().unwrap();
+            assert_eq!(*entry, format!("0x{}", hex::encode(key)));
+        }
+
+        assert_eq!(registry.public_keys().unwrap().len(), 3);
+    }
+}
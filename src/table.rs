@@ -0,0 +1,142 @@
+use std::{
+    fmt::{self, Write as _},
+    io,
+};
+
+use color_eyre::owo_colors::{AnsiColors, OwoColorize};
+use serde_json::{Map, Value};
+
+use crate::output;
+
+/// Table cell, optionally colored when output supports it.
+#[derive(Debug, Clone)]
+pub struct Cell {
+    text: String,
+    color: Option<AnsiColors>,
+}
+
+impl Cell {
+    pub fn new(text: impl fmt::Display) -> Self {
+        Self {
+            text: text.to_string(),
+            color: None,
+        }
+    }
+
+    pub fn colored(text: impl fmt::Display, color: AnsiColors) -> Self {
+        Self {
+            text: text.to_string(),
+            color: Some(color),
+        }
+    }
+}
+
+/// Aligned table, with column widths computed from its contents.
+#[derive(Debug, Clone)]
+pub struct Table {
+    header: Vec<&'static str>,
+    rows: Vec<Vec<Cell>>,
+}
+
+/// Whether stdout should be colored, see [`output::supports_color`].
+pub fn stdout_supports_color() -> bool {
+    output::supports_color(&io::stdout())
+}
+
+impl Table {
+    pub fn new(header: Vec<&'static str>) -> Self {
+        Self {
+            header,
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, row: Vec<Cell>) {
+        debug_assert_eq!(row.len(), self.header.len());
+        self.rows.push(row);
+    }
+
+    /// Rows as JSON objects, keyed by column header.
+    pub fn to_json(&self) -> Value {
+        self.rows
+            .iter()
+            .map(|row| {
+                self.header
+                    .iter()
+                    .zip(row)
+                    .map(|(header, cell)| (header.to_string(), Value::from(cell.text.clone())))
+                    .collect::<Map<_, _>>()
+            })
+            .collect()
+    }
+
+    /// Render table, one line per row, columns separated by two spaces.
+    pub fn render(&self, color: bool) -> String {
+        let mut widths = self.header.iter().map(|v| v.len()).collect::<Vec<_>>();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.text.chars().count());
+            }
+        }
+
+        let mut output = String::new();
+        let mut line = |cells: Vec<(String, Option<AnsiColors>, bool)>| {
+            let last = cells.len() - 1;
+            for (index, ((text, cell_color, bold), width)) in
+                cells.into_iter().zip(&widths).enumerate()
+            {
+                // Last column isn't padded, to avoid trailing whitespace.
+                let text = if index == last {
+                    text
+                } else {
+                    format!("{text:width$}  ")
+                };
+
+                match (color, cell_color, bold) {
+                    (true, _, true) => write!(output, "{}", text.bold()),
+                    (true, Some(cell_color), _) => write!(output, "{}", text.color(cell_color)),
+                    _ => output.write_str(&text),
+                }
+                .unwrap();
+            }
+            output.push('\n');
+        };
+
+        line(
+            self.header
+                .iter()
+                .map(|v| (v.to_uppercase(), None, true))
+                .collect(),
+        );
+        for row in &self.rows {
+            line(
+                row.iter()
+                    .map(|cell| (cell.text.clone(), cell.color, false))
+                    .collect(),
+            );
+        }
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn columns_are_aligned_to_widest_cell() {
+        let mut table = Table::new(vec!["name", "status"]);
+        table.push(vec![Cell::new("alpha_10"), Cell::new("running")]);
+        table.push(vec![
+            Cell::new("beta"),
+            Cell::colored("exited", AnsiColors::Red),
+        ]);
+
+        assert_eq!(
+            table.render(false),
+            "NAME      STATUS\nalpha_10  running\nbeta      exited\n"
+        );
+        assert!(table.render(true).contains('\x1b'));
+    }
+}
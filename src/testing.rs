@@ -0,0 +1,113 @@
+//! Helpers, shared by tests of multiple modules.
+
+use std::{
+    net::SocketAddr,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    config::{ConfigError, NetworkConfig, ResolveOptions, ResolvedNetworkConfig},
+    validator::KeyStore,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+/// Resolve network from TOML `source`, without generating keys.
+pub fn resolve(source: &str) -> Result<ResolvedNetworkConfig, Vec<ConfigError>> {
+    toml::from_str::<NetworkConfig>(source)
+        .expect("config should parse")
+        .resolve(ResolveOptions::new(
+            Path::new("network.toml"),
+            KeyStore::Skip,
+        ))
+}
+
+/// HTTP server, answering requests with canned `(status, body)` responses in
+/// order. Once responses run out, the last one is repeated.
+pub struct MockServer {
+    addr: SocketAddr,
+
+    /// Head of every answered request, i.e. request line and headers.
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockServer {
+    pub async fn start(responses: Vec<(u16, &'static str)>) -> Self {
+        assert!(!responses.is_empty(), "server needs at least one response");
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let log = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+
+                // Request is read until the end of headers, and body is
+                // drained, but not logged.
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                let head_len = loop {
+                    if let Some(index) = request.windows(4).position(|v| v == b"\r\n\r\n") {
+                        break index + 4;
+                    }
+                    match stream.read(&mut buffer).await {
+                        Ok(0) | Err(_) => break request.len(),
+                        Ok(n) => request.extend_from_slice(&buffer[..n]),
+                    }
+                };
+                let mut received = request.len() - head_len;
+                request.truncate(head_len);
+                let head = String::from_utf8_lossy(&request).into_owned();
+
+                let body_len = head
+                    .lines()
+                    .filter_map(|line| line.split_once(':'))
+                    .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                    .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+                    .unwrap_or(0);
+                while received < body_len {
+                    match stream.read(&mut buffer).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => received += n,
+                    }
+                }
+
+                let index = {
+                    let mut log = log.lock().unwrap();
+                    log.push(head);
+                    log.len() - 1
+                };
+                let (status, body) = responses[index.min(responses.len() - 1)];
+                let response = format!(
+                    "HTTP/1.1 {status} Mock\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+
+        Self { addr, requests }
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{path}", self.addr)
+    }
+
+    /// Number of requests, answered so far.
+    pub fn requests(&self) -> usize {
+        self.requests.lock().unwrap().len()
+    }
+
+    /// Head of request `index`, lowercased, so that headers are matched
+    /// regardless of case.
+    pub fn request(&self, index: usize) -> String {
+        self.requests.lock().unwrap()[index].to_lowercase()
+    }
+}
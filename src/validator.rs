@@ -1,5 +1,13 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
 use leansig::signature::SignatureScheme;
-use rand::rng;
+use rand::{RngCore, SeedableRng, rng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{client::ConfigFile, config::ResolvedNetworkConfig};
 
 type LeanSigScheme = leansig::signature::generalized_xmss::instantiations_poseidon_top_level::lifetime_2_to_the_32::hashing_optimized::SIGTopLevelTargetSumLifetime32Dim64Base8;
 
@@ -8,8 +16,503 @@ pub type PrivateKey = <LeanSigScheme as SignatureScheme>::SecretKey;
 pub type PublicKey = <LeanSigScheme as SignatureScheme>::PublicKey;
 
 pub fn generate_keypair(
+    rng: &mut impl RngCore,
+    activation_epoch: usize,
+    num_active_epochs: usize,
+) -> (PublicKey, PrivateKey) {
+    LeanSigScheme::key_gen(rng, activation_epoch, num_active_epochs)
+}
+
+/// Same as [`generate_keypair`], but uses thread-local random generator.
+pub fn generate_random_keypair(
     activation_epoch: usize,
     num_active_epochs: usize,
 ) -> (PublicKey, PrivateKey) {
-    LeanSigScheme::key_gen(&mut rng(), activation_epoch, num_active_epochs)
+    generate_keypair(&mut rng(), activation_epoch, num_active_epochs)
+}
+
+/// Same as [`generate_keypair`], but deterministic: keypair of validator
+/// `index` is always the same for the same `seed`.
+pub fn generate_seeded_keypair(
+    seed: u64,
+    index: usize,
+    activation_epoch: usize,
+    num_active_epochs: usize,
+) -> (PublicKey, PrivateKey) {
+    // Sub-seed of each validator is base seed and index side by side, so
+    // different validators never share one.
+    let mut sub_seed = <StdRng as SeedableRng>::Seed::default();
+    sub_seed[..8].copy_from_slice(&seed.to_le_bytes());
+    sub_seed[8..16].copy_from_slice(&(index as u64).to_le_bytes());
+
+    generate_keypair(
+        &mut StdRng::from_seed(sub_seed),
+        activation_epoch,
+        num_active_epochs,
+    )
+}
+
+/// Serialized validator keypair, as stored on disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyFile {
+    pub index: usize,
+
+    #[serde(with = "hex::serde")]
+    pub public_key: Vec<u8>,
+
+    #[serde(with = "hex::serde")]
+    pub private_key: Vec<u8>,
+}
+
+impl KeyFile {
+    pub fn read(path: &Path) -> io::Result<Self> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+}
+
+/// Path of key file of validator `index`, relative to key directory.
+pub fn key_path(node: &str, index: usize) -> PathBuf {
+    Path::new(node).join(format!("validator_{index}.json"))
+}
+
+/// Index of validator, which key file is at `path`, see [`key_path`].
+fn key_index(path: &Path) -> Option<usize> {
+    path.file_name()?
+        .to_str()?
+        .strip_prefix("validator_")?
+        .strip_suffix(".json")?
+        .parse()
+        .ok()
+}
+
+/// File with JWT secret of a node, relative to directory with its keys.
+pub const JWT_FILE: &str = "jwt.hex";
+
+/// Index of all key files in key directory, written as `manifest.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyManifest {
+    pub network: String,
+    pub nodes: Vec<ManifestNode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestNode {
+    pub name: String,
+
+    /// Key files of node validators, relative to key directory.
+    pub validators: Vec<PathBuf>,
+}
+
+impl KeyManifest {
+    pub const FILE_NAME: &str = "manifest.json";
+
+    pub fn new(network: &ResolvedNetworkConfig) -> Self {
+        Self {
+            network: network.name().to_owned(),
+            nodes: network
+                .nodes()
+                .iter()
+                .map(|(name, node)| ManifestNode {
+                    name: name.clone(),
+                    validators: node
+                        .validators
+                        .iter()
+                        .map(|index| key_path(name, *index))
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Manifest, previously written into `dir`, if there is one.
+    pub fn read(dir: &Path) -> io::Result<Option<Self>> {
+        match fs::read(dir.join(Self::FILE_NAME)) {
+            Ok(contents) => Ok(Some(serde_json::from_slice(&contents)?)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Keep nodes of `previous` manifest, which this one doesn't list, e.g.
+    /// replicas, added by earlier `scale` runs. Nodes of both manifests are
+    /// taken from this one.
+    pub fn merge(mut self, previous: Self) -> Self {
+        let mut nodes = previous.nodes;
+
+        for node in self.nodes {
+            match nodes.iter_mut().find(|prev| prev.name == node.name) {
+                Some(prev) => *prev = node,
+                None => nodes.push(node),
+            }
+        }
+
+        self.nodes = nodes;
+        self
+    }
+
+    /// Global index after the highest one, which has a key file.
+    pub fn next_validator_index(&self) -> usize {
+        self.nodes
+            .iter()
+            .flat_map(|node| &node.validators)
+            .filter_map(|path| key_index(path))
+            .map(|index| index + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn write(&self, dir: &Path) -> io::Result<()> {
+        let contents = serde_json::to_vec_pretty(self)?;
+        fs::write(dir.join(Self::FILE_NAME), contents)
+    }
+}
+
+/// Create `dir` and its missing parents, accessible only to the current
+/// user, as keys are kept at predictable path in shared temporary directory.
+///
+/// Directory, which already exists, is refused if it belongs to another
+/// user, who could read or replace keys in it.
+pub fn create_private_dir(dir: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)?;
+
+        let metadata = fs::metadata(dir)?;
+        if metadata.uid() != nix::unistd::geteuid().as_raw() {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("{dir:?} belongs to another user"),
+            ));
+        }
+
+        // Directory may be left by earlier version, with default mode.
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))
+    }
+
+    #[cfg(not(unix))]
+    fs::create_dir_all(dir)
+}
+
+/// Write `contents` to file at `path`, readable only by the current user.
+fn write_private(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+        options.mode(0o600);
+        let file = options.open(path)?;
+        // Mode only applies to new files.
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        io::Write::write_all(&mut &file, contents.as_ref())
+    }
+
+    #[cfg(not(unix))]
+    io::Write::write_all(&mut options.open(path)?, contents.as_ref())
+}
+
+/// Where to keep generated validator keys.
+#[derive(Debug, Clone)]
+pub enum KeyStore {
+    /// Keep all keys in memory.
+    Memory,
+
+    /// Write keys to `<dir>/<node>/validator_<index>.json` as soon as they are
+    /// generated, keeping only file paths in memory. This bounds memory usage
+    /// for large validator sets.
+    Disk(PathBuf),
+
+    /// Don't generate keys at all, for commands which only need network
+    /// layout (node names, containers), since generation is slow.
+    Skip,
+
+    /// Don't generate keys, but lay out nodes as if keys were written to
+    /// `<dir>`, to show accurate plan without touching the disk.
+    Planned(PathBuf),
+}
+
+impl KeyStore {
+    pub fn generates_keys(&self) -> bool {
+        !matches!(self, Self::Skip | Self::Planned(_))
+    }
+
+    /// Directory with key files of `node`, `None` unless keys are on disk.
+    pub fn node_dir(&self, node: &str) -> Option<PathBuf> {
+        match self {
+            Self::Disk(dir) | Self::Planned(dir) => Some(dir.join(node)),
+            Self::Memory | Self::Skip => None,
+        }
+    }
+
+    /// Store keypair of validator `index`, owned by `node`.
+    ///
+    /// On failure, returns path which couldn't be written.
+    pub fn store(
+        &self,
+        node: &str,
+        index: usize,
+        private_key: Vec<u8>,
+        public_key: Vec<u8>,
+    ) -> Result<StoredKeypair, (PathBuf, io::Error)> {
+        let Self::Disk(dir) = self else {
+            // Memory, or empty placeholder keys of `Skip` and `Planned`.
+            return Ok(StoredKeypair::Memory {
+                private_key,
+                public_key,
+            });
+        };
+
+        let path = dir.join(key_path(node, index));
+        if let Some(dir) = path.parent() {
+            create_private_dir(dir).map_err(|err| (dir.to_owned(), err))?;
+        }
+
+        let file = KeyFile {
+            index,
+            public_key,
+            private_key,
+        };
+
+        serde_json::to_vec_pretty(&file)
+            .map_err(io::Error::from)
+            .and_then(|contents| write_private(&path, contents))
+            .map_err(|err| (path.clone(), err))?;
+
+        Ok(StoredKeypair::Disk(path))
+    }
+
+    /// Store JWT secret of `node` next to its keys, at [`JWT_FILE`], so that
+    /// it can be passed to the client as file. Nothing is written, unless
+    /// keys are on disk.
+    ///
+    /// On failure, returns path which couldn't be written.
+    pub fn store_jwt(&self, node: &str, jwt: &str) -> Result<(), (PathBuf, io::Error)> {
+        let Self::Disk(dir) = self else {
+            return Ok(());
+        };
+
+        let dir = dir.join(node);
+        create_private_dir(&dir).map_err(|err| (dir.clone(), err))?;
+
+        let path = dir.join(JWT_FILE);
+        write_private(&path, jwt).map_err(|err| (path, err))
+    }
+
+    /// Store config `file` of `node`, next to its keys.
+    pub fn store_config_file(
+        &self,
+        node: &str,
+        file: &ConfigFile,
+    ) -> Result<(), (PathBuf, io::Error)> {
+        let Self::Disk(dir) = self else {
+            return Ok(());
+        };
+
+        let dir = dir.join(node);
+        create_private_dir(&dir).map_err(|err| (dir.clone(), err))?;
+
+        let path = dir.join(file.file_name);
+        write_private(&path, &file.contents).map_err(|err| (path, err))
+    }
+}
+
+/// Validator keypair, either held in memory or stored on disk.
+#[derive(Debug, Clone)]
+pub enum StoredKeypair {
+    Memory {
+        private_key: Vec<u8>,
+        public_key: Vec<u8>,
+    },
+    Disk(PathBuf),
+}
+
+impl StoredKeypair {
+    pub fn public_key(&self) -> io::Result<Vec<u8>> {
+        match self {
+            Self::Memory { public_key, .. } => Ok(public_key.clone()),
+            Self::Disk(path) => Ok(KeyFile::read(path)?.public_key),
+        }
+    }
+
+    pub fn private_key(&self) -> io::Result<Vec<u8>> {
+        match self {
+            Self::Memory { private_key, .. } => Ok(private_key.clone()),
+            Self::Disk(path) => Ok(KeyFile::read(path)?.private_key),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use leansig::serialization::Serializable;
+
+    use super::*;
+    use crate::config::{NetworkConfig, ResolveOptions};
+
+    #[test]
+    fn same_rng_seed_gives_same_keypair() {
+        let (first_public, first_private) = generate_keypair(&mut StdRng::seed_from_u64(7), 0, 4);
+        let (second_public, second_private) = generate_keypair(&mut StdRng::seed_from_u64(7), 0, 4);
+
+        assert_eq!(first_public.to_bytes(), second_public.to_bytes());
+        assert_eq!(first_private.to_bytes(), second_private.to_bytes());
+    }
+
+    #[test]
+    fn streamed_keys_are_laid_out_per_node() {
+        let dir = env::temp_dir().join(format!("liesas-keys-{}", process::id()));
+        let config: NetworkConfig = toml::from_str(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            name = "ream"
+            client = "ream"
+            count = 2
+            validator_count = 250
+            num_active_epochs = 4
+            "#,
+        )
+        .unwrap();
+
+        let network = config
+            .resolve(ResolveOptions::new(
+                Path::new("network.toml"),
+                KeyStore::Disk(dir.clone()),
+            ))
+            .unwrap();
+        assert_eq!(network.validators().len(), 500);
+
+        for (name, node) in network.nodes() {
+            for &index in &node.validators {
+                let StoredKeypair::Disk(path) = &network.validators()[index].keys else {
+                    panic!("key of validator {index} is kept in memory");
+                };
+
+                assert_eq!(
+                    *path,
+                    dir.join(name).join(format!("validator_{index}.json"))
+                );
+                assert_eq!(KeyFile::read(path).unwrap().index, index);
+            }
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn resolved_keys_are_distinct_and_round_trip() {
+        let config: NetworkConfig = toml::from_str(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            client = "ream"
+            validator_count = 2
+            num_active_epochs = 4
+            "#,
+        )
+        .unwrap();
+        let network = config
+            .resolve(ResolveOptions::new(
+                Path::new("network.toml"),
+                KeyStore::Memory,
+            ))
+            .unwrap();
+
+        let keys = network
+            .validators()
+            .iter()
+            .map(|validator| {
+                let public_key = validator.keys.public_key().unwrap();
+                let private_key = validator.keys.private_key().unwrap();
+                assert!(!public_key.is_empty());
+
+                let decoded = PublicKey::from_bytes(&public_key).unwrap();
+                assert_eq!(decoded.to_bytes(), public_key);
+                let decoded = PrivateKey::from_bytes(&private_key).unwrap();
+                assert_eq!(decoded.to_bytes(), private_key);
+
+                public_key
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(keys.len(), 2);
+        assert_ne!(keys[0], keys[1]);
+    }
+
+    #[test]
+    fn merged_manifest_keeps_earlier_replicas() {
+        let node = |name: &str, index| ManifestNode {
+            name: name.to_owned(),
+            validators: vec![key_path(name, index)],
+        };
+        let previous = KeyManifest {
+            network: "devnet".to_owned(),
+            nodes: vec![node("ream_0", 0), node("ream_2", 2), node("ream_3", 3)],
+        };
+        let current = KeyManifest {
+            network: "devnet".to_owned(),
+            nodes: vec![node("ream_0", 0), node("ream_1", 1), node("ream_4", 4)],
+        };
+
+        let merged = current.merge(previous);
+        let names = merged
+            .nodes
+            .iter()
+            .map(|node| node.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["ream_0", "ream_2", "ream_3", "ream_1", "ream_4"]);
+        assert_eq!(merged.next_validator_index(), 5);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keys_are_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = env::temp_dir().join(format!("liesas-private-keys-{}", process::id()));
+        let store = KeyStore::Disk(dir.clone());
+        store.store("alpha", 0, vec![1], vec![2]).unwrap();
+        store.store_jwt("alpha", "secret").unwrap();
+
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&dir), 0o700);
+        assert_eq!(mode(&dir.join("alpha")), 0o700);
+        assert_eq!(mode(&dir.join(key_path("alpha", 0))), 0o600);
+        assert_eq!(mode(&dir.join("alpha").join(JWT_FILE)), 0o600);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn directory_of_another_user_is_refused() {
+        use nix::unistd::{Uid, chown, geteuid};
+
+        let dir = env::temp_dir().join(format!("liesas-foreign-keys-{}", process::id()));
+        // Root can't be another user, so root gives the directory away.
+        let dir = if geteuid().is_root() {
+            fs::create_dir_all(&dir).unwrap();
+            chown(&dir, Some(Uid::from_raw(65534)), None).unwrap();
+            dir
+        } else {
+            PathBuf::from("/")
+        };
+
+        let error = create_private_dir(&dir).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::PermissionDenied);
+
+        if dir != Path::new("/") {
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
 }
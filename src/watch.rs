@@ -0,0 +1,232 @@
+use std::{collections::HashMap, time::Duration};
+
+use bollard::Docker;
+
+use crate::{
+    auth::Registries,
+    config::{NetworkPlan, NodePlan},
+    docker::{DockerError, PullPolicy, RetryPolicy, pull_image},
+};
+
+/// Delay between consecutive checks for updated images.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Tracks digests of images, used by running nodes, to find out which nodes
+/// need restart after their image was updated.
+#[derive(Debug, Clone, Default)]
+pub struct ImageWatcher {
+    /// Image reference to local image id.
+    digests: HashMap<String, Option<String>>,
+}
+
+async fn local_digest(docker: &Docker, image: &str) -> Option<String> {
+    docker
+        .inspect_image(image)
+        .await
+        .ok()
+        .and_then(|image| image.id)
+}
+
+impl ImageWatcher {
+    /// Start watching `images`, remembering their current digests.
+    pub async fn new(docker: &Docker, images: impl IntoIterator<Item = String>) -> Self {
+        let mut watcher = Self::default();
+        for image in images {
+            let digest = local_digest(docker, &image).await;
+            watcher.digests.insert(image, digest);
+        }
+
+        watcher
+    }
+
+    /// Remember `current` digest of `image`, returning whether it changed.
+    fn update(&mut self, image: &str, current: Option<String>) -> bool {
+        match self.digests.get_mut(image) {
+            Some(digest) if *digest != current => {
+                *digest = current;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Pull every watched image again, and return ones whose digest changed.
+    ///
+    /// With [`PullPolicy::Never`] nothing is pulled, and only images rebuilt
+    /// or re-tagged locally are picked up.
+    pub async fn poll(
+        &mut self,
+        docker: &Docker,
+        retry: RetryPolicy,
+        pull: PullPolicy,
+        registries: &Registries,
+    ) -> Result<Vec<String>, DockerError> {
+        let mut images = self.digests.keys().cloned().collect::<Vec<_>>();
+        images.sort();
+
+        let mut changed = Vec::new();
+        for image in images {
+            if pull != PullPolicy::Never {
+                pull_image(docker, retry, &image, registries, &|_| {}).await?;
+            }
+
+            let current = local_digest(docker, &image).await;
+            if self.update(&image, current) {
+                changed.push(image);
+            }
+        }
+
+        Ok(changed)
+    }
+}
+
+/// Container nodes of `plan`, running one of `images`.
+pub fn affected_nodes<'a>(plan: &'a NetworkPlan, images: &[String]) -> Vec<&'a NodePlan> {
+    plan.nodes
+        .iter()
+        .filter(|node| {
+            node.container
+                .as_ref()
+                .is_some_and(|spec| images.contains(&spec.image))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use bollard::API_DEFAULT_VERSION;
+
+    use super::*;
+    use crate::{
+        config::{NetworkConfig, ResolveOptions, plan},
+        events::EventSink,
+        launch::Launcher,
+        testing::MockServer,
+        validator::KeyStore,
+    };
+
+    #[test]
+    fn changed_digest_restarts_only_its_node() {
+        let config: NetworkConfig = toml::from_str(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            name = "alpha"
+            client = { kind = "ream", image = "ream:dev" }
+
+            [[node]]
+            name = "beta"
+            client = { kind = "ream", image = "ream:stable" }
+            "#,
+        )
+        .unwrap();
+        let plan = plan(
+            config,
+            ResolveOptions::new(Path::new("network.toml"), KeyStore::Skip),
+        )
+        .unwrap();
+
+        let mut watcher = ImageWatcher {
+            digests: HashMap::from([
+                ("ream:dev".to_owned(), Some("sha256:old".to_owned())),
+                ("ream:stable".to_owned(), Some("sha256:stable".to_owned())),
+            ]),
+        };
+
+        let changed = [("ream:dev", "sha256:new"), ("ream:stable", "sha256:stable")]
+            .into_iter()
+            .filter(|(image, digest)| watcher.update(image, Some(digest.to_string())))
+            .map(|(image, _)| image.to_owned())
+            .collect::<Vec<_>>();
+
+        let restarted = affected_nodes(&plan, &changed)
+            .into_iter()
+            .map(|node| &node.name)
+            .collect::<Vec<_>>();
+        assert_eq!(restarted, ["alpha"]);
+
+        // Same digest is not reported twice.
+        assert!(!watcher.update("ream:dev", Some("sha256:new".to_owned())));
+    }
+
+    #[tokio::test]
+    async fn updated_image_relaunches_its_node_once() {
+        let config: NetworkConfig = toml::from_str(
+            r#"
+            name = "devnet"
+
+            [[node]]
+            name = "alpha"
+            client = { kind = "ream", image = "ream:dev" }
+
+            [[node]]
+            name = "beta"
+            client = { kind = "ream", image = "ream:stable" }
+            "#,
+        )
+        .unwrap();
+        let plan = plan(
+            config,
+            ResolveOptions::new(Path::new("network.toml"), KeyStore::Skip),
+        )
+        .unwrap();
+
+        let server = MockServer::start(vec![
+            // Digests, remembered when watching starts.
+            (200, r#"{"Id":"sha256:old"}"#),
+            (200, r#"{"Id":"sha256:stable"}"#),
+            // Digests after `ream:dev` was rebuilt locally.
+            (200, r#"{"Id":"sha256:new"}"#),
+            (200, r#"{"Id":"sha256:stable"}"#),
+            // Relaunch of `alpha`: stop, remove, create and start.
+            (204, ""),
+            (204, ""),
+            (201, r#"{"Id":"bbbb","Warnings":[]}"#),
+            (204, ""),
+        ])
+        .await;
+        let docker = Docker::connect_with_http(&server.url(""), 5, API_DEFAULT_VERSION).unwrap();
+
+        let mut watcher =
+            ImageWatcher::new(&docker, ["ream:dev".to_owned(), "ream:stable".to_owned()]).await;
+        let changed = watcher
+            .poll(
+                &docker,
+                RetryPolicy::no_retries(),
+                PullPolicy::Never,
+                plan.network.registries(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(changed, ["ream:dev"]);
+
+        let mut launcher = Launcher::new(&docker, RetryPolicy::no_retries(), "devnet");
+        for node in affected_nodes(&plan, &changed) {
+            launcher
+                .relaunch(node, &mut EventSink::disabled())
+                .await
+                .unwrap();
+        }
+
+        let requests = (0..server.requests())
+            .map(|index| server.request(index).lines().next().unwrap().to_owned())
+            .collect::<Vec<_>>();
+        // `--pull never` is respected.
+        assert!(!requests.iter().any(|line| line.contains("/images/create")));
+        let created = requests
+            .iter()
+            .filter(|line| line.starts_with("post ") && line.contains("/containers/create"))
+            .collect::<Vec<_>>();
+        assert_eq!(created.len(), 1);
+        assert!(created[0].contains("name=devnet-alpha"));
+        let started = requests
+            .iter()
+            .filter(|line| line.starts_with("post ") && line.ends_with("/start http/1.1"))
+            .collect::<Vec<_>>();
+        assert_eq!(started.len(), 1);
+        assert!(started[0].contains("/containers/devnet-alpha/start"));
+    }
+}
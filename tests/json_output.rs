@@ -0,0 +1,85 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use serde_json::Value;
+
+/// Write config `source` into a fresh directory of `test`.
+fn write_config(test: &str, source: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("liesas-{test}-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let path = dir.join("network.toml");
+    fs::write(&path, source).unwrap();
+    path
+}
+
+fn run(args: &[&str], config: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_liesas-quickstart"))
+        .args(args)
+        .arg("--config")
+        .arg(config)
+        .output()
+        .unwrap()
+}
+
+/// Parse stdout of the command as stream of JSON documents, expecting exactly
+/// one.
+fn single_document(output: &Output) -> Value {
+    let stdout = String::from_utf8(output.stdout.clone()).unwrap();
+    let documents = serde_json::Deserializer::from_str(&stdout)
+        .into_iter::<Value>()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(documents.len(), 1, "{stdout}");
+    assert_eq!(stdout.lines().count(), 1, "{stdout}");
+
+    documents.into_iter().next().unwrap()
+}
+
+#[test]
+fn success_is_single_document() {
+    let config = write_config(
+        "json-success",
+        r#"
+        name = "devnet"
+
+        [[node]]
+        client = "ream"
+        count = 2
+        "#,
+    );
+
+    let output = run(&["validate", "--json"], &config);
+    let document = single_document(&output);
+
+    assert!(output.status.success());
+    assert_eq!(document["status"], "ok");
+    assert_eq!(document["network"], "devnet");
+    assert_eq!(document["nodes"], 2);
+    fs::remove_dir_all(config.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn failure_is_single_document() {
+    let config = write_config(
+        "json-failure",
+        r#"
+        name = "devnet"
+
+        [[node]]
+        client = "ream"
+        stop_timeout = "2h"
+        "#,
+    );
+
+    let output = run(&["validate", "--json"], &config);
+    let document = single_document(&output);
+
+    assert_eq!(output.status.code(), Some(78));
+    assert_eq!(document["status"], "error");
+    assert_eq!(document["class"], "config_semantic");
+    fs::remove_dir_all(config.parent().unwrap()).unwrap();
+}